@@ -3,16 +3,12 @@
 #[cfg(unix)]
 #[tokio::main]
 async fn main() {
-    use tokio::net::UnixListener;
-    use tokio_stream::wrappers::UnixListenerStream;
+    use warp::Filter;
 
     pretty_env_logger::init();
 
-    let listener = UnixListener::bind("/tmp/warp.sock").unwrap();
-    let incoming = UnixListenerStream::new(listener);
-    warp::serve(warp::fs::dir("examples/dir"))
-        .run_incoming(incoming)
-        .await;
+    let routes = warp::fs::dir("examples/dir").with(warp::log("unix_socket"));
+    warp::serve(routes).bind_unix("/tmp/warp.sock").await;
 }
 
 #[cfg(not(unix))]
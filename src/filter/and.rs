@@ -8,6 +8,7 @@ use pin_project::pin_project;
 use super::{Combine, Filter, FilterBase, Internal, Tuple};
 use crate::generic::CombinedTuples;
 use crate::reject::CombineRejection;
+use crate::routes::RouteTable;
 
 #[derive(Clone, Copy, Debug)]
 pub struct And<T, U> {
@@ -33,6 +34,12 @@ where
             state: State::First(self.first.filter(Internal), self.second.clone()),
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.first
+            .describe(Internal)
+            .and(self.second.describe(Internal))
+    }
 }
 
 #[allow(missing_debug_implementations)]
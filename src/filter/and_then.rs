@@ -7,6 +7,7 @@ use pin_project::pin_project;
 
 use super::{Filter, FilterBase, Func, Internal};
 use crate::reject::CombineRejection;
+use crate::routes::RouteTable;
 
 #[derive(Clone, Copy, Debug)]
 pub struct AndThen<T, F> {
@@ -30,6 +31,10 @@ where
             state: State::First(self.filter.filter(Internal), self.callback.clone()),
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
 
 #[allow(missing_debug_implementations)]
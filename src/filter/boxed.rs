@@ -7,6 +7,7 @@ use futures::TryFutureExt;
 
 use super::{Filter, FilterBase, Internal, Tuple};
 use crate::reject::Rejection;
+use crate::routes::RouteTable;
 
 /// A type representing a boxed `Filter` trait object.
 ///
@@ -79,6 +80,10 @@ impl<T: Tuple + Send> FilterBase for BoxedFilter<T> {
     fn filter(&self, _: Internal) -> Self::Future {
         self.filter.filter(Internal)
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
 
 struct BoxingFilter<F> {
@@ -97,4 +102,8 @@ where
     fn filter(&self, _: Internal) -> Self::Future {
         Box::pin(self.filter.filter(Internal).into_future())
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
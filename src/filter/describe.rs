@@ -0,0 +1,41 @@
+use super::{Filter, FilterBase, Internal};
+use crate::routes::{RouteInfo, RouteTable};
+
+// Attaches route information to a filter that can't describe itself, such as
+// a `FilterFn`.
+//
+// The description is a function instead of a `RouteInfo`, so that describing
+// a zero-sized filter keeps it zero-sized.
+pub(crate) fn describe<F, D>(filter: F, describe: D) -> Described<F, D>
+where
+    F: Filter,
+    D: Fn(&mut RouteInfo),
+{
+    Described { filter, describe }
+}
+
+#[derive(Clone, Copy)]
+#[allow(missing_debug_implementations)]
+pub(crate) struct Described<F, D> {
+    filter: F,
+    describe: D,
+}
+
+impl<F, D> FilterBase for Described<F, D>
+where
+    F: Filter,
+    D: Fn(&mut RouteInfo),
+{
+    type Extract = F::Extract;
+    type Error = F::Error;
+    type Future = F::Future;
+
+    #[inline]
+    fn filter(&self, _: Internal) -> Self::Future {
+        self.filter.filter(Internal)
+    }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal).map(&self.describe)
+    }
+}
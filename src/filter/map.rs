@@ -6,6 +6,7 @@ use futures::{ready, TryFuture};
 use pin_project::pin_project;
 
 use super::{Filter, FilterBase, Func, Internal};
+use crate::routes::RouteTable;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Map<T, F> {
@@ -28,6 +29,10 @@ where
             callback: self.callback.clone(),
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
 
 #[allow(missing_debug_implementations)]
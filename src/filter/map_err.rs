@@ -7,6 +7,7 @@ use pin_project::pin_project;
 
 use super::{Filter, FilterBase, Internal};
 use crate::reject::IsReject;
+use crate::routes::RouteTable;
 
 #[derive(Clone, Copy, Debug)]
 pub struct MapErr<T, F> {
@@ -30,6 +31,10 @@ where
             callback: self.callback.clone(),
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
 
 #[allow(missing_debug_implementations)]
@@ -1,8 +1,10 @@
 mod and;
 mod and_then;
 mod boxed;
+mod describe;
 mod map;
 mod map_err;
+mod named;
 mod or;
 mod or_else;
 mod recover;
@@ -18,12 +20,15 @@ use futures::{future, TryFuture, TryFutureExt};
 pub(crate) use crate::generic::{one, Combine, Either, Func, One, Tuple};
 use crate::reject::{CombineRejection, IsReject, Rejection};
 use crate::route::{self, Route};
+use crate::routes::RouteTable;
 
 pub(crate) use self::and::And;
 use self::and_then::AndThen;
 pub use self::boxed::BoxedFilter;
+pub(crate) use self::describe::describe;
 pub(crate) use self::map::Map;
 pub(crate) use self::map_err::MapErr;
+use self::named::Named;
 pub(crate) use self::or::Or;
 use self::or_else::OrElse;
 use self::recover::Recover;
@@ -41,6 +46,12 @@ pub trait FilterBase {
 
     fn filter(&self, internal: Internal) -> Self::Future;
 
+    // Describes the routes this filter matches. Filters that don't affect
+    // routing, or don't know, describe a single route matching anything.
+    fn describe(&self, _internal: Internal) -> RouteTable {
+        RouteTable::any()
+    }
+
     fn map_err<F, E>(self, _internal: Internal, fun: F) -> MapErr<Self, F>
     where
         Self: Sized,
@@ -397,6 +408,52 @@ pub trait Filter: FilterBase {
     {
         BoxedFilter::new(self)
     }
+
+    /// Gives this `Filter` a name, which shows up in its [`routes`](Filter::routes).
+    ///
+    /// This doesn't change how requests are filtered in any way.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use warp::Filter;
+    ///
+    /// let route = warp::path!("users" / u32)
+    ///     .map(|id| format!("user #{}", id))
+    ///     .named("get_user");
+    ///
+    /// assert_eq!(route.routes().get("get_user").unwrap().path(), "/users/{u32}");
+    /// ```
+    fn named(self, name: &'static str) -> Named<Self>
+    where
+        Self: Sized,
+    {
+        Named { filter: self, name }
+    }
+
+    /// Lists the routes this `Filter` matches.
+    ///
+    /// The built-in path and method filters describe what they match, and
+    /// combining filters combines their routes, so this can be used to log
+    /// every route at startup, for instance. Custom filters that don't
+    /// describe themselves are treated as matching anything.
+    ///
+    /// See the [`routes`](crate::routes) module for more.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use warp::Filter;
+    ///
+    /// let hello = warp::get().and(warp::path!("hello" / String)).map(|name| name);
+    /// let bye = warp::post().and(warp::path!("bye")).map(|| "bye".to_string());
+    ///
+    /// let routes = hello.or(bye).routes();
+    /// assert_eq!(routes.to_string(), "GET     /hello/{String}\nPOST    /bye\n");
+    /// ```
+    fn routes(&self) -> RouteTable {
+        self.describe(Internal)
+    }
 }
 
 impl<T: FilterBase> Filter for T {}
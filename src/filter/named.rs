@@ -0,0 +1,29 @@
+use super::{Filter, FilterBase, Internal};
+use crate::routes::RouteTable;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Named<T> {
+    pub(super) filter: T,
+    pub(super) name: &'static str,
+}
+
+impl<T> FilterBase for Named<T>
+where
+    T: Filter,
+{
+    type Extract = T::Extract;
+    type Error = T::Error;
+    type Future = T::Future;
+
+    #[inline]
+    fn filter(&self, _: Internal) -> Self::Future {
+        self.filter.filter(Internal)
+    }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        let name = self.name;
+        self.filter
+            .describe(Internal)
+            .map(|route| route.set_name(name))
+    }
+}
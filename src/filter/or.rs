@@ -8,7 +8,8 @@ use pin_project::pin_project;
 use super::{Filter, FilterBase, Internal};
 use crate::generic::Either;
 use crate::reject::CombineRejection;
-use crate::route;
+use crate::route::{self, Route};
+use crate::routes::RouteTable;
 
 type Combined<E1, E2> = <E1 as CombineRejection<E2>>::Combined;
 
@@ -30,12 +31,18 @@ where
     type Future = EitherFuture<T, U>;
 
     fn filter(&self, _: Internal) -> Self::Future {
-        let idx = route::with(|route| route.matched_path_index());
+        let checkpoint = route::with(|route| Checkpoint::new(route));
         EitherFuture {
             state: State::First(self.first.filter(Internal), self.second.clone()),
-            original_path_index: PathIndex(idx),
+            checkpoint,
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.first
+            .describe(Internal)
+            .or(self.second.describe(Internal))
+    }
 }
 
 #[allow(missing_debug_implementations)]
@@ -43,7 +50,7 @@ where
 pub struct EitherFuture<T: Filter, U: Filter> {
     #[pin]
     state: State<T, U>,
-    original_path_index: PathIndex,
+    checkpoint: Checkpoint,
 }
 
 #[pin_project(project = StateProj)]
@@ -53,12 +60,26 @@ enum State<T: Filter, U: Filter> {
     Done,
 }
 
+// Where the route was before the first filter, to go back to if it rejects.
 #[derive(Copy, Clone)]
-struct PathIndex(usize);
+struct Checkpoint {
+    path_index: usize,
+    hooks: usize,
+}
+
+impl Checkpoint {
+    fn new(route: &Route) -> Checkpoint {
+        Checkpoint {
+            path_index: route.matched_path_index(),
+            hooks: route.response_hooks(),
+        }
+    }
 
-impl PathIndex {
-    fn reset_path(&self) {
-        route::with(|route| route.reset_matched_path_index(self.0));
+    fn reset(&self) {
+        route::with(|route| {
+            route.reset_matched_path_index(self.path_index);
+            route.discard_response_hooks(self.hooks);
+        });
     }
 }
 
@@ -79,7 +100,7 @@ where
                         return Poll::Ready(Ok((Either::A(ex1),)));
                     }
                     Err(e) => {
-                        pin.original_path_index.reset_path();
+                        pin.checkpoint.reset();
                         (e, second.filter(Internal))
                     }
                 },
@@ -87,7 +108,7 @@ where
                     let ex2 = match ready!(second.try_poll(cx)) {
                         Ok(ex2) => Ok((Either::B(ex2),)),
                         Err(e) => {
-                            pin.original_path_index.reset_path();
+                            pin.checkpoint.reset();
                             let err1 = err1.take().expect("polled after complete");
                             Err(e.combine(err1))
                         }
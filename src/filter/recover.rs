@@ -8,7 +8,8 @@ use pin_project::pin_project;
 use super::{Filter, FilterBase, Func, Internal};
 use crate::generic::Either;
 use crate::reject::IsReject;
-use crate::route;
+use crate::route::{self, Route};
+use crate::routes::RouteTable;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Recover<T, F> {
@@ -28,12 +29,16 @@ where
     type Future = RecoverFuture<T, F>;
     #[inline]
     fn filter(&self, _: Internal) -> Self::Future {
-        let idx = route::with(|route| route.matched_path_index());
+        let checkpoint = route::with(|route| Checkpoint::new(route));
         RecoverFuture {
             state: State::First(self.filter.filter(Internal), self.callback.clone()),
-            original_path_index: PathIndex(idx),
+            checkpoint,
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
 
 #[allow(missing_debug_implementations)]
@@ -47,7 +52,7 @@ where
 {
     #[pin]
     state: State<T, F>,
-    original_path_index: PathIndex,
+    checkpoint: Checkpoint,
 }
 
 #[pin_project(project = StateProj)]
@@ -63,12 +68,26 @@ where
     Done,
 }
 
+// Where the route was before the first filter, to go back to if it rejects.
 #[derive(Copy, Clone)]
-struct PathIndex(usize);
+struct Checkpoint {
+    path_index: usize,
+    hooks: usize,
+}
+
+impl Checkpoint {
+    fn new(route: &Route) -> Checkpoint {
+        Checkpoint {
+            path_index: route.matched_path_index(),
+            hooks: route.response_hooks(),
+        }
+    }
 
-impl PathIndex {
-    fn reset_path(&self) {
-        route::with(|route| route.reset_matched_path_index(self.0));
+    fn reset(&self) {
+        route::with(|route| {
+            route.reset_matched_path_index(self.path_index);
+            route.discard_response_hooks(self.hooks);
+        });
     }
 }
 
@@ -106,7 +125,7 @@ where
                 StateProj::Done => panic!("polled after complete"),
             };
 
-            pin.original_path_index.reset_path();
+            pin.checkpoint.reset();
             let fut2 = second.call(err);
             self.set(RecoverFuture {
                 state: State::Second(fut2),
@@ -2,6 +2,7 @@ use std::convert::Infallible;
 use std::future::Future;
 use std::net::SocketAddr;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use futures::future::TryFuture;
@@ -11,6 +12,7 @@ use pin_project::pin_project;
 use crate::reject::IsReject;
 use crate::reply::{Reply, Response};
 use crate::route::{self, Route};
+use crate::routes::RouteTable;
 use crate::{Filter, Request};
 
 /// Convert a `Filter` into a `Service`.
@@ -36,8 +38,9 @@ use crate::{Filter, Request};
 /// let svc = warp::service(route);
 ///
 /// // Typical hyper setup...
-/// let make_svc = hyper::service::make_service_fn(move |_| async move {
-///     Ok::<_, Infallible>(svc)
+/// let make_svc = hyper::service::make_service_fn(move |_| {
+///     let svc = svc.clone();
+///     async move { Ok::<_, Infallible>(svc) }
 /// });
 ///
 /// hyper::Server::bind(&([127, 0, 0, 1], 3030).into())
@@ -55,12 +58,15 @@ where
     <F::Future as TryFuture>::Ok: Reply,
     <F::Future as TryFuture>::Error: IsReject,
 {
-    FilteredService { filter }
+    let routes = Arc::new(filter.describe(super::Internal));
+    FilteredService { filter, routes }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct FilteredService<F> {
     filter: F,
+    // Resolves which `405 Method Not Allowed` rejections apply to the path.
+    routes: Arc<RouteTable>,
 }
 
 impl<F> FilteredService<F>
@@ -79,7 +85,11 @@ where
 
         let route = Route::new(req, remote_addr);
         let fut = route::set(&route, || self.filter.filter(super::Internal));
-        FilteredFuture { future: fut, route }
+        FilteredFuture {
+            future: fut,
+            route,
+            routes: self.routes.clone(),
+        }
     }
 }
 
@@ -109,6 +119,7 @@ pub struct FilteredFuture<F> {
     #[pin]
     future: F,
     route: ::std::cell::RefCell<Route>,
+    routes: Arc<RouteTable>,
 }
 
 impl<F> Future for FilteredFuture<F>
@@ -125,13 +136,17 @@ where
 
         let pin = self.project();
         let fut = pin.future;
-        match route::set(&pin.route, || fut.try_poll(cx)) {
-            Poll::Ready(Ok(ok)) => Poll::Ready(Ok(ok.into_response())),
-            Poll::Pending => Poll::Pending,
+        let mut res = match route::set(&pin.route, || fut.try_poll(cx)) {
+            Poll::Ready(Ok(ok)) => ok.into_response(),
+            Poll::Pending => return Poll::Pending,
             Poll::Ready(Err(err)) => {
                 tracing::debug!("rejected: {:?}", err);
-                Poll::Ready(Ok(err.into_response()))
+                let route = pin.route.borrow();
+                err.route_methods(pin.routes, route.full_path())
+                    .into_response()
             }
-        }
+        };
+        pin.route.borrow_mut().finish_response(&mut res);
+        Poll::Ready(Ok(res))
     }
 }
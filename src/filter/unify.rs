@@ -6,6 +6,7 @@ use futures::{ready, TryFuture};
 use pin_project::pin_project;
 
 use super::{Either, Filter, FilterBase, Internal, Tuple};
+use crate::routes::RouteTable;
 
 #[derive(Clone, Copy, Debug)]
 pub struct Unify<F> {
@@ -26,6 +27,10 @@ where
             inner: self.filter.filter(Internal),
         }
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        self.filter.describe(Internal)
+    }
 }
 
 #[allow(missing_debug_implementations)]
@@ -1,7 +1,10 @@
 //! Socket Address filters.
 
 use std::convert::Infallible;
+use std::fmt;
 use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
 
 use crate::filter::{filter_fn_one, Filter};
 
@@ -10,6 +13,9 @@ use crate::filter::{filter_fn_one, Filter};
 /// If the underlying transport doesn't use socket addresses, this will yield
 /// `None`.
 ///
+/// Behind a [trusted proxy](crate::proxy::trusted), this is the address of the
+/// client instead.
+///
 /// # Example
 ///
 /// ```
@@ -24,3 +30,109 @@ use crate::filter::{filter_fn_one, Filter};
 pub fn remote() -> impl Filter<Extract = (Option<SocketAddr>,), Error = Infallible> + Copy {
     filter_fn_one(|route| futures::future::ok(route.remote_addr()))
 }
+
+/// Creates a `Filter` to get the peer of the connection.
+///
+/// Unlike [`remote`], this also knows about peers that don't have a socket
+/// address, such as those connected to a Unix socket bound with
+/// [`bind_unix`](crate::Server::bind_unix).
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+/// use warp::addr::Peer;
+///
+/// let route = warp::addr::peer()
+///     .map(|peer: Option<Peer>| {
+///         match peer {
+///             Some(peer) => format!("hello, {}", peer),
+///             None => "hello, stranger".to_owned(),
+///         }
+///     });
+/// ```
+pub fn peer() -> impl Filter<Extract = (Option<Peer>,), Error = Infallible> + Copy {
+    filter_fn_one(|route| futures::future::ok(route.peer()))
+}
+
+/// The peer of a connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Peer {
+    /// A TCP peer, which is the same as [`remote`] gives.
+    Tcp(SocketAddr),
+    /// A peer connected to a Unix socket.
+    ///
+    /// *This variant is only available on Unix.*
+    #[cfg(unix)]
+    Unix {
+        /// The path of the peer's socket, which is usually unnamed.
+        path: Option<PathBuf>,
+        /// Who the peer is, as told by the operating system.
+        credentials: Option<Credentials>,
+    },
+}
+
+impl fmt::Display for Peer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Peer::Tcp(addr) => addr.fmt(f),
+            #[cfg(unix)]
+            Peer::Unix { path, credentials } => {
+                f.write_str("unix")?;
+                if let Some(path) = path {
+                    write!(f, ":{}", path.display())?;
+                }
+                if let Some(credentials) = credentials {
+                    write!(f, "[uid={},gid={}", credentials.uid, credentials.gid)?;
+                    if let Some(pid) = credentials.pid {
+                        write!(f, ",pid={}", pid)?;
+                    }
+                    f.write_str("]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The credentials of a process connected to a Unix socket, from
+/// `SO_PEERCRED` or its equivalent.
+///
+/// These are of the process at the time it connected.
+///
+/// *This type is only available on Unix.*
+#[cfg(unix)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Credentials {
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+    pub(crate) pid: Option<i32>,
+}
+
+#[cfg(unix)]
+impl Credentials {
+    /// The effective user ID.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// The effective group ID.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// The process ID, on platforms that report it.
+    pub fn pid(&self) -> Option<i32> {
+        self.pid
+    }
+}
+
+// Where connections without a socket address put their `Peer`, for the
+// service to add to each request.
+pub(crate) fn with_peer(mut req: crate::Request, peer: &Option<Peer>) -> crate::Request {
+    if let Some(peer) = peer {
+        req.extensions_mut().insert(peer.clone());
+    }
+    req
+}
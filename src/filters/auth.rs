@@ -0,0 +1,345 @@
+//! Authentication Filters
+//!
+//! Filters for the `Basic` ([RFC 7617]) and `Bearer` ([RFC 6750]) schemes of
+//! the `Authorization` header.
+//!
+//! Each takes an async verifier, which is given the credentials of the
+//! request and returns the principal they belong to, if any. Requests without
+//! valid credentials are rejected with [`Unauthorized`], which renders as a
+//! `401 Unauthorized` with a `WWW-Authenticate` challenge.
+//!
+//! [RFC 7617]: https://tools.ietf.org/html/rfc7617
+//! [RFC 6750]: https://tools.ietf.org/html/rfc6750
+
+use std::convert::Infallible;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+
+use base64::Engine;
+use futures::future;
+use http::header::{HeaderValue, AUTHORIZATION};
+
+use crate::filter::{filter_fn_one, Filter, One};
+use crate::reject::{self, Rejection};
+
+/// Creates a `Filter` that requires `Basic` credentials, verified by
+/// `verify`.
+///
+/// The verifier is called with the username and password of the request, and
+/// resolves to the principal they belong to, which is extracted, or `None` if
+/// they aren't valid.
+///
+/// When comparing secrets, such as passwords, use [`constant_time_eq`] or
+/// [`Credentials::matches`], so that the time taken doesn't leak how much of
+/// a guess was right.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+/// use warp::auth::Credentials;
+///
+/// let route = warp::auth::basic("admin", |credentials: Credentials| async move {
+///     if credentials.matches("admin", "hunter2") {
+///         Some(credentials.username().to_owned())
+///     } else {
+///         None
+///     }
+/// })
+/// .map(|user: String| format!("hello, {}", user));
+/// ```
+///
+/// # Panics
+///
+/// Panics if the realm can't be sent in a header.
+pub fn basic<F, Fut, T>(
+    realm: &str,
+    verify: F,
+) -> impl Filter<Extract = One<T>, Error = Rejection> + Clone
+where
+    F: Fn(Credentials) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Option<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let challenge = format!("Basic realm={}, charset=\"UTF-8\"", quote(realm));
+    let challenge = HeaderValue::from_str(&challenge).expect("invalid realm");
+
+    authorization().and_then(move |value: Option<HeaderValue>| {
+        let credentials = value.as_ref().map(Credentials::parse);
+        let challenge = challenge.clone();
+        let verify = verify.clone();
+        async move {
+            let credentials = match credentials {
+                Some(Some(credentials)) => credentials,
+                Some(None) => return Err(unauthorized(challenge, Kind::Malformed)),
+                None => return Err(unauthorized(challenge, Kind::Missing)),
+            };
+            match verify(credentials).await {
+                Some(principal) => Ok(principal),
+                None => Err(unauthorized(challenge, Kind::Invalid)),
+            }
+        }
+    })
+}
+
+/// Creates a `Filter` that requires a `Bearer` token, verified by `verify`.
+///
+/// The verifier is called with the token of the request, and resolves to the
+/// principal it belongs to, which is extracted, or `None` if it isn't valid.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let route = warp::auth::bearer(|token: String| async move {
+///     if warp::auth::constant_time_eq(token.as_bytes(), b"s3cr3t") {
+///         Some("service")
+///     } else {
+///         None
+///     }
+/// })
+/// .map(|principal: &'static str| format!("hello, {}", principal));
+/// ```
+pub fn bearer<F, Fut, T>(verify: F) -> impl Filter<Extract = One<T>, Error = Rejection> + Clone
+where
+    F: Fn(String) -> Fut + Clone + Send + Sync + 'static,
+    Fut: Future<Output = Option<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    authorization().and_then(move |value: Option<HeaderValue>| {
+        let token = value.as_ref().map(parse_bearer);
+        let verify = verify.clone();
+        async move {
+            let token = match token {
+                Some(Some(token)) => token,
+                Some(None) => {
+                    return Err(unauthorized(
+                        HeaderValue::from_static("Bearer error=\"invalid_request\""),
+                        Kind::Malformed,
+                    ))
+                }
+                // Requests without any credentials get no error code.
+                None => {
+                    return Err(unauthorized(
+                        HeaderValue::from_static("Bearer"),
+                        Kind::Missing,
+                    ))
+                }
+            };
+            match verify(token).await {
+                Some(principal) => Ok(principal),
+                None => Err(unauthorized(
+                    HeaderValue::from_static("Bearer error=\"invalid_token\""),
+                    Kind::Invalid,
+                )),
+            }
+        }
+    })
+}
+
+/// Compares two byte strings in time that depends only on their lengths.
+///
+/// Use this when checking secrets, so that how long the check takes doesn't
+/// tell an attacker how many leading bytes they guessed right.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b));
+    // Keep the compiler from short-circuiting the fold.
+    std::hint::black_box(diff) == 0
+}
+
+/// The username and password of a `Basic` authorization.
+#[derive(Clone)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+impl Credentials {
+    /// The username.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// The password.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// Checks if these are the given username and password, in constant time.
+    pub fn matches(&self, username: &str, password: &str) -> bool {
+        // Both are always compared, so a wrong username takes as long as a
+        // wrong password.
+        let username = constant_time_eq(self.username.as_bytes(), username.as_bytes());
+        let password = constant_time_eq(self.password.as_bytes(), password.as_bytes());
+        username & password
+    }
+
+    fn parse(value: &HeaderValue) -> Option<Credentials> {
+        let encoded = token68(value, "Basic")?;
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':')?;
+        Some(Credentials {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        })
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Rejection used when a request doesn't have valid credentials.
+///
+/// Renders as `401 Unauthorized`, with a `WWW-Authenticate` challenge for the
+/// scheme that was required.
+pub struct Unauthorized {
+    challenge: HeaderValue,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Missing,
+    Malformed,
+    Invalid,
+}
+
+impl Unauthorized {
+    /// The `WWW-Authenticate` challenge sent with the response.
+    pub fn challenge(&self) -> &HeaderValue {
+        &self.challenge
+    }
+}
+
+impl fmt::Debug for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Unauthorized")
+            .field("challenge", &self.challenge)
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+impl fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self.kind {
+            Kind::Missing => "Missing credentials",
+            Kind::Malformed => "Malformed credentials",
+            Kind::Invalid => "Invalid credentials",
+        })
+    }
+}
+
+impl StdError for Unauthorized {}
+
+fn unauthorized(challenge: HeaderValue, kind: Kind) -> Rejection {
+    reject::known(Unauthorized { challenge, kind })
+}
+
+fn authorization() -> impl Filter<Extract = One<Option<HeaderValue>>, Error = Infallible> + Copy {
+    filter_fn_one(|route| future::ok(route.headers().get(AUTHORIZATION).cloned()))
+}
+
+fn parse_bearer(value: &HeaderValue) -> Option<String> {
+    token68(value, "Bearer").map(str::to_owned)
+}
+
+// The `token68` credentials of `scheme`, which is case-insensitive.
+fn token68<'a>(value: &'a HeaderValue, scheme: &str) -> Option<&'a str> {
+    let value = value.to_str().ok()?;
+    let (name, token) = value.split_once(' ')?;
+    if !name.eq_ignore_ascii_case(scheme) {
+        return None;
+    }
+    let token = token.trim_start_matches(' ');
+    let padding = token.len() - token.trim_end_matches('=').len();
+    let valid = !token.is_empty()
+        && token[..token.len() - padding]
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"-._~+/".contains(&b));
+    if valid {
+        Some(token)
+    } else {
+        None
+    }
+}
+
+// Quotes a realm as an HTTP `quoted-string`.
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_credentials() {
+        let parse = |s: &'static str| Credentials::parse(&HeaderValue::from_static(s));
+
+        // "Aladdin:open sesame", from RFC 7617.
+        let credentials = parse("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==").unwrap();
+        assert_eq!(credentials.username(), "Aladdin");
+        assert_eq!(credentials.password(), "open sesame");
+        assert!(credentials.matches("Aladdin", "open sesame"));
+        assert!(!credentials.matches("Aladdin", "open sesamE"));
+
+        assert!(parse("basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==").is_some());
+        // Passwords may contain colons.
+        assert_eq!(parse("Basic YTpiOmM=").unwrap().password(), "b:c");
+
+        assert!(parse("Bearer QWxhZGRpbjpvcGVuIHNlc2FtZQ==").is_none());
+        assert!(parse("Basic").is_none());
+        assert!(parse("Basic !!!").is_none());
+        // "nocolon"
+        assert!(parse("Basic bm9jb2xvbg==").is_none());
+    }
+
+    #[test]
+    fn bearer_tokens() {
+        let parse = |s: &'static str| parse_bearer(&HeaderValue::from_static(s));
+
+        assert_eq!(parse("Bearer mF_9.B5f-4.1JqM").unwrap(), "mF_9.B5f-4.1JqM");
+        assert_eq!(parse("bearer abc==").unwrap(), "abc==");
+        assert!(parse("Bearer ").is_none());
+        assert!(parse("Bearer a=b").is_none());
+        assert!(parse("Bearer a b").is_none());
+        assert!(parse("Basic abc").is_none());
+    }
+
+    #[test]
+    fn constant_time() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secrets"));
+        assert!(constant_time_eq(b"", b""));
+    }
+
+    #[test]
+    fn quoted_realm() {
+        assert_eq!(quote(r#"say "hi"\"#), r#""say \"hi\"\\""#);
+    }
+}
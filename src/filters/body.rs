@@ -17,8 +17,9 @@ use serde::de::DeserializeOwned;
 use serde_json;
 use serde_urlencoded;
 
-use crate::filter::{filter_fn, filter_fn_one, Filter, FilterBase};
+use crate::filter::{describe, filter_fn, filter_fn_one, Filter, FilterBase};
 use crate::reject::{self, Rejection};
+use crate::routes::RouteInfo;
 
 type BoxError = Box<dyn StdError + Send + Sync>;
 
@@ -171,14 +172,17 @@ pub fn aggregate() -> impl Filter<Extract = (impl Buf,), Error = Rejection> + Co
 ///     });
 /// ```
 pub fn json<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
-    is_content_type::<Json>()
+    let filter = is_content_type::<Json>()
         .and(bytes())
         .and_then(|buf| async move {
             Json::decode(buf).map_err(|err| {
                 tracing::debug!("request json body error: {}", err);
-                reject::known(BodyDeserializeError { cause: err })
+                reject::known(err)
             })
-        })
+        });
+    describe(filter, |info: &mut RouteInfo| {
+        info.body::<T>("application/json")
+    })
 }
 
 /// Returns a `Filter` that matches any request and extracts a
@@ -206,14 +210,17 @@ pub fn json<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error =
 ///     });
 /// ```
 pub fn form<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
-    is_content_type::<Form>()
+    let filter = is_content_type::<Form>()
         .and(aggregate())
         .and_then(|buf| async move {
             Form::decode(buf).map_err(|err| {
                 tracing::debug!("request form body error: {}", err);
-                reject::known(BodyDeserializeError { cause: err })
+                reject::known(err)
             })
-        })
+        });
+    describe(filter, |info: &mut RouteInfo| {
+        info.body::<T>("application/x-www-form-urlencoded")
+    })
 }
 
 // ===== Decoders =====
@@ -222,7 +229,7 @@ trait Decode {
     const MIME: (mime::Name<'static>, mime::Name<'static>);
     const WITH_NO_CONTENT_TYPE: bool;
 
-    fn decode<B: Buf, T: DeserializeOwned>(buf: B) -> Result<T, BoxError>;
+    fn decode<B: Buf, T: DeserializeOwned>(buf: B) -> Result<T, BodyDeserializeError>;
 }
 
 struct Json;
@@ -231,8 +238,18 @@ impl Decode for Json {
     const MIME: (mime::Name<'static>, mime::Name<'static>) = (mime::APPLICATION, mime::JSON);
     const WITH_NO_CONTENT_TYPE: bool = true;
 
-    fn decode<B: Buf, T: DeserializeOwned>(mut buf: B) -> Result<T, BoxError> {
-        serde_json::from_slice(&buf.copy_to_bytes(buf.remaining())).map_err(Into::into)
+    fn decode<B: Buf, T: DeserializeOwned>(mut buf: B) -> Result<T, BodyDeserializeError> {
+        let bytes = buf.copy_to_bytes(buf.remaining());
+        let json_error = |field, err: serde_json::Error| BodyDeserializeError {
+            field,
+            position: Some((err.line(), err.column())),
+            ..BodyDeserializeError::new(err)
+        };
+        let mut de = serde_json::Deserializer::from_slice(&bytes);
+        let value = serde_path_to_error::deserialize(&mut de)
+            .map_err(|err| json_error(field_path(err.path()), err.into_inner()))?;
+        de.end().map_err(|err| json_error(None, err))?;
+        Ok(value)
     }
 }
 
@@ -243,11 +260,23 @@ impl Decode for Form {
         (mime::APPLICATION, mime::WWW_FORM_URLENCODED);
     const WITH_NO_CONTENT_TYPE: bool = true;
 
-    fn decode<B: Buf, T: DeserializeOwned>(buf: B) -> Result<T, BoxError> {
-        serde_urlencoded::from_reader(buf.reader()).map_err(Into::into)
+    fn decode<B: Buf, T: DeserializeOwned>(mut buf: B) -> Result<T, BodyDeserializeError> {
+        let bytes = buf.copy_to_bytes(buf.remaining());
+        let de = serde_urlencoded::Deserializer::new(form_urlencoded::parse(&bytes));
+        serde_path_to_error::deserialize(de).map_err(|err| BodyDeserializeError {
+            field: field_path(err.path()),
+            ..BodyDeserializeError::new(err.into_inner())
+        })
     }
 }
 
+// The field a decoder failed at, such as `items[0].name`, unless it failed
+// before reaching any.
+pub(crate) fn field_path(path: &serde_path_to_error::Path) -> Option<String> {
+    path.iter().next()?;
+    Some(path.to_string())
+}
+
 // Require the `content-type` header to be this type (or, if there's no `content-type`
 // header at all, optimistically hope it's the right type).
 fn is_content_type<D: Decode>() -> impl Filter<Extract = (), Error = Rejection> + Copy {
@@ -315,6 +344,38 @@ impl Stream for BodyStream {
 #[derive(Debug)]
 pub struct BodyDeserializeError {
     cause: BoxError,
+    field: Option<String>,
+    position: Option<(usize, usize)>,
+}
+
+impl BodyDeserializeError {
+    fn new<E: Into<BoxError>>(err: E) -> BodyDeserializeError {
+        BodyDeserializeError {
+            cause: err.into(),
+            field: None,
+            position: None,
+        }
+    }
+
+    /// The path of the field that failed to decode, such as `items[0].name`,
+    /// if known.
+    ///
+    /// What the field was expected to be and the value sent aren't kept
+    /// apart from the message of the [`source`](StdError::source), which is
+    /// the client's input and isn't safe to show as is.
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    /// The line of a JSON body the error occurred at, starting from 1.
+    pub fn line(&self) -> Option<usize> {
+        self.position.map(|(line, _)| line)
+    }
+
+    /// The column of a JSON body the error occurred at, starting from 1.
+    pub fn column(&self) -> Option<usize> {
+        self.position.map(|(_, column)| column)
+    }
 }
 
 impl fmt::Display for BodyDeserializeError {
@@ -156,6 +156,7 @@ mod internal {
     use crate::filter::{Filter, FilterBase, Internal};
     use crate::reject::IsReject;
     use crate::reply::{Reply, Response};
+    use crate::routes::RouteTable;
 
     use super::Compression;
 
@@ -244,6 +245,10 @@ mod internal {
                 future: self.filter.filter(Internal),
             }
         }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
     }
 
     #[allow(missing_debug_implementations)]
@@ -1,7 +1,12 @@
 //! Cookie Filters
+//!
+//! [`cookie`](cookie) and [`optional`](optional) read the raw value of a
+//! cookie. With the `cookie-jar` feature, [`jar`](jar) gives handlers a
+//! [`CookieJar`](CookieJar) to read and write cookies, including signed and
+//! private ones.
 
 use futures::future;
-use headers::Cookie;
+use headers::Cookie as CookieHeader;
 
 use super::header;
 use crate::filter::{Filter, One};
@@ -9,6 +14,11 @@ use crate::reject::Rejection;
 use std::convert::Infallible;
 use std::str::FromStr;
 
+#[cfg(feature = "cookie-jar")]
+pub use self::jar::{jar, CookieJar, Keys, PrivateJar, SignedJar};
+#[cfg(feature = "cookie-jar")]
+pub use ::cookie::{time, Cookie, CookieBuilder, Expiration, Key, SameSite};
+
 /// Creates a `Filter` that requires a cookie by name.
 ///
 /// If found, extracts the value of the cookie, otherwise rejects.
@@ -16,7 +26,7 @@ pub fn cookie<T>(name: &'static str) -> impl Filter<Extract = One<T>, Error = Re
 where
     T: FromStr + Send + 'static,
 {
-    header::header2().and_then(move |cookie: Cookie| {
+    header::header2().and_then(move |cookie: CookieHeader| {
         let cookie = cookie
             .get(name)
             .ok_or_else(|| crate::reject::missing_cookie(name))
@@ -35,7 +45,7 @@ pub fn optional<T>(
 where
     T: FromStr + Send + 'static,
 {
-    header::optional2().map(move |opt: Option<Cookie>| {
+    header::optional2().map(move |opt: Option<CookieHeader>| {
         let cookie = opt.and_then(|cookie| cookie.get(name).map(|x| T::from_str(x)));
         match cookie {
             Some(Ok(t)) => Some(t),
@@ -44,3 +54,300 @@ where
         }
     })
 }
+
+#[cfg(feature = "cookie-jar")]
+mod jar {
+    use std::convert::Infallible;
+    use std::fmt;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    use ::cookie::{Cookie, Key};
+    use futures::future;
+    use http::header::{HeaderValue, COOKIE, SET_COOKIE};
+
+    use crate::filter::{filter_fn, Filter, One};
+    use crate::route::{self, Route};
+
+    /// Creates a `Filter` that extracts the request's [`CookieJar`](CookieJar).
+    ///
+    /// Cookies added to or removed from the jar are sent back in `Set-Cookie`
+    /// headers, on whatever response the request ends up with. Changes made
+    /// by a filter that rejects are undone when another one handles the
+    /// request instead, such as with [`or`](crate::Filter::or).
+    ///
+    /// Signed and private cookies are protected with `keys`, which can be a
+    /// single [`Key`](Key), or [`Keys`](Keys) to also accept cookies from
+    /// previous keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use warp::cookie::{Cookie, CookieJar, Key};
+    /// use warp::Filter;
+    ///
+    /// let key = Key::generate();
+    ///
+    /// let route = warp::path!("visit")
+    ///     .and(warp::cookie::jar(key))
+    ///     .map(|jar: CookieJar| {
+    ///         let visits = jar
+    ///             .signed()
+    ///             .get("visits")
+    ///             .and_then(|c| c.value().parse::<u32>().ok())
+    ///             .unwrap_or(0);
+    ///         let cookie = Cookie::build(("visits", (visits + 1).to_string()))
+    ///             .path("/")
+    ///             .http_only(true)
+    ///             .secure(true);
+    ///         jar.signed().add(cookie);
+    ///         format!("visit #{}", visits + 1)
+    ///     });
+    /// ```
+    pub fn jar(
+        keys: impl Into<Keys>,
+    ) -> impl Filter<Extract = One<CookieJar>, Error = Infallible> + Clone {
+        let keys = Arc::new(keys.into());
+        filter_fn(move |route| {
+            let cookies = Shared::from_route(route);
+            future::ok((CookieJar {
+                cookies,
+                keys: keys.clone(),
+            },))
+        })
+    }
+
+    /// The cookies of a request, and the changes to send back.
+    ///
+    /// Extracted with [`warp::cookie::jar`](jar). Clones share the same
+    /// cookies.
+    #[derive(Clone)]
+    pub struct CookieJar {
+        cookies: Shared,
+        keys: Arc<Keys>,
+    }
+
+    /// The signed cookies of a [`CookieJar`](CookieJar).
+    ///
+    /// Signed cookies can be read by the client, but not tampered with.
+    #[derive(Debug)]
+    pub struct SignedJar<'a> {
+        jar: &'a CookieJar,
+    }
+
+    /// The private cookies of a [`CookieJar`](CookieJar).
+    ///
+    /// Private cookies are encrypted, so they can't be read or tampered
+    /// with by the client.
+    #[derive(Debug)]
+    pub struct PrivateJar<'a> {
+        jar: &'a CookieJar,
+    }
+
+    /// The keys used to sign and encrypt cookies.
+    ///
+    /// New cookies always use the current key. Cookies from the client are
+    /// also accepted if they were signed or encrypted with a previous key,
+    /// so keys can be rotated without logging everyone out. Adding such a
+    /// cookie again moves it to the current key.
+    #[derive(Clone)]
+    pub struct Keys {
+        current: Key,
+        previous: Vec<Key>,
+    }
+
+    // All the `CookieJar`s of a request share the cookies, so that only one
+    // set of `Set-Cookie` headers is sent.
+    #[derive(Clone)]
+    struct Shared(Arc<Mutex<::cookie::CookieJar>>);
+
+    impl CookieJar {
+        /// Get a cookie by name.
+        ///
+        /// This includes cookies added to this jar since.
+        pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+            self.cookies.lock().get(name).cloned()
+        }
+
+        /// Add a cookie, sending it to the client.
+        pub fn add(&self, cookie: impl Into<Cookie<'static>>) {
+            self.cookies.change(|jar| jar.add(cookie));
+        }
+
+        /// Remove a cookie, telling the client to remove it as well.
+        ///
+        /// The `path` and `domain` must match the ones the cookie was added
+        /// with.
+        pub fn remove(&self, cookie: impl Into<Cookie<'static>>) {
+            self.cookies.change(|jar| jar.remove(cookie));
+        }
+
+        /// The signed cookies of this jar.
+        pub fn signed(&self) -> SignedJar<'_> {
+            SignedJar { jar: self }
+        }
+
+        /// The private cookies of this jar.
+        pub fn private(&self) -> PrivateJar<'_> {
+            PrivateJar { jar: self }
+        }
+    }
+
+    impl fmt::Debug for CookieJar {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let cookies = self.cookies.lock();
+            f.debug_list()
+                .entries(cookies.iter().map(|c| c.name()))
+                .finish()
+        }
+    }
+
+    impl SignedJar<'_> {
+        /// Get a signed cookie by name, if its signature is valid.
+        pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+            let cookies = self.jar.cookies.lock();
+            self.jar
+                .keys
+                .iter()
+                .find_map(|key| cookies.signed(key).get(name))
+        }
+
+        /// Sign and add a cookie.
+        pub fn add(&self, cookie: impl Into<Cookie<'static>>) {
+            let key = &self.jar.keys.current;
+            self.jar
+                .cookies
+                .change(|jar| jar.signed_mut(key).add(cookie));
+        }
+
+        /// Remove a signed cookie.
+        pub fn remove(&self, cookie: impl Into<Cookie<'static>>) {
+            let key = &self.jar.keys.current;
+            self.jar
+                .cookies
+                .change(|jar| jar.signed_mut(key).remove(cookie));
+        }
+    }
+
+    impl PrivateJar<'_> {
+        /// Get and decrypt a private cookie by name, if it's authentic.
+        pub fn get(&self, name: &str) -> Option<Cookie<'static>> {
+            let cookies = self.jar.cookies.lock();
+            self.jar
+                .keys
+                .iter()
+                .find_map(|key| cookies.private(key).get(name))
+        }
+
+        /// Encrypt and add a cookie.
+        pub fn add(&self, cookie: impl Into<Cookie<'static>>) {
+            let key = &self.jar.keys.current;
+            self.jar
+                .cookies
+                .change(|jar| jar.private_mut(key).add(cookie));
+        }
+
+        /// Remove a private cookie.
+        pub fn remove(&self, cookie: impl Into<Cookie<'static>>) {
+            let key = &self.jar.keys.current;
+            self.jar
+                .cookies
+                .change(|jar| jar.private_mut(key).remove(cookie));
+        }
+    }
+
+    impl Keys {
+        /// Use `current` to sign and encrypt cookies.
+        pub fn new(current: Key) -> Keys {
+            Keys {
+                current,
+                previous: Vec::new(),
+            }
+        }
+
+        /// Also accept cookies signed or encrypted with a previous key.
+        pub fn previous(mut self, key: Key) -> Keys {
+            self.previous.push(key);
+            self
+        }
+
+        fn iter(&self) -> impl Iterator<Item = &Key> {
+            std::iter::once(&self.current).chain(&self.previous)
+        }
+    }
+
+    impl From<Key> for Keys {
+        fn from(key: Key) -> Keys {
+            Keys::new(key)
+        }
+    }
+
+    impl fmt::Debug for Keys {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.debug_struct("Keys")
+                .field("previous", &self.previous.len())
+                .finish()
+        }
+    }
+
+    impl Shared {
+        fn from_route(route: &mut Route) -> Shared {
+            if let Some(shared) = route.extensions().get::<Shared>() {
+                return shared.clone();
+            }
+
+            let mut jar = ::cookie::CookieJar::new();
+            let cookies = route
+                .headers()
+                .get_all(COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(';'))
+                .map(str::trim)
+                .filter(|s| !s.is_empty());
+            for cookie in cookies {
+                if let Ok(cookie) = Cookie::parse_encoded(cookie.to_owned()) {
+                    jar.add_original(cookie);
+                }
+            }
+
+            let shared = Shared(Arc::new(Mutex::new(jar)));
+            route.extensions_mut().insert(shared.clone());
+            route.on_discard(|route| {
+                route.extensions_mut().remove::<Shared>();
+            });
+
+            let delta = shared.clone();
+            route.on_response(move |res| {
+                for cookie in delta.lock().delta() {
+                    let value = cookie.encoded().to_string();
+                    match HeaderValue::from_str(&value) {
+                        Ok(value) => {
+                            res.headers_mut().append(SET_COOKIE, value);
+                        }
+                        Err(_) => tracing::warn!("invalid cookie: {:?}", cookie.name()),
+                    }
+                }
+            });
+            shared
+        }
+
+        fn lock(&self) -> MutexGuard<'_, ::cookie::CookieJar> {
+            self.0.lock().unwrap()
+        }
+
+        // Changes the cookies, to be undone if the filter making the change
+        // rejects and another one handles the request.
+        fn change<F>(&self, func: F)
+        where
+            F: FnOnce(&mut ::cookie::CookieJar),
+        {
+            let mut jar = self.lock();
+            if route::is_set() {
+                let before = jar.clone();
+                let shared = self.clone();
+                route::with(|route| route.on_discard(move |_| *shared.lock() = before));
+            }
+            func(&mut jar);
+        }
+    }
+}
@@ -468,6 +468,7 @@ mod internal {
     use crate::generic::Either;
     use crate::reject::{CombineRejection, Rejection};
     use crate::route;
+    use crate::routes::RouteTable;
 
     #[derive(Clone, Debug)]
     pub struct CorsFilter<F> {
@@ -516,6 +517,10 @@ mod internal {
                 }
             }
         }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.inner.describe(Internal)
+        }
     }
 
     #[derive(Debug)]
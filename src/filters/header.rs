@@ -12,8 +12,9 @@ use headers::{Header, HeaderMapExt};
 use http::header::HeaderValue;
 use http::HeaderMap;
 
-use crate::filter::{filter_fn, filter_fn_one, Filter, One};
+use crate::filter::{describe, filter_fn, filter_fn_one, Filter, One};
 use crate::reject::{self, Rejection};
+use crate::routes::{type_name, RouteInfo};
 
 /// Create a `Filter` that tries to parse the specified header.
 ///
@@ -37,16 +38,22 @@ use crate::reject::{self, Rejection};
 pub fn header<T: FromStr + Send + 'static>(
     name: &'static str,
 ) -> impl Filter<Extract = One<T>, Error = Rejection> + Copy {
-    filter_fn_one(move |route| {
+    let filter = filter_fn_one(move |route| {
         tracing::trace!("header({:?})", name);
         let route = route
             .headers()
             .get(name)
             .ok_or_else(|| reject::missing_header(name))
-            .and_then(|value| value.to_str().map_err(|_| reject::invalid_header(name)))
-            .and_then(|s| T::from_str(s).map_err(|_| reject::invalid_header(name)));
+            .and_then(|value| {
+                let invalid = || reject::invalid_header_value(name, value, Some(type_name::<T>()));
+                value
+                    .to_str()
+                    .map_err(|_| invalid())
+                    .and_then(|s| T::from_str(s).map_err(|_| invalid()))
+            });
         future::ready(route)
-    })
+    });
+    describe(filter, move |info: &mut RouteInfo| info.header(name, true))
 }
 
 pub(crate) fn header2<T: Header + Send + 'static>(
@@ -79,14 +86,15 @@ pub fn optional<T>(
 where
     T: FromStr + Send + 'static,
 {
-    filter_fn_one(move |route| {
+    let filter = filter_fn_one(move |route| {
         tracing::trace!("optional({:?})", name);
         let result = route.headers().get(name).map(|value| {
+            let invalid = || reject::invalid_header_value(name, value, Some(type_name::<T>()));
             value
                 .to_str()
-                .map_err(|_| reject::invalid_header(name))?
+                .map_err(|_| invalid())?
                 .parse::<T>()
-                .map_err(|_| reject::invalid_header(name))
+                .map_err(|_| invalid())
         });
 
         match result {
@@ -94,7 +102,8 @@ where
             Some(Err(e)) => future::err(e),
             None => future::ok(None),
         }
-    })
+    });
+    describe(filter, move |info: &mut RouteInfo| info.header(name, false))
 }
 
 pub(crate) fn optional2<T>() -> impl Filter<Extract = One<Option<T>>, Error = Infallible> + Copy
@@ -138,7 +147,7 @@ pub fn exact(
     name: &'static str,
     value: &'static str,
 ) -> impl Filter<Extract = (), Error = Rejection> + Copy {
-    filter_fn(move |route| {
+    let filter = filter_fn(move |route| {
         tracing::trace!("exact?({:?}, {:?})", name, value);
         let route = route
             .headers()
@@ -148,11 +157,12 @@ pub fn exact(
                 if val == value {
                     Ok(())
                 } else {
-                    Err(reject::invalid_header(name))
+                    Err(reject::invalid_header_value(name, val, None))
                 }
             });
         future::ready(route)
-    })
+    });
+    describe(filter, move |info: &mut RouteInfo| info.header(name, true))
 }
 
 /// Create a `Filter` that requires a header to match the value exactly.
@@ -170,7 +180,7 @@ pub fn exact_ignore_case(
     name: &'static str,
     value: &'static str,
 ) -> impl Filter<Extract = (), Error = Rejection> + Copy {
-    filter_fn(move |route| {
+    let filter = filter_fn(move |route| {
         tracing::trace!("exact_ignore_case({:?}, {:?})", name, value);
         let route = route
             .headers()
@@ -180,11 +190,12 @@ pub fn exact_ignore_case(
                 if val.as_bytes().eq_ignore_ascii_case(value.as_bytes()) {
                     Ok(())
                 } else {
-                    Err(reject::invalid_header(name))
+                    Err(reject::invalid_header_value(name, val, None))
                 }
             });
         future::ready(route)
-    })
+    });
+    describe(filter, move |info: &mut RouteInfo| info.header(name, true))
 }
 
 /// Create a `Filter` that gets a `HeaderValue` for the name.
@@ -202,7 +213,7 @@ pub fn exact_ignore_case(
 pub fn value(
     name: &'static str,
 ) -> impl Filter<Extract = One<HeaderValue>, Error = Rejection> + Copy {
-    filter_fn_one(move |route| {
+    let filter = filter_fn_one(move |route| {
         tracing::trace!("value({:?})", name);
         let route = route
             .headers()
@@ -210,7 +221,8 @@ pub fn value(
             .cloned()
             .ok_or_else(|| reject::missing_header(name));
         future::ready(route)
-    })
+    });
+    describe(filter, move |info: &mut RouteInfo| info.header(name, true))
 }
 
 /// Create a `Filter` that returns a clone of the request's `HeaderMap`.
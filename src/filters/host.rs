@@ -4,6 +4,7 @@ use crate::filter::{filter_fn_one, Filter, One};
 use crate::reject::{self, Rejection};
 use futures::future;
 pub use http::uri::Authority;
+use std::net::Ipv6Addr;
 use std::str::FromStr;
 
 /// Creates a `Filter` that requires a specific authority (target server's
@@ -30,6 +31,221 @@ pub fn exact(expected: &str) -> impl Filter<Extract = (), Error = Rejection> + C
         .untuple_one()
 }
 
+/// Creates a `Filter` that requires the host of the request to match a
+/// pattern, extracting the named labels.
+///
+/// A pattern is a host name, where a whole label can be:
+///
+/// - `{name}`, matching any one label, which is extracted as `name`,
+/// - `*`, matching any one label, without extracting it.
+///
+/// Hosts are compared case-insensitively. With the `idna` feature,
+/// internationalized names are compared in their ASCII form, so
+/// `bücher.example` and `xn--bcher-kva.example` are the same, and extracted
+/// labels are in that ASCII form. Without it, hosts that aren't ASCII don't
+/// match.
+///
+/// An IPv6 address is written in brackets, such as `[::1]`, and matched as a
+/// whole.
+///
+/// If the pattern has no port, any port matches. Otherwise, the port must be
+/// the same.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+/// use warp::host::HostParams;
+///
+/// let tenant = warp::host::pattern("{tenant}.example.com")
+///     .map(|params: HostParams| {
+///         format!("hello, {}", params.get("tenant").unwrap_or_default())
+///     });
+/// ```
+///
+/// # Panics
+///
+/// If the pattern isn't a valid host name, which includes names that aren't
+/// ASCII without the `idna` feature.
+pub fn pattern(pattern: &str) -> impl Filter<Extract = One<HostParams>, Error = Rejection> + Clone {
+    let pattern = Pattern::parse(pattern);
+    optional().and_then(move |option: Option<Authority>| {
+        let matched = option.and_then(|authority| pattern.matches(&authority));
+        match matched {
+            Some(params) => future::ok(params),
+            None => future::err(reject::not_found()),
+        }
+    })
+}
+
+/// Creates a `Filter` that requires the host of the request to be a domain,
+/// or any subdomain of it.
+///
+/// Any port matches, and hosts are compared like in [`pattern`].
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// // Matches `example.com`, `www.example.com`, `a.b.example.com`...
+/// let route = warp::host::suffix("example.com").map(|| "hello");
+/// ```
+///
+/// # Panics
+///
+/// If the suffix isn't a valid host name.
+pub fn suffix(suffix: &str) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let suffix = normalize(suffix.trim_start_matches('.')).expect("invalid host suffix");
+    optional()
+        .and_then(move |option: Option<Authority>| {
+            let host = option.and_then(|authority| normalize(authority.host()));
+            match host {
+                Some(host)
+                    if host == suffix
+                        || (host.ends_with(&*suffix)
+                            && host[..host.len() - suffix.len()].ends_with('.')) =>
+                {
+                    future::ok(())
+                }
+                _ => future::err(reject::not_found()),
+            }
+        })
+        .untuple_one()
+}
+
+/// The labels extracted by a [`pattern`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HostParams {
+    params: Vec<(String, String)>,
+}
+
+impl HostParams {
+    /// Get the label extracted for `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Iterate over the names and labels, in pattern order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+#[derive(Clone)]
+struct Pattern {
+    labels: Vec<Label>,
+    port: Option<u16>,
+}
+
+#[derive(Clone)]
+enum Label {
+    Exact(String),
+    Param(String),
+    Any,
+}
+
+impl Pattern {
+    fn parse(source: &str) -> Pattern {
+        let invalid = || -> ! { panic!("invalid host pattern {:?}", source) };
+        // An IPv6 address has colons of its own, so the port can only come
+        // after its closing bracket.
+        let host_end = if source.starts_with('[') {
+            source.find(']').unwrap_or_else(|| invalid()) + 1
+        } else {
+            source.rfind(':').unwrap_or(source.len())
+        };
+        let (host, port) = source.split_at(host_end);
+        let port = match port {
+            "" => None,
+            port => Some(
+                port.strip_prefix(':')
+                    .and_then(|port| port.parse().ok())
+                    .unwrap_or_else(|| panic!("invalid port in host pattern {:?}", source)),
+            ),
+        };
+
+        if host.starts_with('[') {
+            if host[1..host.len() - 1].parse::<Ipv6Addr>().is_err() {
+                invalid();
+            }
+            return Pattern {
+                labels: vec![Label::Exact(host.to_ascii_lowercase())],
+                port,
+            };
+        }
+        let labels = host
+            .split('.')
+            .map(|label| {
+                if label == "*" {
+                    Label::Any
+                } else if let Some(name) = label.strip_prefix('{').and_then(|l| l.strip_suffix('}'))
+                {
+                    Label::Param(name.to_owned())
+                } else {
+                    normalize(label)
+                        .filter(|label| !label.is_empty())
+                        .map(Label::Exact)
+                        .unwrap_or_else(|| invalid())
+                }
+            })
+            .collect();
+        Pattern { labels, port }
+    }
+
+    fn matches(&self, authority: &Authority) -> Option<HostParams> {
+        if self.port.is_some() && self.port != authority.port_u16() {
+            return None;
+        }
+        let host = authority.host();
+        let host = if host.starts_with('[') {
+            host.to_ascii_lowercase()
+        } else {
+            normalize(host)?
+        };
+        let labels = if host.starts_with('[') {
+            vec![host.as_str()]
+        } else {
+            host.split('.').collect::<Vec<_>>()
+        };
+        if labels.len() != self.labels.len() {
+            return None;
+        }
+
+        let mut params = HostParams::default();
+        for (pattern, label) in self.labels.iter().zip(labels) {
+            match pattern {
+                Label::Exact(exact) if exact == label => (),
+                Label::Exact(_) => return None,
+                Label::Param(name) => params.params.push((name.clone(), label.to_owned())),
+                Label::Any => (),
+            }
+        }
+        Some(params)
+    }
+}
+
+// Lowercases a host, and converts internationalized names to their ASCII
+// form.
+#[cfg(feature = "idna")]
+fn normalize(host: &str) -> Option<String> {
+    idna::domain_to_ascii(host.trim_end_matches('.')).ok()
+}
+
+// Lowercases a host, which has to be ASCII.
+#[cfg(not(feature = "idna"))]
+fn normalize(host: &str) -> Option<String> {
+    let host = host.trim_end_matches('.');
+    if host.is_ascii() {
+        Some(host.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
 /// Creates a `Filter` that looks for an authority (target server's host
 /// and port) in the request.
 ///
@@ -41,6 +257,9 @@ pub fn exact(expected: &str) -> impl Filter<Extract = (), Error = Rejection> + C
 /// Rejects with `400 Bad Request` if the `Host` header is malformed or if there
 /// is a mismatch between the `Host` header and the target URI.
 ///
+/// Behind a [trusted proxy](crate::proxy::trusted), extracts the host the
+/// proxy forwarded instead, if it did.
+///
 /// # Example
 ///
 /// ```
@@ -65,6 +284,11 @@ pub fn optional() -> impl Filter<Extract = One<Option<Authority>>, Error = Rejec
         //  2) in the `Host` header (HTTP/1.1 origin requests, HTTP/2 converted)
         //
         // Hyper transparently handles 1a/1b, but not 2, so we must look at both.
+        //
+        // Unless a trusted proxy already told us what the client asked for.
+        if let Some(forwarded) = route.forwarded_host() {
+            return future::ready(Ok(Some(forwarded.clone())));
+        }
 
         let from_uri = route.uri().authority();
 
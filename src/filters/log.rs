@@ -36,8 +36,8 @@ pub fn log(name: &'static str) -> Log<impl Fn(Info) + Copy> {
         // - response content length?
         log::info!(
             target: name,
-            "{} \"{} {} {:?}\" {} \"{}\" \"{}\" {:?}",
-            OptFmt(info.route.remote_addr()),
+            "{} \"{} {} {:?}\" {} \"{}\" \"{}\" {:?}{}",
+            OptFmt(info.peer()),
             info.method(),
             info.path(),
             info.route.version(),
@@ -45,6 +45,7 @@ pub fn log(name: &'static str) -> Log<impl Fn(Info) + Copy> {
             OptFmt(info.referer()),
             OptFmt(info.user_agent()),
             info.elapsed(),
+            IdFmt(info.request_id()),
         );
     };
     Log { func }
@@ -114,6 +115,12 @@ impl<'a> Info<'a> {
         self.route.remote_addr()
     }
 
+    /// View the peer of the connection, which also knows about peers without
+    /// a `SocketAddr`, such as on Unix sockets.
+    pub fn peer(&self) -> Option<crate::addr::Peer> {
+        self.route.peer()
+    }
+
     /// View the `http::Method` of the request.
     pub fn method(&self) -> &http::Method {
         self.route.method()
@@ -157,16 +164,23 @@ impl<'a> Info<'a> {
 
     /// View the host of the request
     pub fn host(&self) -> Option<&str> {
-        self.route
-            .headers()
-            .get(header::HOST)
-            .and_then(|v| v.to_str().ok())
+        self.route.host()
+    }
+
+    /// View the scheme of the request, if known.
+    pub fn scheme(&self) -> Option<&str> {
+        self.route.scheme().map(|s| s.as_str())
     }
 
     /// Access the full headers of the request
     pub fn request_headers(&self) -> &http::HeaderMap {
         self.route.headers()
     }
+
+    /// View the ID assigned by [`warp::request_id`](crate::request_id()), if any.
+    pub fn request_id(&self) -> Option<&str> {
+        crate::request_id::from_route(self.route)
+    }
 }
 
 struct OptFmt<T>(Option<T>);
@@ -181,6 +195,20 @@ impl<T: fmt::Display> fmt::Display for OptFmt<T> {
     }
 }
 
+// Only appended when a request ID was assigned, so the default format doesn't
+// change for everyone else.
+struct IdFmt<'a>(Option<&'a str>);
+
+impl fmt::Display for IdFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(id) = self.0 {
+            write!(f, " {}", id)
+        } else {
+            Ok(())
+        }
+    }
+}
+
 mod internal {
     use std::future::Future;
     use std::pin::Pin;
@@ -195,6 +223,7 @@ mod internal {
     use crate::reject::IsReject;
     use crate::reply::{Reply, Response};
     use crate::route;
+    use crate::routes::RouteTable;
 
     #[allow(missing_debug_implementations)]
     pub struct Logged(pub(super) Response);
@@ -232,6 +261,10 @@ mod internal {
                 started,
             }
         }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
     }
 
     #[allow(missing_debug_implementations)]
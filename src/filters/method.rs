@@ -9,8 +9,9 @@
 use futures::future;
 use http::Method;
 
-use crate::filter::{filter_fn, filter_fn_one, Filter, One};
+use crate::filter::{describe, filter_fn, filter_fn_one, Filter, One};
 use crate::reject::Rejection;
+use crate::routes::RouteInfo;
 use std::convert::Infallible;
 
 /// Create a `Filter` that requires the request method to be `GET`.
@@ -129,14 +130,17 @@ fn method_is<F>(func: F) -> impl Filter<Extract = (), Error = Rejection> + Copy
 where
     F: Fn() -> &'static Method + Copy,
 {
-    filter_fn(move |route| {
+    let filter = filter_fn(move |route| {
         let method = func();
         tracing::trace!("method::{:?}?: {:?}", method, route.method());
         if route.method() == method {
             future::ok(())
         } else {
-            future::err(crate::reject::method_not_allowed())
+            future::err(crate::reject::method_not_allowed(method.clone()))
         }
+    });
+    describe(filter, move |info: &mut RouteInfo| {
+        info.set_method(func().clone())
     })
 }
 
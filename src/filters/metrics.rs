@@ -0,0 +1,595 @@
+//! Metrics Filters
+//!
+//! Wrapping filters that record request metrics for a named route, and a
+//! built-in [`Prometheus`](Prometheus) recorder that can be served in the
+//! Prometheus text exposition format.
+//!
+//! # Example
+//!
+//! ```
+//! use warp::Filter;
+//!
+//! let prometheus = warp::metrics::Prometheus::new();
+//!
+//! let hello = warp::path!("hello" / String)
+//!     .map(|name| format!("Hello, {}!", name))
+//!     .with(warp::metrics::route("hello", prometheus.clone()));
+//!
+//! let metrics = warp::path!("metrics")
+//!     .map(move || prometheus.clone());
+//!
+//! let routes = hello.or(metrics);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use http::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use http::{Method, StatusCode};
+use hyper::body::HttpBody;
+
+use crate::filter::{Filter, WrapSealed};
+use crate::reject::IsReject;
+use crate::reply::{Reply, Response};
+
+use self::internal::WithMetrics;
+
+/// Create a wrapping filter that records metrics of the wrapped filter to
+/// `recorder`, labeled with the route `name`.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let prometheus = warp::metrics::Prometheus::new();
+///
+/// let route = warp::path!("users" / u32)
+///     .map(|id| format!("user #{}", id))
+///     .with(warp::metrics::route("get_user", prometheus));
+/// ```
+pub fn route<R>(name: &'static str, recorder: R) -> Metrics<R>
+where
+    R: Recorder + Clone,
+{
+    Metrics { name, recorder }
+}
+
+/// Decorates a [`Filter`](crate::Filter) to record metrics.
+#[derive(Clone, Copy, Debug)]
+pub struct Metrics<R> {
+    name: &'static str,
+    recorder: R,
+}
+
+/// Receives the metrics of requests passing through a [`route`](route) filter.
+///
+/// Every call to `started` is paired with exactly one call to `finished`,
+/// even if the request is dropped before it completes, so the difference can
+/// be used as an in-flight gauge.
+pub trait Recorder: Send + Sync + 'static {
+    /// A request entered the route.
+    fn started(&self, route: &'static str, method: &Method);
+
+    /// A request left the route.
+    fn finished(&self, info: &Info<'_>);
+}
+
+impl<R: Recorder + ?Sized> Recorder for Arc<R> {
+    fn started(&self, route: &'static str, method: &Method) {
+        (**self).started(route, method)
+    }
+
+    fn finished(&self, info: &Info<'_>) {
+        (**self).finished(info)
+    }
+}
+
+/// Information about a finished request, given to [`Recorder::finished`].
+#[derive(Debug)]
+pub struct Info<'a> {
+    route: &'static str,
+    method: &'a Method,
+    status: Option<StatusCode>,
+    rejected: bool,
+    elapsed: Duration,
+    request_size: Option<u64>,
+    response_size: Option<u64>,
+}
+
+impl<'a> Info<'a> {
+    /// The name the route was given.
+    pub fn route(&self) -> &'static str {
+        self.route
+    }
+
+    /// The `http::Method` of the request.
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    /// The `http::StatusCode` of the response.
+    ///
+    /// If the wrapped filter was rejected, this is the status of the
+    /// rejection. If the request was dropped before a response was
+    /// produced, this is `None`.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    /// Whether the wrapped filter rejected the request.
+    ///
+    /// A rejected request may still be handled by another route, in which
+    /// case this route didn't really serve it.
+    pub fn is_rejection(&self) -> bool {
+        self.rejected
+    }
+
+    /// The `Duration` that elapsed for the request.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The size of the request body, if it was known up front.
+    pub fn request_size(&self) -> Option<u64> {
+        self.request_size
+    }
+
+    /// The size of the response body, if it was known up front.
+    pub fn response_size(&self) -> Option<u64> {
+        self.response_size
+    }
+}
+
+impl<R, F> WrapSealed<F> for Metrics<R>
+where
+    R: Recorder + Clone,
+    F: Filter + Clone + Send,
+    F::Extract: Reply,
+    F::Error: IsReject,
+{
+    type Wrapped = WithMetrics<R, F>;
+
+    fn wrap(&self, filter: F) -> Self::Wrapped {
+        WithMetrics {
+            filter,
+            metrics: self.clone(),
+        }
+    }
+}
+
+fn response_size(resp: &Response) -> Option<u64> {
+    resp.body().size_hint().exact().or_else(|| {
+        resp.headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+    })
+}
+
+// ===== Prometheus =====
+
+const DURATION_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const SIZE_BUCKETS: &[f64] = &[
+    64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0,
+];
+
+/// A [`Recorder`](Recorder) that keeps metrics in memory, and renders them
+/// in the Prometheus text exposition format.
+///
+/// Clones share the same metrics, so one `Prometheus` can be given to every
+/// route, and replied with from a scrape endpoint.
+///
+/// The following metrics are recorded, labeled with `route`, and where it
+/// makes sense, `method` and `status`:
+///
+/// - `http_requests_total`
+/// - `http_requests_in_flight`
+/// - `http_request_duration_seconds`
+/// - `http_request_size_bytes`
+/// - `http_response_size_bytes`
+///
+/// Rejections with `404 Not Found` or `405 Method Not Allowed` mean the
+/// request didn't match this route (another `or` branch may still handle it),
+/// so they are not counted. Methods other than the standard ones are labeled
+/// `other`, so clients can't add a series for each method they make up.
+#[derive(Clone, Debug, Default)]
+pub struct Prometheus {
+    registry: Arc<Mutex<Registry>>,
+}
+
+type Labels = (&'static str, &'static str, u16);
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: BTreeMap<Labels, u64>,
+    in_flight: BTreeMap<&'static str, i64>,
+    duration: BTreeMap<Labels, Histogram>,
+    request_size: BTreeMap<Labels, Histogram>,
+    response_size: BTreeMap<Labels, Histogram>,
+}
+
+#[derive(Debug)]
+struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Prometheus {
+    /// Create a new, empty, `Prometheus` recorder.
+    pub fn new() -> Prometheus {
+        Prometheus::default()
+    }
+
+    /// Render the current metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let registry = self.registry.lock().unwrap();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "http_requests_total",
+            "counter",
+            "Total number of HTTP requests.",
+        );
+        for (labels, count) in &registry.requests {
+            let _ = writeln!(
+                out,
+                "http_requests_total{} {}",
+                LabelFmt(labels, None),
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "http_requests_in_flight",
+            "gauge",
+            "Number of HTTP requests currently being served.",
+        );
+        for (route, count) in &registry.in_flight {
+            let _ = writeln!(
+                out,
+                "http_requests_in_flight{{route=\"{}\"}} {}",
+                Escape(route),
+                count
+            );
+        }
+
+        histograms(
+            &mut out,
+            "http_request_duration_seconds",
+            "HTTP request latency in seconds.",
+            &registry.duration,
+        );
+        histograms(
+            &mut out,
+            "http_request_size_bytes",
+            "HTTP request body size in bytes.",
+            &registry.request_size,
+        );
+        histograms(
+            &mut out,
+            "http_response_size_bytes",
+            "HTTP response body size in bytes.",
+            &registry.response_size,
+        );
+
+        out
+    }
+}
+
+impl Recorder for Prometheus {
+    fn started(&self, route: &'static str, _method: &Method) {
+        let mut registry = self.registry.lock().unwrap();
+        *registry.in_flight.entry(route).or_insert(0) += 1;
+    }
+
+    fn finished(&self, info: &Info<'_>) {
+        let mut registry = self.registry.lock().unwrap();
+        *registry.in_flight.entry(info.route).or_insert(0) -= 1;
+
+        let status = match info.status {
+            Some(StatusCode::NOT_FOUND) | Some(StatusCode::METHOD_NOT_ALLOWED) if info.rejected => {
+                return
+            }
+            Some(status) => status,
+            None => return,
+        };
+
+        let labels = (info.route, method_label(info.method), status.as_u16());
+        *registry.requests.entry(labels).or_insert(0) += 1;
+        registry
+            .duration
+            .entry(labels)
+            .or_insert_with(|| Histogram::new(DURATION_BUCKETS))
+            .observe(info.elapsed.as_secs_f64());
+        if let Some(size) = info.request_size {
+            registry
+                .request_size
+                .entry(labels)
+                .or_insert_with(|| Histogram::new(SIZE_BUCKETS))
+                .observe(size as f64);
+        }
+        if let Some(size) = info.response_size {
+            registry
+                .response_size
+                .entry(labels)
+                .or_insert_with(|| Histogram::new(SIZE_BUCKETS))
+                .observe(size as f64);
+        }
+    }
+}
+
+impl Reply for Prometheus {
+    fn into_response(self) -> Response {
+        let mut res = Response::new(self.render().into());
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        );
+        res
+    }
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Histogram {
+        Histogram {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (bound, count) in self.buckets.iter().zip(self.counts.iter_mut()) {
+            if value <= *bound {
+                *count += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn histograms(out: &mut String, name: &str, help: &str, map: &BTreeMap<Labels, Histogram>) {
+    header(out, name, "histogram", help);
+    for (labels, histogram) in map {
+        for (bound, count) in histogram.buckets.iter().zip(&histogram.counts) {
+            let le = bound.to_string();
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                name,
+                LabelFmt(labels, Some(&le)),
+                count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{} {}",
+            name,
+            LabelFmt(labels, Some("+Inf")),
+            histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "{}_sum{} {}",
+            name,
+            LabelFmt(labels, None),
+            histogram.sum
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{} {}",
+            name,
+            LabelFmt(labels, None),
+            histogram.count
+        );
+    }
+}
+
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::HEAD => "HEAD",
+        Method::OPTIONS => "OPTIONS",
+        Method::CONNECT => "CONNECT",
+        Method::PATCH => "PATCH",
+        Method::TRACE => "TRACE",
+        _ => "other",
+    }
+}
+
+struct LabelFmt<'a>(&'a Labels, Option<&'a str>);
+
+impl fmt::Display for LabelFmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (route, method, status) = self.0;
+        write!(
+            f,
+            "{{route=\"{}\",method=\"{}\",status=\"{}\"",
+            Escape(route),
+            Escape(method),
+            status
+        )?;
+        if let Some(le) = self.1 {
+            write!(f, ",le=\"{}\"", le)?;
+        }
+        f.write_str("}")
+    }
+}
+
+struct Escape<'a>(&'a str);
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+mod internal {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use std::time::Instant;
+
+    use futures::{ready, TryFuture};
+    use http::{header, Method, StatusCode};
+    use pin_project::pin_project;
+
+    use super::{Info, Metrics, Recorder};
+    use crate::filter::{Filter, FilterBase, Internal};
+    use crate::reject::IsReject;
+    use crate::reply::{Reply, Response};
+    use crate::route;
+    use crate::routes::RouteTable;
+
+    #[allow(missing_debug_implementations)]
+    pub struct Measured(pub(super) Response);
+
+    impl Reply for Measured {
+        #[inline]
+        fn into_response(self) -> Response {
+            self.0
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[derive(Clone, Copy)]
+    pub struct WithMetrics<R, F> {
+        pub(super) filter: F,
+        pub(super) metrics: Metrics<R>,
+    }
+
+    impl<R, F> FilterBase for WithMetrics<R, F>
+    where
+        R: Recorder + Clone,
+        F: Filter + Clone + Send,
+        F::Extract: Reply,
+        F::Error: IsReject,
+    {
+        type Extract = (Measured,);
+        type Error = F::Error;
+        type Future = WithMetricsFuture<R, F::Future>;
+
+        fn filter(&self, _: Internal) -> Self::Future {
+            let (method, request_size) = route::with(|route| {
+                let size = route
+                    .headers()
+                    .get(header::CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+                (route.method().clone(), size)
+            });
+            self.metrics.recorder.started(self.metrics.name, &method);
+
+            WithMetricsFuture {
+                guard: Guard {
+                    recorder: self.metrics.recorder.clone(),
+                    name: self.metrics.name,
+                    method,
+                    request_size,
+                    started: tokio::time::Instant::now().into_std(),
+                    finished: false,
+                },
+                future: self.filter.filter(Internal),
+            }
+        }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
+    }
+
+    // Reports the request as finished when dropped, if it didn't complete.
+    struct Guard<R: Recorder> {
+        recorder: R,
+        name: &'static str,
+        method: Method,
+        request_size: Option<u64>,
+        started: Instant,
+        finished: bool,
+    }
+
+    impl<R: Recorder> Guard<R> {
+        fn finish(&mut self, status: Option<StatusCode>, rejected: bool, size: Option<u64>) {
+            self.finished = true;
+            self.recorder.finished(&Info {
+                route: self.name,
+                method: &self.method,
+                status,
+                rejected,
+                elapsed: tokio::time::Instant::now().into_std() - self.started,
+                request_size: self.request_size,
+                response_size: size,
+            });
+        }
+    }
+
+    impl<R: Recorder> Drop for Guard<R> {
+        fn drop(&mut self) {
+            if !self.finished {
+                self.finish(None, false, None);
+            }
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[pin_project]
+    pub struct WithMetricsFuture<R: Recorder, F> {
+        guard: Guard<R>,
+        #[pin]
+        future: F,
+    }
+
+    impl<R, F> Future for WithMetricsFuture<R, F>
+    where
+        R: Recorder,
+        F: TryFuture,
+        F::Ok: Reply,
+        F::Error: IsReject,
+    {
+        type Output = Result<(Measured,), F::Error>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            let pin = self.project();
+            match ready!(pin.future.try_poll(cx)) {
+                Ok(reply) => {
+                    let resp = reply.into_response();
+                    let size = super::response_size(&resp);
+                    pin.guard.finish(Some(resp.status()), false, size);
+                    Poll::Ready(Ok((Measured(resp),)))
+                }
+                Err(reject) => {
+                    pin.guard.finish(Some(reject.status()), true, None);
+                    Poll::Ready(Err(reject))
+                }
+            }
+        }
+    }
+}
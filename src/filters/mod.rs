@@ -5,6 +5,7 @@
 
 pub mod addr;
 pub mod any;
+pub mod auth;
 pub mod body;
 #[cfg(feature = "compression")]
 pub mod compression;
@@ -16,11 +17,18 @@ pub mod header;
 pub mod host;
 pub mod log;
 pub mod method;
+pub mod metrics;
 #[cfg(feature = "multipart")]
 pub mod multipart;
 pub mod path;
+pub mod problem;
+pub mod proxy;
 pub mod query;
 pub mod reply;
+pub mod request_id;
+#[cfg(feature = "session")]
+pub mod session;
+pub mod shutdown;
 pub mod sse;
 pub mod trace;
 #[cfg(feature = "websocket")]
@@ -133,9 +133,10 @@ use futures::future;
 use http::uri::PathAndQuery;
 
 use self::internal::Opaque;
-use crate::filter::{filter_fn, one, Filter, FilterBase, Internal, One, Tuple};
+use crate::filter::{describe, filter_fn, one, Filter, FilterBase, Internal, One, Tuple};
 use crate::reject::{self, Rejection};
 use crate::route::{self, Route};
+use crate::routes::{type_name, RouteInfo, RouteTable, Segment};
 
 /// Create an exact match path segment `Filter`.
 ///
@@ -219,6 +220,10 @@ where
             }))
         })
     }
+
+    fn describe(&self, _: Internal) -> RouteTable {
+        RouteTable::any().map(|info| info.push(Segment::Exact(self.0.as_ref().to_owned())))
+    }
 }
 
 /// Matches the end of a route.
@@ -236,13 +241,14 @@ where
 ///     .map(|| "Hello, World!");
 /// ```
 pub fn end() -> impl Filter<Extract = (), Error = Rejection> + Copy {
-    filter_fn(move |route| {
+    let filter = filter_fn(move |route| {
         if route.path().is_empty() {
             future::ok(())
         } else {
             future::err(reject::not_found())
         }
-    })
+    });
+    describe(filter, RouteInfo::set_end)
 }
 
 /// Extract a parameter from a path segment.
@@ -265,12 +271,15 @@ pub fn end() -> impl Filter<Extract = (), Error = Rejection> + Copy {
 /// ```
 pub fn param<T: FromStr + Send + 'static>(
 ) -> impl Filter<Extract = One<T>, Error = Rejection> + Copy {
-    filter_segment(|seg| {
+    let filter = filter_segment(|seg| {
         tracing::trace!("param?: {:?}", seg);
         if seg.is_empty() {
             return Err(reject::not_found());
         }
         T::from_str(seg).map(one).map_err(|_| reject::not_found())
+    });
+    describe(filter, |info: &mut RouteInfo| {
+        info.push(Segment::Param(type_name::<T>()))
     })
 }
 
@@ -292,7 +301,7 @@ pub fn param<T: FromStr + Send + 'static>(
 ///     });
 /// ```
 pub fn tail() -> impl Filter<Extract = One<Tail>, Error = Infallible> + Copy {
-    filter_fn(move |route| {
+    let filter = filter_fn(move |route| {
         let path = path_and_query(&route);
         let idx = route.matched_path_index();
 
@@ -305,7 +314,8 @@ pub fn tail() -> impl Filter<Extract = One<Tail>, Error = Infallible> + Copy {
             path,
             start_index: idx,
         }))
-    })
+    });
+    describe(filter, |info: &mut RouteInfo| info.push(Segment::Tail))
 }
 
 /// Represents the tail part of a request path, returned by the [`tail()`] filter.
@@ -0,0 +1,589 @@
+//! Problem Details
+//!
+//! Render rejections as [RFC 7807](https://tools.ietf.org/html/rfc7807)
+//! `application/problem+json` responses, instead of the default plain text.
+//!
+//! Every built-in rejection is rendered, and custom rejections can take part
+//! by implementing [`Problem`] and being registered with
+//! [`Problems::custom`].
+//!
+//! # Example
+//!
+//! ```
+//! use warp::http::StatusCode;
+//! use warp::problem::{Details, Problem};
+//! use warp::Filter;
+//!
+//! #[derive(Debug)]
+//! struct OutOfCredit {
+//!     balance: u32,
+//! }
+//!
+//! impl warp::reject::Reject for OutOfCredit {}
+//!
+//! impl Problem for OutOfCredit {
+//!     fn details(&self) -> Details {
+//!         Details::new(StatusCode::FORBIDDEN)
+//!             .with_type("https://example.com/probs/out-of-credit")
+//!             .with_title("You do not have enough credit.")
+//!             .with_extension("balance", self.balance)
+//!     }
+//! }
+//!
+//! let route = warp::path("buy")
+//!     .and_then(|| async { Err::<String, _>(warp::reject::custom(OutOfCredit { balance: 30 })) });
+//!
+//! let problems = warp::problem::json().custom::<OutOfCredit>();
+//!
+//! // Either for some routes...
+//! let api = route.with(problems.clone());
+//!
+//! // Or for the whole server.
+//! let server = warp::serve(route).problems(problems);
+//! ```
+//!
+//! Wrapped routes pass on `404 Not Found` and `405 Method Not Allowed`
+//! rejections, so that routes combined with them using
+//! [`or`](crate::Filter::or) are still tried. Only a server renders those.
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::StatusCode;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::filter::{Filter, Internal, WrapSealed};
+use crate::reject::{IsReject, Known, Preferred, Reject, Rejection};
+use crate::reply::{Reply, Response};
+
+use self::internal::WithProblems;
+
+/// The `content-type` of problem details.
+pub const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
+/// Create a wrapping filter that renders rejections as problem details.
+///
+/// Requests that don't accept JSON still get the default response.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let route = warp::path("hello")
+///     .map(warp::reply)
+///     .with(warp::problem::json());
+/// ```
+pub fn json() -> Problems {
+    Problems {
+        customs: Arc::new(Vec::new()),
+        include_values: false,
+    }
+}
+
+type CustomFn = fn(&dyn Any) -> Option<Details>;
+
+/// Renders rejections as `application/problem+json`.
+///
+/// Use [`json`] to create one, and either wrap routes with it, or install it
+/// on a [`Server`](crate::Server) with
+/// [`Server::problems`](crate::Server::problems).
+///
+/// Wrapped routes still reject with `404 Not Found` and `405 Method Not
+/// Allowed`, since another route could match the request.
+#[derive(Clone)]
+pub struct Problems {
+    customs: Arc<Vec<CustomFn>>,
+    include_values: bool,
+}
+
+impl Problems {
+    /// Render custom rejections of type `T` with their [`Problem`] details.
+    ///
+    /// Custom rejections that aren't registered are rendered with their
+    /// [`Reject::status`], without any detail.
+    pub fn custom<T: Problem>(mut self) -> Self {
+        fn details<T: Problem>(any: &dyn Any) -> Option<Details> {
+            any.downcast_ref::<T>().map(Problem::details)
+        }
+        Arc::make_mut(&mut self.customs).push(details::<T>);
+        self
+    }
+
+    /// Include the raw value that failed to decode in the details of query
+    /// and header rejections, as a `value` member.
+    ///
+    /// This is off by default, since values can contain data clients
+    /// shouldn't see echoed back, such as in logs of proxies. Sensitive header
+    /// values are never included.
+    pub fn include_values(mut self, include: bool) -> Self {
+        self.include_values = include;
+        self
+    }
+
+    // Wraps the whole filter of a server, rendering every rejection.
+    pub(crate) fn serve<F: Filter>(&self, filter: F) -> WithProblems<F> {
+        WithProblems {
+            routes: Some(Arc::new(filter.describe(Internal))),
+            filter,
+            problems: self.clone(),
+        }
+    }
+
+    pub(crate) fn render(&self, accept: Option<&HeaderValue>, rejection: &Rejection) -> Response {
+        if !accepts_json(accept) {
+            return rejection.into_response();
+        }
+
+        let mut res = self.details(rejection).into_response();
+        rejection.insert_headers(&mut res);
+        res
+    }
+
+    fn details(&self, rejection: &Rejection) -> Details {
+        match rejection.preferred() {
+            Preferred::NotFound => Details::new(StatusCode::NOT_FOUND),
+            Preferred::Known(known) => self.known(known),
+            Preferred::Custom(cause) => self
+                .customs
+                .iter()
+                .find_map(|details| details(cause.as_any()))
+                .unwrap_or_else(|| {
+                    let status = cause.status();
+                    if status.is_server_error() {
+                        tracing::error!(
+                            "unhandled custom rejection, returning {} response: {:?}",
+                            status.as_u16(),
+                            cause
+                        );
+                    }
+                    Details::new(status)
+                }),
+        }
+    }
+
+    fn known(&self, known: &Known) -> Details {
+        use crate::body::BodyDeserializeError;
+        use crate::reject::{InvalidHeader, InvalidQuery, MissingCookie, MissingHeader};
+
+        let mut details = Details::new(known.status());
+        if let Some(detail) = known_detail(known) {
+            details = details.with_detail(detail);
+        }
+
+        let any = known.inner_as_any();
+        if let Some(e) = any.downcast_ref::<InvalidQuery>().and_then(|e| e.error()) {
+            if let Some(field) = e.field() {
+                details = details.with_extension("field", field);
+            }
+            if let Some(expected) = e.expected() {
+                details = details.with_extension("expected", expected);
+            }
+            if let Some(value) = e.value().filter(|_| self.include_values) {
+                details = details.with_extension("value", value);
+            }
+        } else if let Some(e) = any.downcast_ref::<InvalidHeader>() {
+            details = details.with_extension("header", e.name());
+            // Only types are expected: exact header values aren't kept, since
+            // they could be secrets.
+            if let Some(expected) = e.expected() {
+                details = details.with_extension("expected", expected);
+            }
+            if let Some(value) = e.value().filter(|_| self.include_values) {
+                details = details.with_extension("value", value);
+            }
+        } else if let Some(e) = any.downcast_ref::<MissingHeader>() {
+            details = details.with_extension("header", e.name());
+        } else if let Some(e) = any.downcast_ref::<MissingCookie>() {
+            details = details.with_extension("cookie", e.name());
+        } else if let Some(e) = any.downcast_ref::<BodyDeserializeError>() {
+            if let Some(field) = e.field() {
+                details = details.with_extension("field", field);
+            }
+            if let (Some(line), Some(column)) = (e.line(), e.column()) {
+                details = details
+                    .with_extension("line", line)
+                    .with_extension("column", column);
+            }
+        }
+        details
+    }
+}
+
+// Describes a known rejection by its kind only. The messages of some, such
+// as body errors, contain what the client sent, and those of server errors
+// can have details that are none of the client's business, like file paths.
+fn known_detail(known: &Known) -> Option<String> {
+    match known {
+        Known::InvalidQuery(_) => Some("Invalid query string".to_owned()),
+        Known::BodyReadError(_) => Some("Request body read error".to_owned()),
+        Known::BodyDeserializeError(_) => Some("Request body deserialize error".to_owned()),
+        // These messages are fixed, or only name what was missing or invalid.
+        Known::MethodNotAllowed(_)
+        | Known::InvalidHeader(_)
+        | Known::MissingHeader(_)
+        | Known::MissingCookie(_)
+        | Known::LengthRequired(_)
+        | Known::PayloadTooLarge(_)
+        | Known::UnsupportedMediaType(_)
+        | Known::CorsForbidden(_)
+        | Known::Unauthorized(_) => Some(known.to_string()),
+        #[cfg(feature = "websocket")]
+        Known::MissingConnectionUpgrade(_) => Some(known.to_string()),
+        _ => None,
+    }
+}
+
+impl fmt::Debug for Problems {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Problems")
+            .field("customs", &self.customs.len())
+            .field("include_values", &self.include_values)
+            .finish()
+    }
+}
+
+impl<F> WrapSealed<F> for Problems
+where
+    F: Filter + Clone + Send,
+    F::Extract: Reply,
+    F::Error: IsReject + Into<Rejection>,
+{
+    type Wrapped = WithProblems<F>;
+
+    fn wrap(&self, filter: F) -> Self::Wrapped {
+        WithProblems {
+            filter,
+            problems: self.clone(),
+            routes: None,
+        }
+    }
+}
+
+/// A custom rejection that can be rendered as problem details.
+///
+/// Register the type with [`Problems::custom`] for it to be used.
+pub trait Problem: Reject {
+    /// The problem details describing this rejection.
+    fn details(&self) -> Details;
+}
+
+/// The details of a problem, as described by RFC 7807.
+///
+/// This is also a [`Reply`], so it can be returned from handlers and
+/// [`recover`](crate::Filter::recover) functions directly.
+#[derive(Clone, Debug)]
+pub struct Details {
+    type_: String,
+    title: String,
+    status: StatusCode,
+    detail: Option<String>,
+    instance: Option<String>,
+    extensions: Map<String, Value>,
+}
+
+impl Details {
+    /// Create details for a status code.
+    ///
+    /// The type is `about:blank`, and the title is the status code's
+    /// canonical reason, such as "Not Found".
+    pub fn new(status: StatusCode) -> Details {
+        Details {
+            type_: "about:blank".to_owned(),
+            title: status.canonical_reason().unwrap_or("Unknown").to_owned(),
+            status,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Set the URI identifying the problem type.
+    pub fn with_type(mut self, type_: impl Into<String>) -> Self {
+        self.type_ = type_.into();
+        self
+    }
+
+    /// Set a short, human-readable summary of the problem type.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Set a human-readable explanation of this occurrence of the problem.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set a URI identifying this occurrence of the problem.
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member.
+    ///
+    /// # Panics
+    ///
+    /// If the value can't be serialized to JSON.
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).expect("extension must serialize to JSON");
+        self.extensions.insert(name.into(), value);
+        self
+    }
+
+    /// The URI identifying the problem type.
+    pub fn type_uri(&self) -> &str {
+        &self.type_
+    }
+
+    /// The short summary of the problem type.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The status code.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The explanation of this occurrence of the problem, if any.
+    pub fn detail(&self) -> Option<&str> {
+        self.detail.as_deref()
+    }
+
+    /// The URI identifying this occurrence of the problem, if any.
+    pub fn instance(&self) -> Option<&str> {
+        self.instance.as_deref()
+    }
+
+    /// An extension member, if set.
+    pub fn extension(&self, name: &str) -> Option<&Value> {
+        self.extensions.get(name)
+    }
+
+    /// The details as a JSON object.
+    ///
+    /// Extension members never replace the standard ones.
+    pub fn to_json(&self) -> Value {
+        let mut json = self.extensions.clone();
+        json.insert("type".to_owned(), self.type_.clone().into());
+        json.insert("title".to_owned(), self.title.clone().into());
+        json.insert("status".to_owned(), self.status.as_u16().into());
+        if let Some(ref detail) = self.detail {
+            json.insert("detail".to_owned(), detail.clone().into());
+        }
+        if let Some(ref instance) = self.instance {
+            json.insert("instance".to_owned(), instance.clone().into());
+        }
+        Value::Object(json)
+    }
+}
+
+impl Reply for Details {
+    fn into_response(self) -> Response {
+        let body = self.to_json().to_string();
+        let mut res = Response::new(body.into());
+        *res.status_mut() = self.status;
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+        );
+        res
+    }
+}
+
+// Whether problem details are at least as acceptable as plain text.
+//
+// Clients that don't send an `Accept` header get problem details too, since
+// that is what an API client not caring about it expects.
+fn accepts_json(accept: Option<&HeaderValue>) -> bool {
+    let accept = match accept.and_then(|v| v.to_str().ok()) {
+        Some(accept) => accept,
+        None => return true,
+    };
+    let json =
+        quality(accept, "application", "problem+json").max(quality(accept, "application", "json"));
+    let text = quality(accept, "text", "plain");
+    json > 0 && json >= text
+}
+
+// The quality, in thousandths, the most specific matching media range in an
+// `Accept` header gives `type/subtype`.
+fn quality(accept: &str, type_: &str, subtype: &str) -> u16 {
+    let mut best = None;
+    for range in accept.split(',') {
+        let mut params = range.split(';');
+        let media = params.next().unwrap_or("").trim();
+        let (range_type, range_subtype) = match media.find('/') {
+            Some(idx) => (&media[..idx], &media[idx + 1..]),
+            None => continue,
+        };
+        let specificity = if range_type.eq_ignore_ascii_case(type_)
+            && range_subtype.eq_ignore_ascii_case(subtype)
+        {
+            2
+        } else if range_type.eq_ignore_ascii_case(type_) && range_subtype == "*" {
+            1
+        } else if range_type == "*" && range_subtype == "*" {
+            0
+        } else {
+            continue;
+        };
+        let q = params
+            .filter_map(|param| {
+                let param = param.trim();
+                param
+                    .strip_prefix("q=")
+                    .or_else(|| param.strip_prefix("Q="))
+            })
+            .next()
+            .and_then(|q| q.trim().parse::<f32>().ok())
+            .map(|q| (q.clamp(0.0, 1.0) * 1000.0) as u16)
+            .unwrap_or(1000);
+        match best {
+            Some((s, _)) if s >= specificity => (),
+            _ => best = Some((specificity, q)),
+        }
+    }
+    best.map(|(_, q)| q).unwrap_or(0)
+}
+
+pub(crate) mod internal {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use futures::{ready, TryFuture};
+    use http::header::{HeaderValue, ACCEPT};
+    use pin_project::pin_project;
+
+    use super::Problems;
+    use crate::filter::{Filter, FilterBase, Internal};
+    use crate::reject::{IsReject, Known, Preferred, Rejection};
+    use crate::reply::{Reply, Response};
+    use crate::route;
+    use crate::routes::RouteTable;
+
+    #[allow(missing_debug_implementations)]
+    pub struct Rendered(Response);
+
+    impl Reply for Rendered {
+        #[inline]
+        fn into_response(self) -> Response {
+            self.0
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[derive(Clone)]
+    pub struct WithProblems<F> {
+        pub(super) filter: F,
+        pub(super) problems: Problems,
+        // The routes of the server, when rejections that other routes could
+        // still handle are rendered too, which only the server does.
+        pub(super) routes: Option<Arc<RouteTable>>,
+    }
+
+    impl<F> FilterBase for WithProblems<F>
+    where
+        F: Filter + Clone + Send,
+        F::Extract: Reply,
+        F::Error: IsReject + Into<Rejection>,
+    {
+        type Extract = (Rendered,);
+        type Error = Rejection;
+        type Future = WithProblemsFuture<F::Future>;
+
+        fn filter(&self, _: Internal) -> Self::Future {
+            let accept = route::with(|route| route.headers().get(ACCEPT).cloned());
+            WithProblemsFuture {
+                accept,
+                problems: self.problems.clone(),
+                routes: self.routes.clone(),
+                future: self.filter.filter(Internal),
+            }
+        }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[pin_project]
+    pub struct WithProblemsFuture<F> {
+        accept: Option<HeaderValue>,
+        problems: Problems,
+        routes: Option<Arc<RouteTable>>,
+        #[pin]
+        future: F,
+    }
+
+    impl<F> Future for WithProblemsFuture<F>
+    where
+        F: TryFuture,
+        F::Ok: Reply,
+        F::Error: IsReject + Into<Rejection>,
+    {
+        type Output = Result<(Rendered,), Rejection>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            let pin = self.project();
+            let res = match ready!(pin.future.try_poll(cx)) {
+                Ok(reply) => reply.into_response(),
+                Err(reject) => {
+                    let rejection: Rejection = reject.into();
+                    let rejection = match pin.routes {
+                        Some(routes) => {
+                            route::with(|route| rejection.route_methods(routes, route.full_path()))
+                        }
+                        None => {
+                            let unmatched = matches!(
+                                rejection.preferred(),
+                                Preferred::NotFound | Preferred::Known(Known::MethodNotAllowed(_))
+                            );
+                            if unmatched {
+                                return Poll::Ready(Err(rejection));
+                            }
+                            rejection
+                        }
+                    };
+                    tracing::debug!("rejected: {:?}", rejection);
+                    pin.problems.render(pin.accept.as_ref(), &rejection)
+                }
+            };
+            Poll::Ready(Ok((Rendered(res),)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accepts_json, quality};
+    use http::header::HeaderValue;
+
+    #[test]
+    fn accept() {
+        assert_eq!(quality("application/json", "application", "json"), 1000);
+        assert_eq!(
+            quality("application/*;q=0.5, */*;q=0.1", "application", "json"),
+            500
+        );
+        assert_eq!(quality("text/html", "application", "json"), 0);
+
+        let accepts = |s: &'static str| accepts_json(Some(&HeaderValue::from_static(s)));
+        assert!(accepts_json(None));
+        assert!(accepts("*/*"));
+        assert!(accepts("application/problem+json"));
+        assert!(accepts("text/plain;q=0.5, application/json"));
+        assert!(!accepts("text/plain"));
+        assert!(!accepts("text/html"));
+        assert!(!accepts("application/json;q=0.2, text/*"));
+    }
+}
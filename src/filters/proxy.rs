@@ -0,0 +1,492 @@
+//! Trusted proxy filters
+//!
+//! When a server runs behind a reverse proxy or load balancer, the connection
+//! comes from the proxy, not the client. Proxies describe the original
+//! request in the `Forwarded` header ([RFC 7239]), or the older
+//! `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host` headers.
+//!
+//! Since anyone can send those headers, they are only believed when they come
+//! from a proxy that is trusted, and only the kind that proxy sets is read.
+//!
+//! [RFC 7239]: https://tools.ietf.org/html/rfc7239
+//!
+//! # Example
+//!
+//! ```
+//! use std::net::SocketAddr;
+//! use warp::Filter;
+//!
+//! let route = warp::addr::remote()
+//!     .map(|addr: Option<SocketAddr>| format!("hello, {:?}", addr))
+//!     .with(warp::log("example::api"))
+//!     .with(warp::proxy::trusted(["10.0.0.0/8", "127.0.0.1"]));
+//! ```
+
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use http::header::{HeaderMap, FORWARDED};
+use http::uri::{Authority, Scheme};
+
+use crate::filter::{Filter, WrapSealed};
+use crate::route::Route;
+
+use self::internal::WithTrusted;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
+const X_FORWARDED_HOST: &str = "x-forwarded-host";
+
+/// Create a wrapping filter that trusts proxies in the given networks.
+///
+/// Each network is a CIDR, such as `10.0.0.0/8` or `fd00::/8`, or a single
+/// address.
+///
+/// If the connection comes from a trusted proxy, the `Forwarded` header is
+/// read, or the `X-Forwarded-*` headers with
+/// [`x_forwarded`](Trusted::x_forwarded). The chain of
+/// proxies is walked from the nearest one, skipping trusted hops, and the
+/// first untrusted hop is taken to be the client. Its address, scheme and host
+/// are then seen by:
+///
+/// - [`warp::addr::remote`](crate::addr::remote),
+/// - [`warp::host::optional`](crate::host::optional), and the filters built
+///   on it,
+/// - [`log::Info`](crate::log::Info) and [`trace::Info`](crate::trace::Info).
+///
+/// Hops that don't say where they came from, such as `for=unknown`, end the
+/// walk, since nothing behind them can be checked, and the remote address is
+/// left as the connection's. Addresses sent without a port are given port
+/// `0`.
+///
+/// This should wrap every other filter, including `warp::log` and
+/// `warp::trace`, so that it applies before they look at the request.
+///
+/// The proxies must set or strip the headers that are read, since clients
+/// can send them too. Headers of the other kind are ignored, as a proxy only
+/// setting one kind passes along whatever clients sent of the other.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let route = warp::any()
+///     .map(warp::reply)
+///     .with(warp::trace::request())
+///     .with(warp::proxy::trusted(["10.0.0.0/8", "::1"]));
+/// ```
+///
+/// # Panics
+///
+/// Panics if any of the networks isn't a valid CIDR or address.
+pub fn trusted<I>(cidrs: I) -> Trusted
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let networks = cidrs
+        .into_iter()
+        .map(|cidr| {
+            let cidr = cidr.as_ref();
+            Cidr::parse(cidr).unwrap_or_else(|| panic!("invalid CIDR: {:?}", cidr))
+        })
+        .collect();
+    Trusted {
+        networks: Arc::new(networks),
+        header: Header::Forwarded,
+    }
+}
+
+/// Decorates a [`Filter`](crate::Filter) to believe forwarding headers from
+/// trusted proxies.
+#[derive(Clone)]
+pub struct Trusted {
+    networks: Arc<Vec<Cidr>>,
+    header: Header,
+}
+
+// The headers the trusted proxies describe requests with.
+#[derive(Clone, Copy, Debug)]
+enum Header {
+    Forwarded,
+    XForwarded,
+}
+
+impl Trusted {
+    /// Read the `Forwarded` header, ignoring `X-Forwarded-*`.
+    ///
+    /// This is the default.
+    pub fn forwarded(mut self) -> Self {
+        self.header = Header::Forwarded;
+        self
+    }
+
+    /// Read the `X-Forwarded-For`, `X-Forwarded-Proto` and `X-Forwarded-Host`
+    /// headers, ignoring `Forwarded`.
+    pub fn x_forwarded(mut self) -> Self {
+        self.header = Header::XForwarded;
+        self
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|net| net.contains(ip))
+    }
+
+    fn apply(&self, route: &mut Route) {
+        // Only the outermost of nested wrappers gets to look at the headers.
+        if route.is_forwarded() {
+            return;
+        }
+        match route.remote_addr() {
+            Some(peer) if self.contains(peer.ip()) => (),
+            _ => return,
+        }
+
+        let hops = match self.header {
+            Header::Forwarded => forwarded(route.headers()),
+            Header::XForwarded => x_forwarded(route.headers()),
+        };
+        let hop = match self.client(&hops) {
+            Some(hop) => hop,
+            None => return,
+        };
+
+        let scheme = hop
+            .proto
+            .as_deref()
+            .and_then(|proto| Scheme::from_str(proto).ok());
+        let host = hop
+            .host
+            .as_deref()
+            .and_then(|host| Authority::from_str(host).ok());
+        route.set_forwarded(hop.addr, scheme, host);
+    }
+
+    // Walks the hops from the nearest proxy, returning the client's.
+    fn client<'a>(&self, hops: &'a [Hop]) -> Option<&'a Hop> {
+        let mut client = None;
+        for hop in hops.iter().rev() {
+            client = Some(hop);
+            match hop.addr {
+                Some(addr) if self.contains(addr.ip()) => continue,
+                _ => break,
+            }
+        }
+        client
+    }
+}
+
+impl fmt::Debug for Trusted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Trusted")
+            .field("networks", &self.networks)
+            .field("header", &self.header)
+            .finish()
+    }
+}
+
+impl<F> WrapSealed<F> for Trusted
+where
+    F: Filter + Clone + Send,
+{
+    type Wrapped = WithTrusted<F>;
+
+    fn wrap(&self, filter: F) -> Self::Wrapped {
+        WithTrusted {
+            filter,
+            trusted: self.clone(),
+        }
+    }
+}
+
+/// A network of addresses.
+#[derive(Clone, Copy)]
+pub(crate) struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub(crate) fn parse(s: &str) -> Option<Cidr> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = canonical(addr.trim().parse().ok()?);
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse().ok().filter(|&p| p <= max)?,
+            None => max,
+        };
+        Some(Cidr { addr, prefix })
+    }
+
+    pub(crate) fn contains(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+// Dual-stack sockets report IPv4 peers as IPv4-mapped IPv6 addresses.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => ip,
+        },
+        ip => ip,
+    }
+}
+
+/// What one proxy said about where a request came from.
+#[derive(Debug, Default, PartialEq)]
+struct Hop {
+    addr: Option<SocketAddr>,
+    proto: Option<String>,
+    host: Option<String>,
+}
+
+// Reads the hops from `X-Forwarded-*`, client first.
+fn x_forwarded(headers: &HeaderMap) -> Vec<Hop> {
+    let list = |name: &str| -> Vec<String> {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|value| value.trim().to_owned())
+            .collect()
+    };
+    let fors = list(X_FORWARDED_FOR);
+    let protos = list(X_FORWARDED_PROTO);
+    let hosts = list(X_FORWARDED_HOST);
+
+    // The other lists are usually a single value, set by the nearest proxy,
+    // but some proxies append to them like `X-Forwarded-For`.
+    let aligned = |list: &[String], i: usize| -> Option<String> {
+        if list.len() == fors.len() {
+            list.get(i).cloned()
+        } else {
+            list.last().cloned()
+        }
+    };
+
+    fors.iter()
+        .enumerate()
+        .map(|(i, node)| Hop {
+            addr: node_addr(node),
+            proto: aligned(&protos, i),
+            host: aligned(&hosts, i),
+        })
+        .collect()
+}
+
+// Reads the hops from `Forwarded`, client first.
+fn forwarded(headers: &HeaderMap) -> Vec<Hop> {
+    let mut hops = Vec::new();
+    for value in headers.get_all(FORWARDED) {
+        let value = match value.to_str() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        for element in split_unquoted(value, ',') {
+            let mut hop = Hop::default();
+            for pair in split_unquoted(element, ';') {
+                let (key, value) = match pair.split_once('=') {
+                    Some((key, value)) => (key.trim(), unquote(value.trim())),
+                    None => continue,
+                };
+                if key.eq_ignore_ascii_case("for") {
+                    hop.addr = node_addr(&value);
+                } else if key.eq_ignore_ascii_case("proto") {
+                    hop.proto = Some(value);
+                } else if key.eq_ignore_ascii_case("host") {
+                    hop.host = Some(value);
+                }
+            }
+            hops.push(hop);
+        }
+    }
+    hops
+}
+
+// Splits on `sep`, except inside quoted strings.
+fn split_unquoted(s: &str, sep: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (i, c) in s.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == sep && !quoted {
+            parts.push(&s[start..i]);
+            start = i + 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts.into_iter().filter(|part| !part.trim().is_empty())
+}
+
+fn unquote(s: &str) -> String {
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => {
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => out.extend(chars.next()),
+                    c => out.push(c),
+                }
+            }
+            out
+        }
+        None => s.to_owned(),
+    }
+}
+
+// Parses a node: `192.0.2.1`, `192.0.2.1:80`, `[2001:db8::1]:80` or a bare
+// IPv6 address. Anything else, like `unknown` or `_hidden`, is `None`.
+fn node_addr(node: &str) -> Option<SocketAddr> {
+    if let Ok(addr) = node.parse::<SocketAddr>() {
+        return Some(SocketAddr::new(canonical(addr.ip()), addr.port()));
+    }
+    let ip = node
+        .strip_prefix('[')
+        .and_then(|node| node.strip_suffix(']'))
+        .unwrap_or(node);
+    ip.parse().ok().map(|ip| SocketAddr::new(canonical(ip), 0))
+}
+
+mod internal {
+    use super::Trusted;
+    use crate::filter::{Filter, FilterBase, Internal};
+    use crate::route;
+    use crate::routes::RouteTable;
+
+    #[allow(missing_debug_implementations)]
+    #[derive(Clone)]
+    pub struct WithTrusted<F> {
+        pub(super) filter: F,
+        pub(super) trusted: Trusted,
+    }
+
+    impl<F> FilterBase for WithTrusted<F>
+    where
+        F: Filter + Clone + Send,
+    {
+        type Extract = F::Extract;
+        type Error = F::Error;
+        type Future = F::Future;
+
+        fn filter(&self, _: Internal) -> Self::Future {
+            route::with(|route| self.trusted.apply(route));
+            self.filter.filter(Internal)
+        }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cidrs() {
+        let net = Cidr::parse("10.0.0.0/8").unwrap();
+        assert!(net.contains("10.1.2.3".parse().unwrap()));
+        assert!(!net.contains("11.0.0.1".parse().unwrap()));
+        assert!(!net.contains("::1".parse().unwrap()));
+
+        let net = Cidr::parse("fd00::/8").unwrap();
+        assert!(net.contains("fd12::1".parse().unwrap()));
+        assert!(!net.contains("fe80::1".parse().unwrap()));
+
+        assert!(Cidr::parse("0.0.0.0/0")
+            .unwrap()
+            .contains("8.8.8.8".parse().unwrap()));
+        assert!(Cidr::parse("::ffff:127.0.0.1")
+            .unwrap()
+            .contains("127.0.0.1".parse().unwrap()));
+        assert!(Cidr::parse("10.0.0.0/33").is_none());
+        assert!(Cidr::parse("localhost").is_none());
+    }
+
+    #[test]
+    fn forwarded_elements() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            FORWARDED,
+            r#"for=192.0.2.60;proto=https;host="example.com", For="[2001:db8:cafe::17]:4711""#
+                .parse()
+                .unwrap(),
+        );
+        headers.append(FORWARDED, "for=unknown;by=10.0.0.1".parse().unwrap());
+
+        let hops = forwarded(&headers);
+        assert_eq!(
+            hops,
+            vec![
+                Hop {
+                    addr: Some("192.0.2.60:0".parse().unwrap()),
+                    proto: Some("https".into()),
+                    host: Some("example.com".into()),
+                },
+                Hop {
+                    addr: Some("[2001:db8:cafe::17]:4711".parse().unwrap()),
+                    ..Hop::default()
+                },
+                Hop::default(),
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes() {
+        assert_eq!(node_addr("1.2.3.4"), Some("1.2.3.4:0".parse().unwrap()));
+        assert_eq!(node_addr("1.2.3.4:80"), Some("1.2.3.4:80".parse().unwrap()));
+        assert_eq!(
+            node_addr("2001:db8::1"),
+            Some("[2001:db8::1]:0".parse().unwrap())
+        );
+        assert_eq!(
+            node_addr("[2001:db8::1]"),
+            Some("[2001:db8::1]:0".parse().unwrap())
+        );
+        assert_eq!(node_addr("unknown"), None);
+        assert_eq!(node_addr("_hidden"), None);
+    }
+}
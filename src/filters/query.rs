@@ -1,15 +1,23 @@
 //! Query Filters
 
+use std::error::Error as StdError;
+use std::fmt;
+
 use futures::future;
 use serde::de::DeserializeOwned;
 use serde_urlencoded;
 
-use crate::filter::{filter_fn_one, Filter, One};
+use crate::filter::{describe, filter_fn_one, Filter, One};
+use crate::filters::body::field_path;
 use crate::reject::{self, Rejection};
+use crate::routes::RouteInfo;
 
 /// Creates a `Filter` that decodes query parameters to the type `T`.
 ///
-/// If cannot decode into a `T`, the request is rejected with a `400 Bad Request`.
+/// If cannot decode into a `T`, the request is rejected with a `400 Bad Request`,
+/// and the rejection's [`InvalidQuery::error`](crate::reject::InvalidQuery::error)
+/// names the field that failed. Only [`structured`] also knows what the field
+/// was expected to be and its value.
 ///
 /// # Example
 ///
@@ -64,20 +72,146 @@ use crate::reject::{self, Rejection};
 /// [Serde]: https://docs.rs/serde
 pub fn query<T: DeserializeOwned + Send + 'static>(
 ) -> impl Filter<Extract = One<T>, Error = Rejection> + Copy {
-    filter_fn_one(|route| {
+    let filter = filter_fn_one(|route| {
         let query_string = route.query().unwrap_or_else(|| {
             tracing::debug!("route was called without a query string, defaulting to empty");
             ""
         });
 
-        let query_encoded = serde_urlencoded::from_str(query_string).map_err(|e| {
+        let de =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query_string.as_bytes()));
+        let query_encoded = serde_path_to_error::deserialize(de).map_err(|e| {
             tracing::debug!("failed to decode query string '{}': {:?}", query_string, e);
-            reject::invalid_query()
+            let field = field_path(e.path());
+            reject::invalid_query_error(QueryError::new(field, e.into_inner().to_string()))
         });
         future::ready(query_encoded)
-    })
+    });
+    describe(filter, RouteInfo::query::<T>)
+}
+
+/// Creates a `Filter` that decodes query parameters to the type `T`, with
+/// support for repeated keys and bracketed nesting.
+///
+/// Unlike [`query`](query()), this understands:
+///
+/// - repeated keys as sequences: `?tag=a&tag=b` or `?tag[]=a&tag[]=b`,
+/// - indexed sequences: `?tag[0]=a&tag[1]=b`,
+/// - brackets as nested maps and structs: `?filter[status]=open`.
+///
+/// Queries with more than 1000 parameters, or keys nested more than 32
+/// levels deep, are refused.
+///
+/// If cannot decode into a `T`, the request is rejected with a `400 Bad Request`,
+/// and the rejection's [`InvalidQuery::error`](crate::reject::InvalidQuery::error)
+/// names the field that failed.
+///
+/// # Example
+///
+/// ```
+/// use serde_derive::Deserialize;
+/// use warp::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Search {
+///     tag: Vec<String>,
+///     filter: StatusFilter,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct StatusFilter {
+///     status: String,
+/// }
+///
+/// // GET /search?tag=a&tag=b&filter[status]=open
+/// let route = warp::path("search")
+///     .and(warp::query::structured::<Search>())
+///     .map(|search: Search| {
+///         format!("{} tags, status {}", search.tag.len(), search.filter.status)
+///     });
+/// ```
+pub fn structured<T: DeserializeOwned + Send + 'static>(
+) -> impl Filter<Extract = One<T>, Error = Rejection> + Copy {
+    let filter = filter_fn_one(|route| {
+        let query_string = route.query().unwrap_or("");
+
+        let query_decoded = from_str(query_string).map_err(|e| {
+            tracing::debug!("failed to decode query string '{}': {}", query_string, e);
+            reject::invalid_query_error(e)
+        });
+        future::ready(query_decoded)
+    });
+    describe(filter, RouteInfo::query::<T>)
 }
 
+/// Decodes a query string into a `T`, the same way as [`structured`] does.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let map: HashMap<String, Vec<u32>> = warp::query::from_str("a=1&a=2").unwrap();
+/// assert_eq!(map["a"], [1, 2]);
+///
+/// let err = warp::query::from_str::<HashMap<String, Vec<u32>>>("a=1&a=x").unwrap_err();
+/// assert_eq!(err.field(), Some("a[1]"));
+/// ```
+pub fn from_str<T: DeserializeOwned>(query: &str) -> Result<T, QueryError> {
+    de::from_str(query)
+}
+
+/// An error decoding a query string.
+#[derive(Debug)]
+pub struct QueryError {
+    field: Option<String>,
+    message: String,
+    expected: Option<String>,
+    value: Option<String>,
+}
+
+impl QueryError {
+    fn new(field: Option<String>, message: impl Into<String>) -> QueryError {
+        QueryError {
+            field,
+            message: message.into(),
+            expected: None,
+            value: None,
+        }
+    }
+
+    /// The field that failed to decode, such as `filter[status]`, if known.
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    /// A description of what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// What the field was expected to be, such as `u32`, if known.
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    /// The raw value that failed to decode, if known.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.field {
+            Some(ref field) => write!(f, "{}: {}", field, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl StdError for QueryError {}
+
 /// Creates a `Filter` that returns the raw query string as type String.
 pub fn raw() -> impl Filter<Extract = One<String>, Error = Rejection> + Copy {
     filter_fn_one(|route| {
@@ -89,3 +223,5 @@ pub fn raw() -> impl Filter<Extract = One<String>, Error = Rejection> + Copy {
         future::ready(route)
     })
 }
+
+mod de;
@@ -0,0 +1,608 @@
+//! A query string `Deserializer`, supporting repeated keys and brackets.
+//!
+//! The query is first parsed into a tree of `Node`s, which then drives the
+//! deserialization:
+//!
+//! - `tag=a&tag=b` and `tag[]=a&tag[]=b` give `tag` the values `a` and `b`,
+//! - `filter[status]=open` nests `status` into `filter`,
+//! - `item[0]=a&item[1]=b` is a sequence, ordered by index.
+//!
+//! Queries with more than `MAX_PARAMS` parameters, or keys nested deeper than
+//! `MAX_DEPTH`, are refused, to bound the work and stack a request can cost.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use percent_encoding::percent_decode_str;
+use serde::de::{
+    self, DeserializeSeed, Deserializer, Expected, IntoDeserializer, MapAccess, SeqAccess,
+    Unexpected, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use super::QueryError;
+
+// The most parameters a query may have.
+const MAX_PARAMS: usize = 1000;
+// The most levels a key may have, counting the first: `a[b][c]` has three.
+const MAX_DEPTH: usize = 32;
+
+pub(super) fn from_str<T: de::DeserializeOwned>(query: &str) -> Result<T, QueryError> {
+    let root = parse(query)?;
+    T::deserialize(NodeDe {
+        node: &root,
+        path: String::new(),
+    })
+}
+
+#[derive(Debug)]
+enum Node {
+    Values(Vec<String>),
+    Map(Map),
+}
+
+// Entries in the order their keys first appeared.
+#[derive(Debug, Default)]
+struct Map {
+    entries: Vec<(String, Node)>,
+    index: HashMap<String, usize>,
+}
+
+fn parse(query: &str) -> Result<Node, QueryError> {
+    let mut root = Map::default();
+    for (i, pair) in query.split('&').filter(|p| !p.is_empty()).enumerate() {
+        if i == MAX_PARAMS {
+            return Err(QueryError::new(
+                None,
+                format!("has more than {} parameters", MAX_PARAMS),
+            ));
+        }
+        let (key, value) = match pair.find('=') {
+            Some(idx) => (&pair[..idx], &pair[idx + 1..]),
+            None => (pair, ""),
+        };
+        let key = decode(key);
+        let value = decode(value);
+        let keys = split_key(&key);
+        if keys.len() > MAX_DEPTH {
+            return Err(QueryError::new(
+                Some(keys[0].to_owned()),
+                format!("is nested more than {} levels deep", MAX_DEPTH),
+            ));
+        }
+        insert(&mut root, &keys, value, &mut String::new())?;
+    }
+    Ok(Node::Map(root))
+}
+
+fn decode(s: &str) -> String {
+    let s = s.replace('+', " ");
+    percent_decode_str(&s).decode_utf8_lossy().into_owned()
+}
+
+// Splits `a[b][c]` into `a`, `b` and `c`. A trailing `[]` is dropped, since
+// appending is what repeated keys do anyway. Keys with unbalanced brackets
+// are taken literally.
+fn split_key(key: &str) -> Vec<&str> {
+    let start = match key.find('[') {
+        Some(0) | None => return vec![key],
+        Some(start) => start,
+    };
+    let mut parts = vec![&key[..start]];
+    let mut rest = &key[start..];
+    while !rest.is_empty() {
+        match (rest.strip_prefix('['), rest.find(']')) {
+            (Some(inner), Some(end)) if !inner[..end - 1].contains('[') => {
+                parts.push(&inner[..end - 1]);
+                rest = &rest[end + 1..];
+            }
+            _ => return vec![key],
+        }
+    }
+    if parts.last() == Some(&"") {
+        parts.pop();
+    }
+    parts
+}
+
+fn insert(
+    map: &mut Map,
+    keys: &[&str],
+    value: String,
+    path: &mut String,
+) -> Result<(), QueryError> {
+    let (key, rest) = keys.split_first().expect("keys are never empty");
+    push_path(path, key);
+
+    let idx = match map.index.get(*key) {
+        Some(&idx) => idx,
+        None => {
+            let node = if rest.is_empty() {
+                Node::Values(Vec::new())
+            } else {
+                Node::Map(Map::default())
+            };
+            map.entries.push(((*key).to_owned(), node));
+            map.index.insert((*key).to_owned(), map.entries.len() - 1);
+            map.entries.len() - 1
+        }
+    };
+    match (&mut map.entries[idx].1, rest.is_empty()) {
+        (Node::Values(values), true) => {
+            values.push(value);
+            Ok(())
+        }
+        (Node::Map(map), false) => insert(map, rest, value, path),
+        _ => Err(QueryError::new(
+            Some(path.clone()),
+            "is given both a value and nested keys",
+        )),
+    }
+}
+
+fn push_path(path: &mut String, key: &str) {
+    if path.is_empty() {
+        path.push_str(key);
+    } else {
+        path.push('[');
+        path.push_str(key);
+        path.push(']');
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    let mut path = path.to_owned();
+    push_path(&mut path, key);
+    path
+}
+
+// Errors from deserializing a value are about the field it is in, unless a
+// nested field already claimed them.
+fn at<T>(result: Result<T, QueryError>, path: &str) -> Result<T, QueryError> {
+    result.map_err(|mut err| {
+        if err.field.is_none() && !path.is_empty() {
+            err.field = Some(path.to_owned());
+        }
+        err
+    })
+}
+
+struct NodeDe<'a> {
+    node: &'a Node,
+    path: String,
+}
+
+impl<'de, 'a> Deserializer<'de> for NodeDe<'a> {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        match self.node {
+            Node::Values(values) if values.len() == 1 => {
+                ValueDe::new(&values[0]).deserialize_any(visitor)
+            }
+            Node::Values(_) => self.deserialize_seq(visitor),
+            Node::Map(map) => visitor.visit_map(Entries {
+                iter: map.entries.iter(),
+                value: None,
+                path: &self.path,
+            }),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        match self.node {
+            Node::Values(values) => visitor.visit_seq(Items {
+                iter: values.iter(),
+                index: 0,
+                path: &self.path,
+            }),
+            Node::Map(map) => {
+                let mut indexed = map
+                    .entries
+                    .iter()
+                    .map(|(k, v)| k.parse::<usize>().map(|i| (i, v)))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| de::Error::invalid_type(Unexpected::Map, &visitor))?;
+                indexed.sort_by_key(|(i, _)| *i);
+                visitor.visit_seq(Indexed {
+                    iter: indexed.into_iter(),
+                    path: &self.path,
+                })
+            }
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        match self.node {
+            Node::Map(_) => self.deserialize_any(visitor),
+            Node::Values(_) => Err(de::Error::invalid_type(
+                Unexpected::Str("a value"),
+                &visitor,
+            )),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        visitor.visit_unit()
+    }
+
+    // Everything else is a single value. If a key is repeated, the last
+    // value wins.
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_bool(visitor)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_i8(visitor)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_i16(visitor)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_i32(visitor)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_i64(visitor)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_u8(visitor)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_u16(visitor)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_u32(visitor)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_u64(visitor)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_f32(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_f64(visitor)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_char(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_str(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_string(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_identifier(visitor)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_i128(visitor)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        self.single()?.deserialize_u128(visitor)
+    }
+}
+
+impl<'a> NodeDe<'a> {
+    fn single(&self) -> Result<ValueDe<'_>, QueryError> {
+        match self.node {
+            Node::Values(values) => {
+                let value = values.last().map(String::as_str).unwrap_or("");
+                Ok(ValueDe::new(value))
+            }
+            Node::Map(_) => Err(QueryError::new(
+                Some(self.path.clone()),
+                "expected a value, found nested keys",
+            )),
+        }
+    }
+}
+
+// A single string value, parsed into whatever is asked for.
+struct ValueDe<'a> {
+    value: &'a str,
+}
+
+impl<'a> ValueDe<'a> {
+    fn new(value: &'a str) -> ValueDe<'a> {
+        ValueDe { value }
+    }
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+            let value = self
+                .value
+                .parse()
+                .map_err(|_| de::Error::invalid_value(Unexpected::Str(self.value), &visitor))?;
+            visitor.$visit(value)
+        }
+        )*
+    };
+}
+
+impl<'de, 'a> Deserializer<'de> for ValueDe<'a> {
+    type Error = QueryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        visitor.visit_str(self.value)
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, QueryError> {
+        visitor.visit_enum(self.value.into_deserializer())
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, QueryError> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct Entries<'a> {
+    iter: std::slice::Iter<'a, (String, Node)>,
+    value: Option<(&'a String, &'a Node)>,
+    path: &'a str,
+}
+
+impl<'de, 'a> MapAccess<'de> for Entries<'a> {
+    type Error = QueryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, QueryError> {
+        match self.iter.next() {
+            Some((key, node)) => {
+                self.value = Some((key, node));
+                let path = child_path(self.path, key);
+                at(seed.deserialize(ValueDe::new(key)), &path).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, QueryError> {
+        let (key, node) = self
+            .value
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        let path = child_path(self.path, key);
+        let result = seed.deserialize(NodeDe {
+            node,
+            path: path.clone(),
+        });
+        at(result, &path)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct Items<'a> {
+    iter: std::slice::Iter<'a, String>,
+    index: usize,
+    path: &'a str,
+}
+
+impl<'de, 'a> SeqAccess<'de> for Items<'a> {
+    type Error = QueryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, QueryError> {
+        match self.iter.next() {
+            Some(value) => {
+                let path = child_path(self.path, &self.index.to_string());
+                self.index += 1;
+                at(seed.deserialize(ValueDe::new(value)), &path).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct Indexed<'a> {
+    iter: std::vec::IntoIter<(usize, &'a Node)>,
+    path: &'a str,
+}
+
+impl<'de, 'a> SeqAccess<'de> for Indexed<'a> {
+    type Error = QueryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, QueryError> {
+        match self.iter.next() {
+            Some((index, node)) => {
+                let path = child_path(self.path, &index.to_string());
+                let result = seed.deserialize(NodeDe {
+                    node,
+                    path: path.clone(),
+                });
+                at(result, &path).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+impl de::Error for QueryError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        QueryError::new(None, msg.to_string())
+    }
+
+    fn invalid_type(unexp: Unexpected<'_>, exp: &dyn Expected) -> Self {
+        QueryError {
+            expected: Some(exp.to_string()),
+            value: unexpected_str(&unexp),
+            ..QueryError::custom(format_args!("invalid type: {}, expected {}", unexp, exp))
+        }
+    }
+
+    fn invalid_value(unexp: Unexpected<'_>, exp: &dyn Expected) -> Self {
+        QueryError {
+            expected: Some(exp.to_string()),
+            value: unexpected_str(&unexp),
+            ..QueryError::custom(format_args!("invalid value: {}, expected {}", unexp, exp))
+        }
+    }
+}
+
+// Every value in a query string starts out as a string.
+fn unexpected_str(unexp: &Unexpected<'_>) -> Option<String> {
+    match *unexp {
+        Unexpected::Str(value) => Some(value.to_owned()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_key;
+
+    #[test]
+    fn split_keys() {
+        assert_eq!(split_key("a"), ["a"]);
+        assert_eq!(split_key("a[]"), ["a"]);
+        assert_eq!(split_key("a[b][c]"), ["a", "b", "c"]);
+        assert_eq!(split_key("a[0]"), ["a", "0"]);
+        assert_eq!(split_key("a[b"), ["a[b"]);
+        assert_eq!(split_key("a[b]c"), ["a[b]c"]);
+        assert_eq!(split_key("[a]"), ["[a]"]);
+    }
+}
@@ -0,0 +1,277 @@
+//! Request ID Filters
+//!
+//! Assign a correlation ID to every request, so that log lines, spans and
+//! responses belonging to the same request can be tied together.
+//!
+//! # Example
+//!
+//! ```
+//! use warp::Filter;
+//! use warp::request_id::RequestId;
+//!
+//! let route = warp::any()
+//!     .and(warp::ext::get::<RequestId>())
+//!     .map(|id: RequestId| format!("your request is {}", id))
+//!     .with(warp::log("example::api"))
+//!     .with(warp::request_id());
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use http::header::{HeaderName, HeaderValue};
+
+use crate::filter::{Filter, WrapSealed};
+use crate::reject::IsReject;
+use crate::reply::Reply;
+use crate::route::Route;
+
+use self::internal::WithRequestId;
+
+/// The default header used to read and echo the request ID.
+pub const X_REQUEST_ID: &str = "x-request-id";
+
+/// Create a wrapping filter that assigns a request ID to every request.
+///
+/// The ID is read from the `x-request-id` header if the client (or a proxy in
+/// front of the server) sent one, otherwise a new random UUID is generated.
+///
+/// The [`RequestId`](RequestId) is then:
+///
+/// - stored in the request extensions, so it can be extracted with
+///   [`warp::ext::get`](crate::ext::get),
+/// - available from [`log::Info`](crate::log::Info::request_id) and
+///   [`trace::Info`](crate::trace::Info::request_id),
+/// - echoed back in the same header of the response.
+///
+/// To have the ID show up in the `warp::trace` span, this should wrap the
+/// `trace` filter, since the span is created before the inner filter runs.
+///
+/// # Note
+///
+/// The header is only echoed if the inner filter returns a
+/// [`Reply`](crate::Reply). If the request is rejected, the rejection is
+/// passed through unchanged.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let route = warp::any()
+///     .map(warp::reply)
+///     .with(warp::trace::request())
+///     .with(warp::request_id());
+/// ```
+pub fn request_id() -> Assign {
+    Assign {
+        header: HeaderName::from_static(X_REQUEST_ID),
+    }
+}
+
+/// Create a wrapping filter that assigns a request ID, using a custom header
+/// instead of `x-request-id`.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let route = warp::any()
+///     .map(warp::reply)
+///     .with(warp::request_id::header("x-correlation-id"));
+/// ```
+///
+/// # Panics
+///
+/// Panics if `name` is not a valid header name.
+pub fn header<K>(name: K) -> Assign
+where
+    HeaderName: TryFrom<K>,
+{
+    let header = HeaderName::try_from(name)
+        .map_err(|_| ())
+        .expect("invalid header name");
+    Assign { header }
+}
+
+/// Decorates a [`Filter`](crate::Filter) to assign a request ID.
+#[derive(Clone, Debug)]
+pub struct Assign {
+    header: HeaderName,
+}
+
+/// The ID assigned to a request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RequestId(HeaderValue);
+
+impl RequestId {
+    /// View this ID as a string.
+    pub fn as_str(&self) -> &str {
+        // Only constructed from values that passed `to_str`.
+        self.0.to_str().unwrap_or_default()
+    }
+
+    fn from_request(route: &Route, header: &HeaderName) -> RequestId {
+        route
+            .headers()
+            .get(header)
+            .filter(|value| !value.is_empty() && value.to_str().is_ok())
+            .map(|value| RequestId(value.clone()))
+            .unwrap_or_else(RequestId::generate)
+    }
+
+    /// Generates a random (version 4) UUID.
+    ///
+    /// The IDs only need to be unique, not unguessable, so the randomly
+    /// seeded `RandomState` from std is enough, and avoids pulling in an RNG.
+    fn generate() -> RequestId {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+
+        let state = RandomState::new();
+        let half = |salt: u8| state.hash_one((count, now, salt));
+        let hi = (half(0) & !0xF000) | 0x4000;
+        let lo = (half(1) & !(0b11 << 62)) | (0b10 << 62);
+
+        let uuid = format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            hi >> 32,
+            (hi >> 16) & 0xFFFF,
+            hi & 0xFFFF,
+            lo >> 48,
+            lo & 0xFFFF_FFFF_FFFF,
+        );
+        RequestId(HeaderValue::from_str(&uuid).expect("uuid is a valid header value"))
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub(crate) fn from_route(route: &Route) -> Option<&str> {
+    route.extensions().get::<RequestId>().map(RequestId::as_str)
+}
+
+impl<F> WrapSealed<F> for Assign
+where
+    F: Filter + Clone + Send,
+    F::Extract: Reply,
+    F::Error: IsReject,
+{
+    type Wrapped = WithRequestId<F>;
+
+    fn wrap(&self, filter: F) -> Self::Wrapped {
+        WithRequestId {
+            filter,
+            assign: self.clone(),
+        }
+    }
+}
+
+mod internal {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::{ready, TryFuture};
+    use http::header::HeaderName;
+    use pin_project::pin_project;
+
+    use super::{Assign, RequestId};
+    use crate::filter::{Filter, FilterBase, Internal};
+    use crate::reject::IsReject;
+    use crate::reply::{Reply, Response};
+    use crate::route;
+    use crate::routes::RouteTable;
+
+    #[allow(missing_debug_implementations)]
+    pub struct Identified(pub(super) Response);
+
+    impl Reply for Identified {
+        #[inline]
+        fn into_response(self) -> Response {
+            self.0
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[derive(Clone)]
+    pub struct WithRequestId<F> {
+        pub(super) filter: F,
+        pub(super) assign: Assign,
+    }
+
+    impl<F> FilterBase for WithRequestId<F>
+    where
+        F: Filter + Clone + Send,
+        F::Extract: Reply,
+        F::Error: IsReject,
+    {
+        type Extract = (Identified,);
+        type Error = F::Error;
+        type Future = WithRequestIdFuture<F::Future>;
+
+        fn filter(&self, _: Internal) -> Self::Future {
+            let header = &self.assign.header;
+            let id = route::with(|route| {
+                let id = RequestId::from_request(route, header);
+                route.extensions_mut().insert(id.clone());
+                id
+            });
+
+            WithRequestIdFuture {
+                header: header.clone(),
+                id,
+                future: self.filter.filter(Internal),
+            }
+        }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[pin_project]
+    pub struct WithRequestIdFuture<F> {
+        header: HeaderName,
+        id: RequestId,
+        #[pin]
+        future: F,
+    }
+
+    impl<F> Future for WithRequestIdFuture<F>
+    where
+        F: TryFuture,
+        F::Ok: Reply,
+        F::Error: IsReject,
+    {
+        type Output = Result<(Identified,), F::Error>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            let pin = self.project();
+            match ready!(pin.future.try_poll(cx)) {
+                Ok(reply) => {
+                    let mut resp = reply.into_response();
+                    resp.headers_mut()
+                        .insert(pin.header.clone(), pin.id.0.clone());
+                    Poll::Ready(Ok((Identified(resp),)))
+                }
+                Err(reject) => Poll::Ready(Err(reject)),
+            }
+        }
+    }
+}
@@ -0,0 +1,662 @@
+//! Session Filters
+//!
+//! Keeps data about a client across requests, such as who is logged in.
+//!
+//! The data lives in a [`SessionStore`](SessionStore), and the client only
+//! gets a random session ID, in a cookie. The [`sessions`](sessions) wrapper
+//! loads the session before the wrapped filter runs, and saves it once a
+//! reply has been produced. In between, handlers read and change it through
+//! the [`Session`](Session) handle, extracted with [`session`](session()).
+//!
+//! Requires the `session` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//! use warp::session::{MemoryStore, Session};
+//! use warp::Filter;
+//!
+//! let store = MemoryStore::new(Duration::from_secs(60 * 60));
+//!
+//! let login = warp::path!("login")
+//!     .and(warp::post())
+//!     .and(warp::session::session())
+//!     .map(|session: Session| {
+//!         // A new ID after logging in, so an ID planted before can't be used.
+//!         session.regenerate();
+//!         session.insert("user", "alice").unwrap();
+//!         "welcome"
+//!     });
+//!
+//! let whoami = warp::path!("whoami")
+//!     .and(warp::session::session())
+//!     .map(|session: Session| {
+//!         session.get::<String>("user").unwrap_or_else(|| "nobody".into())
+//!     });
+//!
+//! let routes = login.or(whoami).with(warp::session::sessions(store));
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use futures::future;
+use headers::{Cookie, HeaderMapExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::time::Instant;
+
+use crate::filter::{Filter, WrapSealed};
+use crate::reject::{self, Rejection};
+use crate::reply::Reply;
+use crate::route::Route;
+
+use self::internal::WithSessions;
+
+/// The default name of the session ID cookie.
+pub const DEFAULT_COOKIE_NAME: &str = "session_id";
+
+/// The data of a session.
+pub type SessionData = HashMap<String, Value>;
+
+/// An error from a [`SessionStore`](SessionStore).
+pub type StoreError = Box<dyn StdError + Send + Sync>;
+
+/// The future returned by the methods of a [`SessionStore`](SessionStore).
+pub type StoreFuture<T> = Pin<Box<dyn Future<Output = Result<T, StoreError>> + Send>>;
+
+/// Create a wrapping filter that loads and saves sessions.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use warp::Filter;
+///
+/// let store = warp::session::MemoryStore::new(Duration::from_secs(600));
+///
+/// let route = warp::any()
+///     .map(warp::reply)
+///     .with(warp::session::sessions(store).cookie_name("sid").secure(false));
+/// ```
+pub fn sessions<S: SessionStore>(store: S) -> Sessions<S> {
+    Sessions {
+        store: Arc::new(store),
+        config: Arc::new(Config {
+            cookie_name: DEFAULT_COOKIE_NAME,
+            secure: true,
+            max_age: None,
+        }),
+    }
+}
+
+/// Extract the [`Session`](Session) of the request.
+///
+/// This requires the route to be wrapped with [`sessions`](sessions), and
+/// rejects with a [`MissingExtension`](crate::ext::MissingExtension)
+/// otherwise.
+pub fn session() -> impl Filter<Extract = (Session,), Error = Rejection> + Copy {
+    crate::ext::get::<Session>()
+}
+
+/// Storage for the data of sessions.
+///
+/// Sessions are identified by random IDs, which are safe to use as keys.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Load the data of a session, or `None` if it doesn't exist (anymore).
+    fn load(&self, id: &str) -> StoreFuture<Option<SessionData>>;
+
+    /// Create or replace the data of a session.
+    fn save(&self, id: &str, data: SessionData) -> StoreFuture<()>;
+
+    /// Delete a session.
+    fn destroy(&self, id: &str) -> StoreFuture<()>;
+}
+
+/// Decorates a [`Filter`](crate::Filter) to load and save sessions.
+pub struct Sessions<S> {
+    store: Arc<S>,
+    config: Arc<Config>,
+}
+
+#[derive(Clone, Debug)]
+struct Config {
+    cookie_name: &'static str,
+    secure: bool,
+    max_age: Option<Duration>,
+}
+
+/// The session of a request.
+///
+/// Changes are saved to the store after the reply is produced. If the
+/// request is rejected instead, changes are dropped.
+///
+/// Clones refer to the same session.
+#[derive(Clone)]
+pub struct Session {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // `None` until the session is saved for the first time.
+    id: Option<String>,
+    data: SessionData,
+    changed: bool,
+    regenerate: bool,
+    destroy: bool,
+}
+
+/// A [`SessionStore`](SessionStore) keeping sessions in memory.
+///
+/// Sessions expire after not being used for `ttl`, as loading one keeps it
+/// for another `ttl`. They are lost when the server restarts, and aren't
+/// shared between servers.
+#[derive(Clone, Debug)]
+pub struct MemoryStore {
+    inner: Arc<Mutex<Memory>>,
+    ttl: Duration,
+}
+
+#[derive(Debug)]
+struct Memory {
+    sessions: HashMap<String, (SessionData, Instant)>,
+    // When to next look for expired sessions, so saving only does so once
+    // per `ttl` instead of every time.
+    sweep_at: Instant,
+}
+
+/// The session store failed to load or save a session.
+#[derive(Debug)]
+pub struct SessionStoreError {
+    source: StoreError,
+}
+
+impl<S> Sessions<S> {
+    /// Set the name of the session ID cookie.
+    ///
+    /// Defaults to `session_id`.
+    pub fn cookie_name(mut self, name: &'static str) -> Self {
+        self.config_mut().cookie_name = name;
+        self
+    }
+
+    /// Set whether the cookie is only sent over HTTPS.
+    ///
+    /// Defaults to `true`.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.config_mut().secure = secure;
+        self
+    }
+
+    /// Make the cookie persist for `max_age`, instead of until the browser
+    /// is closed.
+    ///
+    /// This doesn't affect how long the store keeps the session.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.config_mut().max_age = Some(max_age);
+        self
+    }
+
+    fn config_mut(&mut self) -> &mut Config {
+        Arc::make_mut(&mut self.config)
+    }
+}
+
+impl<S> Clone for Sessions<S> {
+    fn clone(&self) -> Self {
+        Sessions {
+            store: self.store.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S> fmt::Debug for Sessions<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sessions")
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<F, S> WrapSealed<F> for Sessions<S>
+where
+    F: Filter + Clone + Send,
+    F::Extract: Reply,
+    F::Error: Into<Rejection>,
+    S: SessionStore,
+{
+    type Wrapped = WithSessions<F, S>;
+
+    fn wrap(&self, filter: F) -> Self::Wrapped {
+        WithSessions {
+            filter,
+            sessions: self.clone(),
+        }
+    }
+}
+
+impl Config {
+    fn id_from_request(&self, route: &Route) -> Option<String> {
+        route
+            .headers()
+            .typed_get::<Cookie>()
+            .and_then(|cookie| cookie.get(self.cookie_name).map(String::from))
+            .filter(|id| !id.is_empty())
+    }
+
+    fn set_cookie(&self, id: &str) -> String {
+        let mut cookie = ::cookie::Cookie::build((self.cookie_name, id.to_owned()))
+            .path("/")
+            .http_only(true)
+            .same_site(::cookie::SameSite::Lax)
+            .secure(self.secure);
+        if let Some(max_age) = self.max_age {
+            let secs = i64::try_from(max_age.as_secs()).unwrap_or(i64::MAX);
+            cookie = cookie.max_age(::cookie::time::Duration::seconds(secs));
+        }
+        cookie.to_string()
+    }
+
+    fn remove_cookie(&self) -> String {
+        ::cookie::Cookie::build(self.cookie_name)
+            .path("/")
+            .http_only(true)
+            .removal()
+            .to_string()
+    }
+}
+
+impl Session {
+    fn new(id: Option<String>, data: SessionData) -> Session {
+        Session {
+            state: Arc::new(Mutex::new(State {
+                id,
+                data,
+                ..State::default()
+            })),
+        }
+    }
+
+    /// The ID of this session, or `None` if it is new and hasn't been saved.
+    pub fn id(&self) -> Option<String> {
+        self.lock().id.clone()
+    }
+
+    /// Get a value from this session.
+    ///
+    /// Returns `None` if the key doesn't exist, or the value can't be
+    /// deserialized into a `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().data.get(key).cloned()?;
+        serde_json::from_value(value).ok()
+    }
+
+    /// Insert a value into this session.
+    pub fn insert<T: Serialize>(&self, key: &str, value: T) -> Result<(), serde_json::Error> {
+        let value = serde_json::to_value(value)?;
+        let mut state = self.lock();
+        state.data.insert(key.to_owned(), value);
+        state.changed = true;
+        Ok(())
+    }
+
+    /// Remove a value from this session.
+    pub fn remove(&self, key: &str) {
+        let mut state = self.lock();
+        if state.data.remove(key).is_some() {
+            state.changed = true;
+        }
+    }
+
+    /// Remove all values from this session.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.data.clear();
+        state.changed = true;
+    }
+
+    /// Move this session to a new ID, keeping its data.
+    ///
+    /// This should be called whenever the privileges of a session change,
+    /// such as when logging in, so that an attacker who planted a session ID
+    /// in the client can't use it afterwards (session fixation).
+    pub fn regenerate(&self) {
+        let mut state = self.lock();
+        state.regenerate = true;
+        state.changed = true;
+    }
+
+    /// Delete this session from the store, and the cookie from the client.
+    pub fn destroy(&self) {
+        let mut state = self.lock();
+        state.data.clear();
+        state.destroy = true;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    // Saves this session, returning the `Set-Cookie` header, if any.
+    fn persist<S: SessionStore>(
+        &self,
+        sessions: &Sessions<S>,
+    ) -> impl Future<Output = Result<Option<String>, StoreError>> + Send {
+        let mut state = self.lock();
+        let old_id = state.id.clone();
+        let store = sessions.store.clone();
+        let config = sessions.config.clone();
+
+        let (destroy, save, cookie) = if state.destroy {
+            state.id = None;
+            let cookie = old_id.as_ref().map(|_| config.remove_cookie());
+            (old_id, None, cookie)
+        } else if state.changed && (state.regenerate || old_id.is_none()) {
+            let id = generate_id();
+            state.id = Some(id.clone());
+            let cookie = Some(config.set_cookie(&id));
+            (old_id, Some((id, state.data.clone())), cookie)
+        } else if state.changed {
+            (None, old_id.map(|id| (id, state.data.clone())), None)
+        } else {
+            (None, None, None)
+        };
+        state.changed = false;
+        state.regenerate = false;
+        state.destroy = false;
+        drop(state);
+
+        async move {
+            if let Some(id) = destroy {
+                store.destroy(&id).await?;
+            }
+            if let Some((id, data)) = save {
+                store.save(&id, data).await?;
+            }
+            Ok(cookie)
+        }
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("Session")
+            .field("keys", &state.data.keys())
+            .finish()
+    }
+}
+
+impl MemoryStore {
+    /// Create a store keeping sessions for `ttl` after they were last loaded
+    /// or saved.
+    pub fn new(ttl: Duration) -> MemoryStore {
+        MemoryStore {
+            inner: Arc::new(Mutex::new(Memory {
+                sessions: HashMap::new(),
+                sweep_at: deadline(Instant::now(), ttl),
+            })),
+            ttl,
+        }
+    }
+
+    /// The number of sessions in the store, including expired ones that
+    /// haven't been removed yet.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().sessions.len()
+    }
+
+    /// Returns whether the store has no sessions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> StoreFuture<Option<SessionData>> {
+        let now = Instant::now();
+        let sessions = &mut self.inner.lock().unwrap().sessions;
+        let data = match sessions.get_mut(id) {
+            Some((_, expires)) if *expires <= now => {
+                sessions.remove(id);
+                None
+            }
+            Some((data, expires)) => {
+                *expires = deadline(now, self.ttl);
+                Some(data.clone())
+            }
+            None => None,
+        };
+        Box::pin(future::ok(data))
+    }
+
+    fn save(&self, id: &str, data: SessionData) -> StoreFuture<()> {
+        let now = Instant::now();
+        let mut memory = self.inner.lock().unwrap();
+        // Saving is a good time to get rid of expired sessions, so they
+        // don't pile up, but going through all of them every time would make
+        // each save slower the more sessions there are.
+        if memory.sweep_at <= now {
+            memory.sessions.retain(|_, (_, expires)| *expires > now);
+            memory.sweep_at = deadline(now, self.ttl);
+        }
+        let expires = deadline(now, self.ttl);
+        memory.sessions.insert(id.to_owned(), (data, expires));
+        Box::pin(future::ok(()))
+    }
+
+    fn destroy(&self, id: &str) -> StoreFuture<()> {
+        self.inner.lock().unwrap().sessions.remove(id);
+        Box::pin(future::ok(()))
+    }
+}
+
+// A `ttl` too long to add to `now` keeps sessions for about 30 years, as
+// long as a timer in tokio can wait.
+fn deadline(now: Instant, ttl: Duration) -> Instant {
+    now.checked_add(ttl)
+        .unwrap_or_else(|| now + Duration::from_secs(86400 * 365 * 30))
+}
+
+impl<S: SessionStore + ?Sized> SessionStore for Arc<S> {
+    fn load(&self, id: &str) -> StoreFuture<Option<SessionData>> {
+        (**self).load(id)
+    }
+
+    fn save(&self, id: &str, data: SessionData) -> StoreFuture<()> {
+        (**self).save(id, data)
+    }
+
+    fn destroy(&self, id: &str) -> StoreFuture<()> {
+        (**self).destroy(id)
+    }
+}
+
+impl fmt::Display for SessionStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Session store error: {}", self.source)
+    }
+}
+
+impl StdError for SessionStoreError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&*self.source)
+    }
+}
+
+fn store_error(source: StoreError) -> Rejection {
+    tracing::error!("session store error: {}", source);
+    reject::known(SessionStoreError { source })
+}
+
+// 256 bits from the OS random number generator, hex encoded.
+fn generate_id() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).expect("failed to generate session ID");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+mod internal {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::{ready, FutureExt, TryFuture};
+    use http::header::{HeaderValue, SET_COOKIE};
+    use pin_project::pin_project;
+
+    use super::{Session, SessionData, SessionStore, Sessions, StoreError};
+    use crate::filter::{Filter, FilterBase, Internal};
+    use crate::reject::Rejection;
+    use crate::reply::{Reply, Response};
+    use crate::route;
+    use crate::routes::RouteTable;
+
+    #[allow(missing_debug_implementations)]
+    pub struct Persisted(Response);
+
+    impl Reply for Persisted {
+        #[inline]
+        fn into_response(self) -> Response {
+            self.0
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    pub struct WithSessions<F, S> {
+        pub(super) filter: F,
+        pub(super) sessions: Sessions<S>,
+    }
+
+    impl<F: Clone, S> Clone for WithSessions<F, S> {
+        fn clone(&self) -> Self {
+            WithSessions {
+                filter: self.filter.clone(),
+                sessions: self.sessions.clone(),
+            }
+        }
+    }
+
+    impl<F, S> FilterBase for WithSessions<F, S>
+    where
+        F: Filter + Clone + Send,
+        F::Extract: Reply,
+        F::Error: Into<Rejection>,
+        S: SessionStore,
+    {
+        type Extract = (Persisted,);
+        type Error = Rejection;
+        type Future = WithSessionsFuture<F, S>;
+
+        fn filter(&self, _: Internal) -> Self::Future {
+            let state = match route::with(|route| self.sessions.config.id_from_request(route)) {
+                Some(id) => {
+                    let load = self.sessions.store.load(&id);
+                    State::Loading { id, load }
+                }
+                None => start(&self.filter, Session::new(None, SessionData::new())),
+            };
+
+            WithSessionsFuture {
+                filter: self.filter.clone(),
+                sessions: self.sessions.clone(),
+                state,
+            }
+        }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
+    }
+
+    #[allow(missing_debug_implementations)]
+    #[pin_project]
+    pub struct WithSessionsFuture<F: FilterBase, S> {
+        filter: F,
+        sessions: Sessions<S>,
+        #[pin]
+        state: State<F::Future>,
+    }
+
+    type Persist = Pin<Box<dyn Future<Output = Result<Option<String>, StoreError>> + Send>>;
+
+    #[pin_project(project = StateProj)]
+    enum State<F> {
+        Loading {
+            id: String,
+            load: super::StoreFuture<Option<SessionData>>,
+        },
+        Filtering {
+            session: Session,
+            #[pin]
+            future: F,
+        },
+        Persisting {
+            persist: Persist,
+            res: Option<Response>,
+        },
+    }
+
+    fn start<F: Filter>(filter: &F, session: Session) -> State<F::Future> {
+        route::with(|route| route.extensions_mut().insert(session.clone()));
+        State::Filtering {
+            session,
+            future: filter.filter(Internal),
+        }
+    }
+
+    impl<F, S> Future for WithSessionsFuture<F, S>
+    where
+        F: Filter + Clone + Send,
+        F::Extract: Reply,
+        F::Error: Into<Rejection>,
+        S: SessionStore,
+    {
+        type Output = Result<(Persisted,), Rejection>;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+            let mut pin = self.project();
+            loop {
+                let next = match pin.state.as_mut().project() {
+                    StateProj::Loading { id, load } => {
+                        let data = ready!(load.poll_unpin(cx)).map_err(super::store_error)?;
+                        // An unknown ID (such as an expired one) isn't reused,
+                        // a new one is generated when the session is saved.
+                        let session = match data {
+                            Some(data) => Session::new(Some(id.clone()), data),
+                            None => Session::new(None, SessionData::new()),
+                        };
+                        start(pin.filter, session)
+                    }
+                    StateProj::Filtering { session, future } => {
+                        let reply = ready!(future.try_poll(cx)).map_err(Into::into)?;
+                        let persist = session.persist(pin.sessions).boxed();
+                        State::Persisting {
+                            persist,
+                            res: Some(reply.into_response()),
+                        }
+                    }
+                    StateProj::Persisting { persist, res } => {
+                        let cookie = ready!(persist.poll_unpin(cx)).map_err(super::store_error)?;
+                        let mut res = res.take().expect("polled after complete");
+                        if let Some(cookie) = cookie.and_then(|c| HeaderValue::from_str(&c).ok()) {
+                            res.headers_mut().append(SET_COOKIE, cookie);
+                        }
+                        return Poll::Ready(Ok((Persisted(res),)));
+                    }
+                };
+                pin.state.set(next);
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Graceful Shutdown
+//!
+//! When the signal given to a `bind_with_graceful_shutdown` method completes,
+//! the server stops accepting connections and waits for the open ones to
+//! finish. Connections that never finish on their own, such as server-sent
+//! event streams and WebSockets, can hold that up indefinitely, so:
+//!
+//! - [`Server::drain_timeout`](crate::Server::drain_timeout) closes whatever
+//!   is still open once it passes,
+//! - upgraded [WebSockets](crate::ws::WebSocket) send a close frame,
+//! - the [`draining`](draining) filter lets other streams finish early,
+//! - a [`Shutdown`](Shutdown) handle reports how many connections are left.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::convert::Infallible;
+//! use std::time::Duration;
+//! use futures::StreamExt;
+//! use tokio::sync::oneshot;
+//! use warp::shutdown::{Draining, Shutdown};
+//! use warp::sse::Event;
+//! use warp::Filter;
+//!
+//! # async fn run() {
+//! let events = warp::path("events")
+//!     .and(warp::shutdown::draining())
+//!     .map(|draining: Draining| {
+//!         let stream = futures::stream::pending::<Result<Event, Infallible>>();
+//!         // Ends the response once the server starts shutting down.
+//!         warp::sse::reply(stream.take_until(draining))
+//!     });
+//!
+//! let shutdown = Shutdown::new();
+//! let (tx, rx) = oneshot::channel::<()>();
+//! let (_addr, server) = warp::serve(events)
+//!     .shutdown(shutdown.clone())
+//!     .drain_timeout(Duration::from_secs(30))
+//!     .bind_with_graceful_shutdown(([127, 0, 0, 1], 3030), async {
+//!         rx.await.ok();
+//!     });
+//! tokio::task::spawn(server);
+//!
+//! // Later...
+//! let _ = tx.send(());
+//! println!("{} connections left", shutdown.connections());
+//! # }
+//! ```
+
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::FutureExt;
+use tokio::sync::{watch, Notify};
+
+use crate::filter::{filter_fn_one, Filter, One};
+
+/// Create a filter that extracts a [`Draining`](Draining) future, which
+/// completes once the server starts shutting down gracefully.
+///
+/// Outside of a server, such as with [`warp::test`](crate::test), it never
+/// completes.
+pub fn draining() -> impl Filter<Extract = One<Draining>, Error = Infallible> + Copy {
+    filter_fn_one(|route| {
+        let draining = match route.extensions().get::<Shutdown>() {
+            Some(shutdown) => shutdown.draining(),
+            None => Draining::never(),
+        };
+        future::ok(draining)
+    })
+}
+
+/// A handle on the graceful shutdown of one or more servers, given to
+/// [`Server::shutdown`](crate::Server::shutdown).
+///
+/// Servers sharing a handle also share their shutdown: once the signal of
+/// any of them completes, they all start draining.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    connections: AtomicUsize,
+    // Notified when the last connection closes.
+    idle: Notify,
+    state: watch::Sender<State>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum State {
+    Running,
+    Draining,
+    Closed,
+}
+
+/// A future that completes once the server starts shutting down, extracted
+/// by [`draining`](draining) or made by [`Shutdown::draining`].
+///
+/// It can end a stream with
+/// [`StreamExt::take_until`](futures::StreamExt::take_until).
+pub struct Draining {
+    future: Pin<Box<dyn Future<Output = ()> + Send>>,
+}
+
+// Counts an open connection until it's dropped.
+pub(crate) struct Connection {
+    inner: Arc<Inner>,
+}
+
+// Spawns the tasks of a server, which are dropped once the drain timeout
+// passes.
+#[derive(Clone)]
+pub(crate) struct Executor {
+    shutdown: Shutdown,
+}
+
+impl Shutdown {
+    /// Create a new handle.
+    pub fn new() -> Shutdown {
+        let (state, _) = watch::channel(State::Running);
+        Shutdown {
+            inner: Arc::new(Inner {
+                connections: AtomicUsize::new(0),
+                idle: Notify::new(),
+                state,
+            }),
+        }
+    }
+
+    /// The number of connections still open, including upgraded WebSockets.
+    pub fn connections(&self) -> usize {
+        self.inner.connections.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the server has started shutting down.
+    pub fn is_draining(&self) -> bool {
+        *self.inner.state.borrow() != State::Running
+    }
+
+    /// A future that completes once the server starts shutting down.
+    pub fn draining(&self) -> Draining {
+        Draining {
+            future: Box::pin(self.wait_for(State::Draining)),
+        }
+    }
+
+    fn wait_for(&self, state: State) -> impl Future<Output = ()> + Send + 'static {
+        let mut rx = self.inner.state.subscribe();
+        async move {
+            // Every handle is gone, so the state can't change anymore.
+            if rx.wait_for(|current| *current >= state).await.is_err() {
+                future::pending::<()>().await;
+            }
+        }
+    }
+
+    // Completes once no connections are open, including upgraded ones that
+    // hyper no longer knows about.
+    fn idle(&self) -> impl Future<Output = ()> + Send + 'static {
+        let inner = self.inner.clone();
+        async move {
+            loop {
+                let notified = inner.idle.notified();
+                if inner.connections.load(Ordering::Acquire) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        }
+    }
+
+    fn set(&self, state: State) {
+        self.inner.state.send_if_modified(|current| {
+            let modified = *current < state;
+            if modified {
+                *current = state;
+            }
+            modified
+        });
+    }
+
+    pub(crate) fn connection(&self) -> Connection {
+        self.inner.connections.fetch_add(1, Ordering::AcqRel);
+        Connection {
+            inner: self.inner.clone(),
+        }
+    }
+
+    pub(crate) fn executor(&self) -> Executor {
+        Executor {
+            shutdown: self.clone(),
+        }
+    }
+
+    // Runs `future` until the server is closed.
+    pub(crate) fn until_closed<F>(&self, future: F) -> impl Future<Output = ()> + Send + 'static
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        future::select(Box::pin(future), Box::pin(self.wait_for(State::Closed))).map(|_| ())
+    }
+
+    // Starts draining when `signal` completes, or when another server sharing
+    // this handle does. The result is meant for hyper's graceful shutdown.
+    pub(crate) fn signal(
+        &self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = ()> + Send + 'static {
+        let shutdown = self.clone();
+        let draining = self.wait_for(State::Draining);
+        async move {
+            future::select(Box::pin(signal), Box::pin(draining)).await;
+            tracing::debug!("draining {} connections", shutdown.connections());
+            shutdown.set(State::Draining);
+        }
+    }
+
+    // Drives `server` to completion and waits for the connections to close,
+    // closing the ones still open once `timeout` has passed since draining
+    // started.
+    //
+    // Hyper is done with upgraded connections, so its server can finish while
+    // they're still open.
+    pub(crate) fn deadline<F>(
+        &self,
+        server: F,
+        timeout: Option<Duration>,
+    ) -> impl Future<Output = ()>
+    where
+        F: Future<Output = ()>,
+    {
+        let shutdown = self.clone();
+        let drained = server.then({
+            let shutdown = self.clone();
+            move |()| shutdown.idle()
+        });
+        let timeout = match timeout {
+            Some(timeout) => timeout,
+            None => return Either::Left(drained),
+        };
+        let expired = self
+            .wait_for(State::Draining)
+            .then(move |()| tokio::time::sleep(timeout));
+        Either::Right(async move {
+            if let Either::Right((_, drained)) =
+                future::select(Box::pin(drained), Box::pin(expired)).await
+            {
+                tracing::debug!(
+                    "drain timeout passed, closing {} connections",
+                    shutdown.connections()
+                );
+                shutdown.set(State::Closed);
+                drained.await;
+            }
+        })
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Shutdown {
+        Shutdown::new()
+    }
+}
+
+impl fmt::Debug for Shutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("connections", &self.connections())
+            .field("state", &*self.inner.state.borrow())
+            .finish()
+    }
+}
+
+impl Draining {
+    fn never() -> Draining {
+        Draining {
+            future: Box::pin(future::pending()),
+        }
+    }
+}
+
+impl Future for Draining {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        self.future.as_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for Draining {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Draining").finish()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if self.inner.connections.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl<F> hyper::rt::Executor<F> for Executor
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    fn execute(&self, future: F) {
+        tokio::task::spawn(self.shutdown.until_closed(future));
+    }
+}
+
+// Lets filters of the server's requests find out about its shutdown.
+pub(crate) fn with_shutdown(mut req: crate::Request, shutdown: &Shutdown) -> crate::Request {
+    req.extensions_mut().insert(shutdown.clone());
+    req
+}
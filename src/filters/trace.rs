@@ -49,17 +49,22 @@ pub fn request() -> Trace<impl Fn(Info) -> Span + Clone> {
             path = %info.path(),
             version = ?info.route.version(),
             referer = Empty,
+            request.id = Empty,
         );
 
         // Record optional fields.
-        if let Some(remote_addr) = info.remote_addr() {
-            span.record("remote.addr", &display(remote_addr));
+        if let Some(peer) = info.peer() {
+            span.record("remote.addr", display(peer));
         }
 
         if let Some(referer) = info.referer() {
             span.record("referer", &display(referer));
         }
 
+        if let Some(id) = info.request_id() {
+            span.record("request.id", display(id));
+        }
+
         tracing::debug!(parent: &span, "received request");
 
         span
@@ -158,10 +163,19 @@ where
 
 impl<'a> Info<'a> {
     /// View the remote `SocketAddr` of the request.
+    ///
+    /// Behind a [trusted proxy](crate::proxy::trusted), this is the address of
+    /// the client.
     pub fn remote_addr(&self) -> Option<SocketAddr> {
         self.route.remote_addr()
     }
 
+    /// View the peer of the connection, which also knows about peers without
+    /// a `SocketAddr`, such as on Unix sockets.
+    pub fn peer(&self) -> Option<crate::addr::Peer> {
+        self.route.peer()
+    }
+
     /// View the `http::Method` of the request.
     pub fn method(&self) -> &http::Method {
         self.route.method()
@@ -194,17 +208,30 @@ impl<'a> Info<'a> {
     }
 
     /// View the host of the request
+    ///
+    /// Behind a [trusted proxy](crate::proxy::trusted), this is the host the
+    /// client asked for.
     pub fn host(&self) -> Option<&str> {
-        self.route
-            .headers()
-            .get(header::HOST)
-            .and_then(|v| v.to_str().ok())
+        self.route.host()
+    }
+
+    /// View the scheme of the request, if known.
+    ///
+    /// Behind a [trusted proxy](crate::proxy::trusted), this is the scheme the
+    /// client used.
+    pub fn scheme(&self) -> Option<&str> {
+        self.route.scheme().map(|s| s.as_str())
     }
 
     /// View the request headers.
     pub fn request_headers(&self) -> &http::HeaderMap {
         self.route.headers()
     }
+
+    /// View the ID assigned by [`warp::request_id`](crate::request_id()), if any.
+    pub fn request_id(&self) -> Option<&str> {
+        crate::request_id::from_route(self.route)
+    }
 }
 
 mod internal {
@@ -216,6 +243,7 @@ mod internal {
     use crate::reply::Reply;
     use crate::reply::Response;
     use crate::route;
+    use crate::routes::RouteTable;
 
     #[allow(missing_debug_implementations)]
     pub struct Traced(pub(super) Response);
@@ -301,5 +329,9 @@ mod internal {
                 .inspect(finished_logger as fn(&Result<Self::Extract, F::Error>))
                 .instrument(span.clone())
         }
+
+        fn describe(&self, _: Internal) -> RouteTable {
+            self.filter.describe(Internal)
+        }
     }
 }
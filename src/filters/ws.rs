@@ -7,6 +7,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use super::header;
+use super::shutdown::{Draining, Shutdown};
 use crate::filter::{filter_fn_one, Filter, One};
 use crate::reject::Rejection;
 use crate::reply::{Reply, Response};
@@ -40,6 +41,10 @@ use tokio_tungstenite::{
 /// - Header `connection: upgrade`
 /// - Header `upgrade: websocket`
 /// - Header `sec-websocket-accept` with the hash value of the received key.
+///
+/// When the server shuts down gracefully, a close frame with the code `1001`
+/// (going away) is sent, even if the handler never reads from the
+/// `WebSocket`. It counts as a connection until the handler is done. See [`warp::shutdown`](crate::shutdown).
 pub fn ws() -> impl Filter<Extract = One<Ws>, Error = Rejection> + Copy {
     let connection_has_upgrade = header::header2()
         .and_then(|conn: ::headers::Connection| {
@@ -59,11 +64,15 @@ pub fn ws() -> impl Filter<Extract = One<Ws>, Error = Rejection> + Copy {
         //.and(header::exact2(SecWebsocketVersion::V13))
         .and(header::header2::<SecWebsocketKey>())
         .and(on_upgrade())
+        .and(shutdown())
         .map(
-            move |key: SecWebsocketKey, on_upgrade: Option<OnUpgrade>| Ws {
+            move |key: SecWebsocketKey,
+                  on_upgrade: Option<OnUpgrade>,
+                  shutdown: Option<Shutdown>| Ws {
                 config: None,
                 key,
                 on_upgrade,
+                shutdown,
             },
         )
 }
@@ -73,6 +82,7 @@ pub struct Ws {
     config: Option<WebSocketConfig>,
     key: SecWebsocketKey,
     on_upgrade: Option<OnUpgrade>,
+    shutdown: Option<Shutdown>,
 }
 
 impl Ws {
@@ -138,18 +148,28 @@ where
         if let Some(on_upgrade) = self.ws.on_upgrade {
             let on_upgrade_cb = self.on_upgrade;
             let config = self.ws.config;
+            let shutdown = self.ws.shutdown;
+            let draining = shutdown.as_ref().map(Shutdown::draining);
+            let connection = shutdown.as_ref().map(Shutdown::connection);
             let fut = on_upgrade
                 .and_then(move |upgraded| {
                     tracing::trace!("websocket upgrade complete");
                     WebSocket::from_raw_socket(upgraded, protocol::Role::Server, config).map(Ok)
                 })
-                .and_then(move |socket| on_upgrade_cb(socket).map(Ok))
-                .map(|result| {
+                .and_then(move |mut socket| {
+                    socket.draining = draining;
+                    on_upgrade_cb(socket).map(Ok)
+                })
+                .map(move |result| {
+                    drop(connection);
                     if let Err(err) = result {
                         tracing::debug!("ws upgrade error: {}", err);
                     }
                 });
-            ::tokio::task::spawn(fut);
+            match shutdown {
+                Some(shutdown) => ::tokio::task::spawn(shutdown.until_closed(fut)),
+                None => ::tokio::task::spawn(fut),
+            };
         } else {
             tracing::debug!("ws couldn't be upgraded since no upgrade state was present");
         }
@@ -167,6 +187,11 @@ where
     }
 }
 
+// Extracts the shutdown of the server, if there is one.
+fn shutdown() -> impl Filter<Extract = (Option<Shutdown>,), Error = Rejection> + Copy {
+    filter_fn_one(|route| future::ready(Ok(route.extensions().get::<Shutdown>().cloned())))
+}
+
 // Extracts OnUpgrade state from the route.
 fn on_upgrade() -> impl Filter<Extract = (Option<OnUpgrade>,), Error = Rejection> + Copy {
     filter_fn_one(|route| future::ready(Ok(route.extensions_mut().remove::<OnUpgrade>())))
@@ -180,9 +205,16 @@ fn on_upgrade() -> impl Filter<Extract = (Option<OnUpgrade>,), Error = Rejection
 ///
 /// **Note!**
 /// Due to rust futures nature, pings won't be handled until read part of `WebSocket` is polled
-
+///
+/// When the server shuts down, a close frame is sent the next time the
+/// `WebSocket` is read or written, and the client's reply to it ends the
+/// stream.
 pub struct WebSocket {
     inner: WebSocketStream<hyper::upgrade::Upgraded>,
+    // Until the server starts shutting down.
+    draining: Option<Draining>,
+    // Whether the close frame for the shutdown is yet to be queued.
+    going_away: bool,
 }
 
 impl WebSocket {
@@ -192,7 +224,11 @@ impl WebSocket {
         config: Option<protocol::WebSocketConfig>,
     ) -> Self {
         WebSocketStream::from_raw_socket(upgraded, role, config)
-            .map(|inner| WebSocket { inner })
+            .map(|inner| WebSocket {
+                inner,
+                draining: None,
+                going_away: false,
+            })
             .await
     }
 
@@ -206,6 +242,7 @@ impl Stream for WebSocket {
     type Item = Result<Message, crate::Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.poll_going_away(cx);
         match ready!(Pin::new(&mut self.inner).poll_next(cx)) {
             Some(Ok(item)) => Poll::Ready(Some(Ok(Message { inner: item }))),
             Some(Err(e)) => {
@@ -220,10 +257,56 @@ impl Stream for WebSocket {
     }
 }
 
+impl WebSocket {
+    // Starts the close handshake once the server starts shutting down.
+    //
+    // This is polled by both reading and writing, so that a handler doing
+    // either, such as with each half of `split`, gets to send it.
+    fn poll_going_away(&mut self, cx: &mut Context) {
+        if let Some(draining) = &mut self.draining {
+            if Pin::new(draining).poll(cx).is_pending() {
+                return;
+            }
+            tracing::trace!("websocket closing for shutdown");
+            self.draining = None;
+            self.going_away = true;
+        }
+        if !self.going_away {
+            return;
+        }
+
+        let mut inner = Pin::new(&mut self.inner);
+        let sent = match inner.as_mut().poll_ready(cx) {
+            Poll::Ready(Ok(())) => {
+                self.going_away = false;
+                let close = Message::close_with(1001u16, "server shutting down").inner;
+                inner.as_mut().start_send(close)
+            }
+            Poll::Ready(Err(err)) => {
+                self.going_away = false;
+                Err(err)
+            }
+            Poll::Pending => return,
+        };
+        // The rest is written as the socket is read or written.
+        let sent = match sent {
+            Ok(()) => match inner.poll_flush(cx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => Ok(()),
+            },
+            Err(err) => Err(err),
+        };
+        if let Err(err) = sent {
+            tracing::debug!("websocket close error: {}", err);
+        }
+    }
+}
+
 impl Sink<Message> for WebSocket {
     type Error = crate::Error;
 
     fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.poll_going_away(cx);
         match ready!(Pin::new(&mut self.inner).poll_ready(cx)) {
             Ok(()) => Poll::Ready(Ok(())),
             Err(e) => Poll::Ready(Err(crate::Error::new(e))),
@@ -90,16 +90,23 @@ mod error;
 mod filter;
 pub mod filters;
 mod generic;
+mod listener;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+mod proxy_protocol;
 pub mod redirect;
 pub mod reject;
 pub mod reply;
 mod route;
+pub mod routes;
 mod server;
 mod service;
 pub mod test;
 #[cfg(feature = "tls")]
-mod tls;
+pub mod tls;
 mod transport;
+#[cfg(unix)]
+mod unix;
 
 pub use self::error::Error;
 pub use self::filter::Filter;
@@ -112,6 +119,9 @@ pub use self::filters::compression;
 #[cfg(feature = "multipart")]
 #[doc(hidden)]
 pub use self::filters::multipart;
+#[cfg(feature = "session")]
+#[doc(hidden)]
+pub use self::filters::session;
 #[cfg(feature = "websocket")]
 #[doc(hidden)]
 pub use self::filters::ws;
@@ -120,6 +130,7 @@ pub use self::filters::{
     addr,
     // any() function
     any::any,
+    auth,
     body,
     cookie,
     // cookie() function
@@ -137,12 +148,19 @@ pub use self::filters::{
     // log() function
     log::log,
     method::{delete, get, head, method, options, patch, post, put},
+    metrics,
     path,
     // path() function and macro
     path::path,
+    problem,
+    proxy,
     query,
     // query() function
     query::query,
+    request_id,
+    // request_id() function
+    request_id::request_id,
+    shutdown,
     sse,
     trace,
     // trace() function
@@ -153,6 +171,8 @@ pub use self::filter::wrap_fn;
 #[cfg(feature = "websocket")]
 #[doc(hidden)]
 pub use self::filters::ws::ws;
+pub use self::listener::Listener;
+pub use self::proxy_protocol::ProxyProtocol;
 #[doc(hidden)]
 pub use self::redirect::redirect;
 #[doc(hidden)]
@@ -162,8 +182,10 @@ pub use self::reject::{reject, Rejection};
 pub use self::reply::{reply, Reply};
 #[cfg(feature = "tls")]
 pub use self::server::TlsServer;
-pub use self::server::{serve, Server};
+pub use self::server::{serve, LimitPolicy, LocalAddr, MultiServer, Server};
 pub use self::service::service;
+#[cfg(unix)]
+pub use self::unix::UnixSocket;
 #[doc(hidden)]
 pub use http;
 #[doc(hidden)]
@@ -0,0 +1,342 @@
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::io::{FromRawFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use hyper::server::conn::AddrIncoming;
+
+use crate::proxy_protocol::{ProxyIncoming, ProxyProtocol};
+use crate::server::{ConnConfig, LocalAddr};
+#[cfg(unix)]
+use crate::unix::UnixIncoming;
+
+// The first file descriptor systemd passes.
+#[cfg(unix)]
+const LISTEN_FDS_START: RawFd = 3;
+
+// Whether the inherited file descriptors were taken, which happens once.
+#[cfg(unix)]
+static INHERITED: AtomicBool = AtomicBool::new(false);
+
+/// A socket that is already listening, to serve with
+/// [`bind_listener`](crate::Server::bind_listener).
+///
+/// These are usually handed down by a supervisor, such as systemd with socket
+/// activation, which can then restart the server without refusing any
+/// connections. See [`Listener::inherited`].
+///
+/// TCP listeners are served like sockets bound by warp itself, with
+/// `TCP_NODELAY` and the server's TCP options set on each connection.
+pub struct Listener {
+    inner: Inner,
+    name: Option<String>,
+}
+
+enum Inner {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+pub(crate) enum Incoming {
+    Tcp(ProxyIncoming),
+    #[cfg(unix)]
+    Unix(UnixIncoming),
+}
+
+impl Listener {
+    /// Takes the listeners passed to this process with systemd's socket
+    /// activation protocol, in the order they were passed.
+    ///
+    /// Only the first call takes the listeners, later calls return nothing.
+    /// Like `sd_listen_fds`, nothing is returned either unless `LISTEN_PID`
+    /// names this process. The environment is left as it is, but the
+    /// listeners are closed on `exec`, so child processes don't inherit them.
+    ///
+    /// If any of the file descriptors isn't a listening socket, an error is
+    /// returned and none of them are taken.
+    ///
+    /// Returns no listeners on platforms other than Unix.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use warp::{Filter, Listener};
+    ///
+    /// # async fn run() {
+    /// let routes = warp::any().map(|| "Hello, World!");
+    ///
+    /// match Listener::inherited().unwrap().pop() {
+    ///     Some(listener) => warp::serve(routes).bind_listener(listener).await,
+    ///     None => warp::serve(routes).run(([127, 0, 0, 1], 3030)).await,
+    /// }
+    /// # }
+    /// ```
+    pub fn inherited() -> Result<Vec<Listener>, crate::Error> {
+        #[cfg(unix)]
+        {
+            if INHERITED.swap(true, Ordering::SeqCst) {
+                return Ok(Vec::new());
+            }
+            let var = |name| std::env::var(name).ok();
+            let fds = parse_listen_fds(
+                var("LISTEN_PID").as_deref(),
+                var("LISTEN_FDS").as_deref(),
+                var("LISTEN_FDNAMES").as_deref(),
+                std::process::id(),
+            );
+            // Every one is checked before any is taken, since those taken
+            // are closed when dropped.
+            let checked = fds.and_then(|fds| {
+                fds.into_iter()
+                    .map(|(fd, name)| Ok((fd, unsafe { check_fd(fd)? }, name)))
+                    .collect::<io::Result<Vec<_>>>()
+            });
+            match checked {
+                Ok(fds) => Ok(fds
+                    .into_iter()
+                    .map(|(fd, family, name)| unsafe { Listener::from_fd(fd, family, name) })
+                    .collect()),
+                Err(err) => {
+                    INHERITED.store(false, Ordering::SeqCst);
+                    Err(crate::Error::new(err))
+                }
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            Ok(Vec::new())
+        }
+    }
+
+    // Takes ownership of a listening socket passed by the parent, once
+    // `check_fd` has found its family.
+    #[cfg(unix)]
+    unsafe fn from_fd(fd: RawFd, family: Family, name: Option<String>) -> Listener {
+        let inner = match family {
+            Family::Inet => Inner::Tcp(TcpListener::from_raw_fd(fd)),
+            Family::Unix => Inner::Unix(UnixListener::from_raw_fd(fd)),
+        };
+        Listener { inner, name }
+    }
+
+    /// The name systemd gave this listener, with `FileDescriptorName=`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The address this listens on, if it's a TCP listener.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        match self.inner {
+            Inner::Tcp(ref listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Inner::Unix(_) => None,
+        }
+    }
+
+    pub(crate) fn local(&self) -> LocalAddr {
+        match self.inner {
+            Inner::Tcp(ref listener) => match listener.local_addr() {
+                Ok(addr) => LocalAddr::Tcp(addr),
+                // Not a bound socket, which accepting will complain about.
+                Err(_) => LocalAddr::Tcp(([0, 0, 0, 0], 0).into()),
+            },
+            #[cfg(unix)]
+            Inner::Unix(ref listener) => LocalAddr::Unix(
+                listener
+                    .local_addr()
+                    .ok()
+                    .and_then(|addr| addr.as_pathname().map(Into::into)),
+            ),
+        }
+    }
+
+    pub(crate) fn into_incoming(
+        self,
+        proxy_protocol: Option<ProxyProtocol>,
+        conn: &ConnConfig,
+    ) -> Result<Incoming, crate::Error> {
+        match self.inner {
+            Inner::Tcp(listener) => {
+                listener.set_nonblocking(true).map_err(crate::Error::new)?;
+                let listener =
+                    tokio::net::TcpListener::from_std(listener).map_err(crate::Error::new)?;
+                let mut incoming =
+                    AddrIncoming::from_listener(listener).map_err(crate::Error::new)?;
+                conn.tcp(&mut incoming);
+                Ok(Incoming::Tcp(ProxyIncoming::new(incoming, proxy_protocol)))
+            }
+            #[cfg(unix)]
+            Inner::Unix(listener) => {
+                listener.set_nonblocking(true).map_err(crate::Error::new)?;
+                let listener =
+                    tokio::net::UnixListener::from_std(listener).map_err(crate::Error::new)?;
+                Ok(Incoming::Unix(UnixIncoming::from_listener(listener)))
+            }
+        }
+    }
+}
+
+impl From<TcpListener> for Listener {
+    fn from(listener: TcpListener) -> Listener {
+        Listener {
+            inner: Inner::Tcp(listener),
+            name: None,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixListener> for Listener {
+    fn from(listener: UnixListener) -> Listener {
+        Listener {
+            inner: Inner::Unix(listener),
+            name: None,
+        }
+    }
+}
+
+impl fmt::Debug for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut f = f.debug_struct("Listener");
+        match self.inner {
+            Inner::Tcp(ref listener) => f.field("tcp", listener),
+            #[cfg(unix)]
+            Inner::Unix(ref listener) => f.field("unix", listener),
+        };
+        f.field("name", &self.name).finish()
+    }
+}
+
+#[cfg(unix)]
+enum Family {
+    Inet,
+    Unix,
+}
+
+// Checks that a file descriptor passed by the parent is a listening socket
+// that can be served, without taking ownership of it.
+#[cfg(unix)]
+unsafe fn check_fd(fd: RawFd) -> io::Result<Family> {
+    if libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // Only listening stream sockets can be served.
+    let sockopt = |opt| {
+        let mut value: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let value_ptr = &mut value as *mut libc::c_int as *mut libc::c_void;
+        if libc::getsockopt(fd, libc::SOL_SOCKET, opt, value_ptr, &mut len) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(value)
+    };
+    if sockopt(libc::SO_TYPE)? != libc::SOCK_STREAM || sockopt(libc::SO_ACCEPTCONN)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("file descriptor {} is not a listening stream socket", fd),
+        ));
+    }
+
+    let mut addr: libc::sockaddr_storage = std::mem::zeroed();
+    let mut len = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    if libc::getsockname(fd, &mut addr as *mut _ as *mut libc::sockaddr, &mut len) == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    match libc::c_int::from(addr.ss_family) {
+        libc::AF_INET | libc::AF_INET6 => Ok(Family::Inet),
+        libc::AF_UNIX => Ok(Family::Unix),
+        family => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("file descriptor {} has unsupported family {}", fd, family),
+        )),
+    }
+}
+
+// The file descriptors and names passed by systemd, as `sd_listen_fds` reads
+// them.
+#[cfg(unix)]
+fn parse_listen_fds(
+    pid: Option<&str>,
+    fds: Option<&str>,
+    names: Option<&str>,
+    own_pid: u32,
+) -> io::Result<Vec<(RawFd, Option<String>)>> {
+    let invalid = |what| io::Error::new(io::ErrorKind::InvalidInput, what);
+
+    let fds = match fds {
+        Some(fds) => fds,
+        None => return Ok(Vec::new()),
+    };
+    // Otherwise, the variables could be left over from a parent that was
+    // passed the listeners.
+    let pid = match pid {
+        Some(pid) => pid
+            .parse::<u32>()
+            .map_err(|_| invalid("invalid LISTEN_PID"))?,
+        None => return Ok(Vec::new()),
+    };
+    if pid != own_pid {
+        return Ok(Vec::new());
+    }
+    let count = fds
+        .parse::<RawFd>()
+        .ok()
+        .filter(|count| (0..=RawFd::MAX - LISTEN_FDS_START).contains(count))
+        .ok_or_else(|| invalid("invalid LISTEN_FDS"))?;
+
+    let mut names = names.unwrap_or("").split(':');
+    Ok((0..count)
+        .map(|i| {
+            let name = names.next().filter(|name| !name.is_empty());
+            (LISTEN_FDS_START + i, name.map(str::to_owned))
+        })
+        .collect())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_fds() {
+        assert_eq!(parse_listen_fds(None, None, None, 42).unwrap(), vec![]);
+        assert_eq!(
+            parse_listen_fds(Some("42"), Some("2"), Some("http:admin"), 42).unwrap(),
+            vec![(3, Some("http".to_owned())), (4, Some("admin".to_owned()))]
+        );
+        assert_eq!(
+            parse_listen_fds(Some("42"), Some("2"), Some("http"), 42).unwrap(),
+            vec![(3, Some("http".to_owned())), (4, None)]
+        );
+        assert_eq!(parse_listen_fds(None, Some("2"), None, 42).unwrap(), vec![]);
+        // Meant for another process.
+        assert_eq!(
+            parse_listen_fds(Some("7"), Some("1"), None, 42).unwrap(),
+            vec![]
+        );
+
+        assert!(parse_listen_fds(Some("x"), Some("1"), None, 42).is_err());
+        assert!(parse_listen_fds(Some("42"), Some("-1"), None, 42).is_err());
+    }
+
+    #[test]
+    fn not_listening() {
+        use std::os::unix::io::AsRawFd;
+
+        let udp = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        assert!(unsafe { check_fd(udp.as_raw_fd()) }.is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        assert!(unsafe { check_fd(stream.as_raw_fd()) }.is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! OpenAPI documents
+//!
+//! Generates an [OpenAPI 3](https://spec.openapis.org/oas/v3.0.3) document
+//! from the routes of a [`Filter`](crate::Filter), so it can't drift from
+//! the code serving them.
+//!
+//! Besides the paths and methods listed by
+//! [`Filter::routes`](crate::Filter::routes), the built-in filters record:
+//!
+//! - path parameters, from `warp::path::param` and `warp::path!`,
+//! - query parameters, from `warp::query::<T>()`,
+//! - request bodies, from `warp::body::json::<T>()` and `warp::body::form::<T>()`,
+//! - headers, from `warp::header` and friends.
+//!
+//! Only the names of the fields of query and body structs are known, not
+//! their types, so their schemas are left open.
+//!
+//! Routes accepting any method are left out, since every OpenAPI operation
+//! needs one.
+//!
+//! OpenAPI paths only match exactly, so a route that doesn't end its path
+//! (with `warp::path::end()` or `warp::path!`) is documented at its prefix,
+//! and its operation is marked with `"x-warp-prefix": true`.
+//!
+//! Requires the `openapi` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use serde_derive::Deserialize;
+//! use warp::Filter;
+//!
+//! #[derive(Deserialize)]
+//! struct Page {
+//!     offset: u32,
+//!     limit: u32,
+//! }
+//!
+//! let list_users = warp::get()
+//!     .and(warp::path!("users"))
+//!     .and(warp::query::<Page>())
+//!     .map(|_page: Page| "users")
+//!     .named("list_users");
+//!
+//! let document = warp::openapi::Document::new("Users", "1.0").routes(&list_users);
+//!
+//! let api = warp::path!("openapi.json")
+//!     .and(warp::openapi::serve(document))
+//!     .or(list_users);
+//! ```
+
+use std::sync::Arc;
+
+use serde::de::{self, DeserializeOwned, Deserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use serde_json::{json, Map, Value};
+
+use crate::filter::Filter;
+use crate::reject::Rejection;
+use crate::reply::{self, Reply, Response};
+use crate::routes::{RouteInfo, RouteTable, Segment};
+
+const OPENAPI_VERSION: &str = "3.0.3";
+
+/// Create a `Filter` that replies with a `Document` as JSON.
+///
+/// The document is rendered once, when the filter is created.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+///
+/// let hello = warp::path!("hello").and(warp::get()).map(|| "hello");
+/// let document = warp::openapi::Document::new("Hello", "1.0").routes(&hello);
+///
+/// let route = warp::path!("openapi.json")
+///     .and(warp::openapi::serve(document))
+///     .or(hello);
+/// ```
+pub fn serve(
+    document: Document,
+) -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let json = Arc::new(document.to_json());
+    crate::get().map(move || reply::json(&*json))
+}
+
+/// An OpenAPI document.
+#[derive(Clone, Debug)]
+pub struct Document {
+    title: String,
+    version: String,
+    routes: Vec<RouteInfo>,
+}
+
+impl Document {
+    /// Creates an empty document, with the title and version of the API.
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Document {
+        Document {
+            title: title.into(),
+            version: version.into(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Adds the routes of a `Filter` to this document.
+    pub fn routes<F: Filter>(self, filter: &F) -> Document {
+        self.route_table(filter.routes())
+    }
+
+    /// Adds the routes of a [`RouteTable`](crate::routes::RouteTable).
+    pub fn route_table(mut self, routes: RouteTable) -> Document {
+        self.routes.extend(routes);
+        self
+    }
+
+    /// Renders this document as JSON.
+    pub fn to_json(&self) -> Value {
+        let mut paths = Map::new();
+        for route in &self.routes {
+            let method = match route.method() {
+                Some(method) => method.as_str().to_ascii_lowercase(),
+                None => continue,
+            };
+            let (path, mut parameters) = path_template(route);
+            let item = paths
+                .entry(path)
+                .or_insert_with(|| Value::Object(Map::new()))
+                .as_object_mut()
+                .expect("path items are objects");
+            // Like with `or`, the first route wins.
+            if item.contains_key(&method) {
+                continue;
+            }
+
+            let operation = &route.operation;
+            parameters.extend(operation.params.iter().map(Param::to_json));
+            let mut op = json!({
+                "responses": {
+                    "default": { "description": "Default response" }
+                }
+            });
+            if let Some(name) = route.name() {
+                op["operationId"] = name.into();
+            }
+            if route.is_prefix() {
+                op["x-warp-prefix"] = true.into();
+            }
+            if !parameters.is_empty() {
+                op["parameters"] = parameters.into();
+            }
+            if let Some(ref body) = operation.body {
+                op["requestBody"] = body.to_json();
+            }
+            item.insert(method, op);
+        }
+
+        json!({
+            "openapi": OPENAPI_VERSION,
+            "info": {
+                "title": self.title,
+                "version": self.version,
+            },
+            "paths": paths,
+        })
+    }
+}
+
+impl Reply for Document {
+    fn into_response(self) -> Response {
+        reply::json(&self.to_json()).into_response()
+    }
+}
+
+// What a route expects from a request, besides its path and method.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Operation {
+    params: Vec<Param>,
+    body: Option<Body>,
+}
+
+#[derive(Clone, Debug)]
+enum Param {
+    Query(&'static str),
+    // A query deserialized into a map, accepting any keys.
+    QueryMap,
+    Header { name: &'static str, required: bool },
+}
+
+#[derive(Clone, Debug)]
+struct Body {
+    content_type: &'static str,
+    fields: Option<&'static [&'static str]>,
+}
+
+impl Operation {
+    pub(crate) fn query<T: DeserializeOwned>(&mut self) {
+        match fields::<T>() {
+            Some(fields) => self.params.extend(fields.iter().copied().map(Param::Query)),
+            None => self.params.push(Param::QueryMap),
+        }
+    }
+
+    pub(crate) fn body<T: DeserializeOwned>(&mut self, content_type: &'static str) {
+        self.body = Some(Body {
+            content_type,
+            fields: fields::<T>(),
+        });
+    }
+
+    pub(crate) fn header(&mut self, name: &'static str, required: bool) {
+        self.params.push(Param::Header { name, required });
+    }
+
+    pub(crate) fn and(&mut self, other: Operation) {
+        self.params.extend(other.params);
+        if other.body.is_some() {
+            self.body = other.body;
+        }
+    }
+}
+
+impl Param {
+    fn to_json(&self) -> Value {
+        match *self {
+            // Every field could be an `Option`, so none are required.
+            Param::Query(name) => json!({
+                "name": name,
+                "in": "query",
+                "schema": { "type": "string" },
+            }),
+            Param::QueryMap => json!({
+                "name": "query",
+                "in": "query",
+                "style": "form",
+                "explode": true,
+                "schema": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                },
+            }),
+            Param::Header { name, required } => json!({
+                "name": name,
+                "in": "header",
+                "required": required,
+                "schema": { "type": "string" },
+            }),
+        }
+    }
+}
+
+impl Body {
+    fn to_json(&self) -> Value {
+        let mut schema = json!({ "type": "object" });
+        if let Some(fields) = self.fields {
+            let properties = fields
+                .iter()
+                .map(|field| (field.to_string(), json!({})))
+                .collect::<Map<_, _>>();
+            schema["properties"] = properties.into();
+        }
+        json!({
+            "required": true,
+            "content": {
+                self.content_type: { "schema": schema }
+            },
+        })
+    }
+}
+
+// OpenAPI path templates need a name for every parameter, so they are
+// numbered in order, as `{param1}`, `{param2}`...
+//
+// Prefix routes get the template of their prefix, as there is no way to
+// match the rest.
+fn path_template(route: &RouteInfo) -> (String, Vec<Value>) {
+    let mut path = String::new();
+    let mut params = Vec::new();
+    for segment in route.segments() {
+        path.push('/');
+        let (name, schema) = match segment {
+            Segment::Exact(s) => {
+                path.push_str(s);
+                continue;
+            }
+            Segment::Param(ty) => (format!("param{}", params.len() + 1), schema(ty)),
+            Segment::Tail => ("tail".to_owned(), json!({ "type": "string" })),
+        };
+        path.push('{');
+        path.push_str(&name);
+        path.push('}');
+        params.push(json!({
+            "name": name,
+            "in": "path",
+            "required": true,
+            "schema": schema,
+        }));
+    }
+    if path.is_empty() {
+        path.push('/');
+    }
+    (path, params)
+}
+
+fn schema(ty: &str) -> Value {
+    match ty {
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+            json!({ "type": "integer", "minimum": 0 })
+        }
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => json!({ "type": "integer" }),
+        "f32" | "f64" => json!({ "type": "number" }),
+        "bool" => json!({ "type": "boolean" }),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+// Finds the field names of a struct, by deserializing it from a
+// `Deserializer` that records what `deserialize_struct` is asked for, and
+// then gives up.
+//
+// Returns `None` for anything that isn't a struct, such as a `HashMap`.
+fn fields<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    let mut fields = None;
+    let _ = T::deserialize(Fields(&mut fields));
+    fields
+}
+
+struct Fields<'a>(&'a mut Option<&'static [&'static str]>);
+
+impl<'de, 'a> Deserializer<'de> for Fields<'a> {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.0 = Some(fields);
+        Err(de::Error::custom("only looking for fields"))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple tuple_struct map enum
+        identifier ignored_any
+    }
+}
@@ -0,0 +1,447 @@
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::stream::{FuturesUnordered, Stream};
+use hyper::server::accept::Accept;
+use hyper::server::conn::{AddrIncoming, AddrStream};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+use crate::filters::proxy::Cidr;
+use crate::transport::Transport;
+
+const V1_PREFIX: &[u8] = b"PROXY ";
+// The longest possible v1 header, including the CRLF.
+const V1_MAX_LEN: usize = 107;
+const V2_SIGNATURE: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
+const V2_HEADER_LEN: usize = 16;
+// How many headers are read at once. Further connections wait in the listen
+// backlog, so that limits on connections also bound the open sockets.
+const MAX_HANDSHAKES: usize = 64;
+
+/// Configuration for accepting connections that start with a [PROXY protocol]
+/// header.
+///
+/// Load balancers that forward TCP, rather than HTTP, use it to tell the
+/// server who the client is. Both the text (v1) and binary (v2) versions of
+/// the header are read, before any HTTP or TLS, and the client address in it
+/// becomes the remote address of the connection, as seen by
+/// [`warp::addr::remote`](crate::addr::remote).
+///
+/// Since the header can claim any client, it's only read from upstreams that
+/// are allowed, which by default are loopback addresses only. Connections
+/// from elsewhere, or that don't start with a valid header, or don't finish
+/// sending it in time, are closed. Up to 64 headers are read at once, and
+/// further connections wait to be accepted until one is done.
+///
+/// [PROXY protocol]: https://www.haproxy.org/download/2.0/doc/proxy-protocol.txt
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use warp::{Filter, ProxyProtocol};
+///
+/// # async fn run() {
+/// let routes = warp::addr::remote().map(|addr| format!("hello, {:?}", addr));
+///
+/// warp::serve(routes)
+///     .proxy_protocol(
+///         ProxyProtocol::new()
+///             .timeout(Duration::from_secs(3))
+///             .allow(["10.0.0.0/8"]),
+///     )
+///     .run(([0, 0, 0, 0], 3030))
+///     .await;
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ProxyProtocol {
+    timeout: Duration,
+    allow: Arc<Vec<Cidr>>,
+}
+
+impl ProxyProtocol {
+    /// Creates a configuration that accepts headers from loopback addresses
+    /// only, and waits up to 5 seconds for them.
+    ///
+    /// **Anyone who can connect to the server can claim to be any client
+    /// with a PROXY header.** Upstreams on other hosts must be listed with
+    /// [`allow`](ProxyProtocol::allow), and the server shouldn't be reachable
+    /// other than through them.
+    pub fn new() -> ProxyProtocol {
+        ProxyProtocol {
+            timeout: Duration::from_secs(5),
+            allow: Arc::new(vec![
+                Cidr::parse("127.0.0.0/8").expect("valid CIDR"),
+                Cidr::parse("::1").expect("valid CIDR"),
+            ]),
+        }
+    }
+
+    /// Sets how long to wait for the header, after accepting a connection.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Only accept connections from upstreams in the given networks, instead
+    /// of loopback addresses.
+    ///
+    /// Each network is a CIDR, such as `10.0.0.0/8`, or a single address.
+    /// Connections from anywhere else are closed without reading anything.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the networks isn't a valid CIDR or address.
+    pub fn allow<I>(mut self, cidrs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let networks = cidrs
+            .into_iter()
+            .map(|cidr| {
+                let cidr = cidr.as_ref();
+                Cidr::parse(cidr).unwrap_or_else(|| panic!("invalid CIDR: {:?}", cidr))
+            })
+            .collect();
+        self.allow = Arc::new(networks);
+        self
+    }
+
+    fn allows(&self, ip: IpAddr) -> bool {
+        self.allow.iter().any(|net| net.contains(ip))
+    }
+}
+
+impl Default for ProxyProtocol {
+    fn default() -> ProxyProtocol {
+        ProxyProtocol::new()
+    }
+}
+
+impl fmt::Debug for ProxyProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProxyProtocol")
+            .field("timeout", &self.timeout)
+            .field("allow", &self.allow)
+            .finish()
+    }
+}
+
+type Handshake = Pin<Box<dyn Future<Output = io::Result<ProxyStream>> + Send>>;
+
+// Accepts connections, reading their PROXY headers if configured to.
+//
+// Headers are read concurrently, so that a slow upstream doesn't hold up the
+// others, up to `MAX_HANDSHAKES`. Without a configuration, connections are
+// passed straight through.
+pub(crate) struct ProxyIncoming {
+    incoming: AddrIncoming,
+    config: Option<ProxyProtocol>,
+    handshakes: FuturesUnordered<Handshake>,
+    closed: bool,
+}
+
+impl ProxyIncoming {
+    pub(crate) fn new(incoming: AddrIncoming, config: Option<ProxyProtocol>) -> ProxyIncoming {
+        ProxyIncoming {
+            incoming,
+            config,
+            handshakes: FuturesUnordered::new(),
+            closed: false,
+        }
+    }
+}
+
+impl Accept for ProxyIncoming {
+    type Conn = ProxyStream;
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let pin = self.get_mut();
+        let config = match pin.config {
+            Some(ref config) => config,
+            None => {
+                return Pin::new(&mut pin.incoming)
+                    .poll_accept(cx)
+                    .map_ok(ProxyStream::direct);
+            }
+        };
+
+        loop {
+            while !pin.closed && pin.handshakes.len() < MAX_HANDSHAKES {
+                match Pin::new(&mut pin.incoming).poll_accept(cx) {
+                    Poll::Ready(Some(Ok(sock))) => {
+                        let peer = sock.remote_addr();
+                        if config.allows(peer.ip()) {
+                            pin.handshakes
+                                .push(Box::pin(ProxyStream::handshake(sock, config.timeout)));
+                        } else {
+                            tracing::debug!("closing connection from untrusted upstream {}", peer);
+                        }
+                    }
+                    Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                    Poll::Ready(None) => pin.closed = true,
+                    Poll::Pending => break,
+                }
+            }
+
+            match Pin::new(&mut pin.handshakes).poll_next(cx) {
+                Poll::Ready(Some(Ok(stream))) => return Poll::Ready(Some(Ok(stream))),
+                // A slot is free again, so go back to accepting.
+                Poll::Ready(Some(Err(e))) => {
+                    tracing::debug!("PROXY protocol handshake failed: {}", e);
+                }
+                Poll::Ready(None) if pin.closed => return Poll::Ready(None),
+                Poll::Ready(None) | Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+// A connection, after its PROXY header has been read.
+pub(crate) struct ProxyStream {
+    inner: AddrStream,
+    // Whatever was read past the header.
+    prefix: Bytes,
+    remote_addr: SocketAddr,
+}
+
+impl ProxyStream {
+    fn direct(inner: AddrStream) -> ProxyStream {
+        ProxyStream {
+            remote_addr: inner.remote_addr(),
+            prefix: Bytes::new(),
+            inner,
+        }
+    }
+
+    async fn handshake(mut inner: AddrStream, timeout: Duration) -> io::Result<ProxyStream> {
+        let peer = inner.remote_addr();
+        let read = async {
+            let mut buf = BytesMut::with_capacity(V1_MAX_LEN);
+            loop {
+                if let Some((source, len)) = parse(&buf)? {
+                    buf.advance(len);
+                    return Ok::<_, io::Error>((source, buf.freeze()));
+                }
+                if inner.read_buf(&mut buf).await? == 0 {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+                }
+            }
+        };
+        let (source, prefix) = tokio::time::timeout(timeout, read)
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "PROXY header timed out"))??;
+
+        Ok(ProxyStream {
+            inner,
+            prefix,
+            // `LOCAL` connections, like health checks, are from the upstream.
+            remote_addr: source.unwrap_or(peer),
+        })
+    }
+}
+
+impl Transport for ProxyStream {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        Some(self.remote_addr)
+    }
+}
+
+impl AsyncRead for ProxyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let pin = self.get_mut();
+        if pin.prefix.has_remaining() {
+            let len = pin.prefix.len().min(buf.remaining());
+            buf.put_slice(&pin.prefix.split_to(len));
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut pin.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid PROXY protocol header")
+}
+
+// Parses a header at the start of `buf`, returning the source address, if
+// there is one, and the length of the header. `None` means more is needed.
+fn parse(buf: &[u8]) -> io::Result<Option<(Option<SocketAddr>, usize)>> {
+    if buf.len() < V2_SIGNATURE.len() && V2_SIGNATURE.starts_with(buf) {
+        Ok(None)
+    } else if buf.starts_with(V2_SIGNATURE) {
+        parse_v2(buf)
+    } else if buf.len() < V1_PREFIX.len() && V1_PREFIX.starts_with(buf) {
+        Ok(None)
+    } else if buf.starts_with(V1_PREFIX) {
+        parse_v1(buf)
+    } else {
+        Err(invalid())
+    }
+}
+
+// `PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\n`
+fn parse_v1(buf: &[u8]) -> io::Result<Option<(Option<SocketAddr>, usize)>> {
+    let end = match buf.windows(2).position(|w| w == b"\r\n") {
+        Some(end) if end + 2 <= V1_MAX_LEN => end,
+        Some(_) => return Err(invalid()),
+        None if buf.len() >= V1_MAX_LEN => return Err(invalid()),
+        None => return Ok(None),
+    };
+    let line = std::str::from_utf8(&buf[..end]).map_err(|_| invalid())?;
+    let mut parts = line.split(' ').skip(1);
+
+    let source = match parts.next() {
+        Some("UNKNOWN") => None,
+        Some(family @ "TCP4") | Some(family @ "TCP6") => {
+            let ip = parts
+                .next()
+                .and_then(|ip| ip.parse::<IpAddr>().ok())
+                .filter(|ip| ip.is_ipv4() == (family == "TCP4"))
+                .ok_or_else(invalid)?;
+            let _destination = parts.next().ok_or_else(invalid)?;
+            let port = parts
+                .next()
+                .and_then(|port| port.parse::<u16>().ok())
+                .ok_or_else(invalid)?;
+            Some(SocketAddr::new(ip, port))
+        }
+        _ => return Err(invalid()),
+    };
+    Ok(Some((source, end + 2)))
+}
+
+fn parse_v2(buf: &[u8]) -> io::Result<Option<(Option<SocketAddr>, usize)>> {
+    if buf.len() < V2_HEADER_LEN {
+        return Ok(None);
+    }
+    let version = buf[12] >> 4;
+    let command = buf[12] & 0x0F;
+    let family = buf[13] >> 4;
+    let len = V2_HEADER_LEN + u16::from_be_bytes([buf[14], buf[15]]) as usize;
+    if version != 2 {
+        return Err(invalid());
+    }
+    if buf.len() < len {
+        return Ok(None);
+    }
+    let addrs = &buf[V2_HEADER_LEN..len];
+
+    let source = match (command, family) {
+        // LOCAL
+        (0x0, _) => None,
+        // PROXY, over IPv4
+        (0x1, 0x1) if addrs.len() >= 12 => {
+            let ip = Ipv4Addr::new(addrs[0], addrs[1], addrs[2], addrs[3]);
+            let port = u16::from_be_bytes([addrs[8], addrs[9]]);
+            Some(SocketAddr::new(ip.into(), port))
+        }
+        // PROXY, over IPv6
+        (0x1, 0x2) if addrs.len() >= 36 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&addrs[..16]);
+            let port = u16::from_be_bytes([addrs[32], addrs[33]]);
+            Some(SocketAddr::new(Ipv6Addr::from(octets).into(), port))
+        }
+        // PROXY, over something without an address we can use
+        (0x1, 0x0) | (0x1, 0x3) => None,
+        _ => return Err(invalid()),
+    };
+    Ok(Some((source, len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allows_loopback_by_default() {
+        let config = ProxyProtocol::new();
+        assert!(config.allows("127.0.0.1".parse().unwrap()));
+        assert!(config.allows("::1".parse().unwrap()));
+        assert!(config.allows("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!config.allows("10.0.0.1".parse().unwrap()));
+
+        let config = config.allow(["10.0.0.0/8"]);
+        assert!(config.allows("10.0.0.1".parse().unwrap()));
+        assert!(!config.allows("127.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn v1() {
+        let header = b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\nGET /";
+        assert_eq!(
+            parse(header).unwrap(),
+            Some((Some("192.0.2.1:56324".parse().unwrap()), 45))
+        );
+
+        let header = b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443\r\n";
+        assert_eq!(
+            parse(header).unwrap(),
+            Some((Some("[2001:db8::1]:56324".parse().unwrap()), header.len()))
+        );
+
+        assert_eq!(parse(b"PROXY UNKNOWN\r\n").unwrap(), Some((None, 15)));
+        assert_eq!(parse(b"PRO").unwrap(), None);
+        assert_eq!(parse(b"PROXY TCP4 192.0.2.1").unwrap(), None);
+        assert!(parse(b"PROXY TCP4 2001:db8::1 2001:db8::2 1 2\r\n").is_err());
+        assert!(parse(b"GET / HTTP/1.1\r\n").is_err());
+    }
+
+    #[test]
+    fn v2() {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[0x21, 0x11, 0, 12]);
+        header.extend_from_slice(&[192, 0, 2, 1, 198, 51, 100, 1]);
+        header.extend_from_slice(&56324u16.to_be_bytes());
+        header.extend_from_slice(&443u16.to_be_bytes());
+        assert_eq!(
+            parse(&header).unwrap(),
+            Some((Some("192.0.2.1:56324".parse().unwrap()), 28))
+        );
+        assert_eq!(parse(&header[..20]).unwrap(), None);
+        assert_eq!(parse(&header[..5]).unwrap(), None);
+
+        let mut local = V2_SIGNATURE.to_vec();
+        local.extend_from_slice(&[0x20, 0x00, 0, 0]);
+        assert_eq!(parse(&local).unwrap(), Some((None, 16)));
+
+        let mut bad = V2_SIGNATURE.to_vec();
+        bad.extend_from_slice(&[0x11, 0x11, 0, 0]);
+        assert!(parse(&bad).is_err());
+    }
+}
@@ -34,12 +34,13 @@ use std::fmt;
 
 use http::{
     self,
-    header::{HeaderValue, CONTENT_TYPE},
-    StatusCode,
+    header::{HeaderValue, ALLOW, CONTENT_TYPE, WWW_AUTHENTICATE},
+    Method, StatusCode,
 };
 use hyper::Body;
 
 pub(crate) use self::sealed::{CombineRejection, IsReject};
+use crate::routes::RouteTable;
 
 /// Rejects a request with `404 Not Found`.
 #[inline]
@@ -58,7 +59,13 @@ pub fn not_found() -> Rejection {
 // 400 Bad Request
 #[inline]
 pub(crate) fn invalid_query() -> Rejection {
-    known(InvalidQuery { _p: () })
+    known(InvalidQuery { error: None })
+}
+
+// 400 Bad Request
+#[inline]
+pub(crate) fn invalid_query_error(error: crate::filters::query::QueryError) -> Rejection {
+    known(InvalidQuery { error: Some(error) })
 }
 
 // 400 Bad Request
@@ -70,7 +77,36 @@ pub(crate) fn missing_header(name: &'static str) -> Rejection {
 // 400 Bad Request
 #[inline]
 pub(crate) fn invalid_header(name: &'static str) -> Rejection {
-    known(InvalidHeader { name })
+    known(InvalidHeader {
+        name,
+        value: None,
+        expected: None,
+    })
+}
+
+// 400 Bad Request
+#[inline]
+pub(crate) fn invalid_header_value(
+    name: &'static str,
+    value: &HeaderValue,
+    expected: Option<String>,
+) -> Rejection {
+    let value = if value.is_sensitive() || is_sensitive_header(name) {
+        None
+    } else {
+        Some(String::from_utf8_lossy(value.as_bytes()).into_owned())
+    };
+    known(InvalidHeader {
+        name,
+        value,
+        expected,
+    })
+}
+
+fn is_sensitive_header(name: &str) -> bool {
+    ["authorization", "proxy-authorization", "cookie"]
+        .iter()
+        .any(|sensitive| name.eq_ignore_ascii_case(sensitive))
 }
 
 // 400 Bad Request
@@ -81,8 +117,8 @@ pub(crate) fn missing_cookie(name: &'static str) -> Rejection {
 
 // 405 Method Not Allowed
 #[inline]
-pub(crate) fn method_not_allowed() -> Rejection {
-    known(MethodNotAllowed { _p: () })
+pub(crate) fn method_not_allowed(allowed: Method) -> Rejection {
+    known(MethodNotAllowed { allowed })
 }
 
 // 411 Length Required
@@ -129,6 +165,12 @@ fn __reject_custom_compilefail() {}
 ///
 /// Can be converted into Rejection.
 ///
+/// By default, a custom rejection is a `500 Internal Server Error`. Override
+/// [`status`](Reject::status) to give it another status code, which is also
+/// used to pick the rejection to respond with when several routes rejected a
+/// request. Override [`to_response`](Reject::to_response) to fully
+/// control the response sent when it isn't recovered.
+///
 /// # Example
 ///
 /// ```
@@ -143,21 +185,84 @@ fn __reject_custom_compilefail() {}
 ///     Err::<(), _>(warp::reject::custom(RateLimited))
 /// });
 /// ```
+///
+/// With a status code and response of its own:
+///
+/// ```
+/// use warp::http::StatusCode;
+/// use warp::reject::Reject;
+/// use warp::reply::Response;
+/// use warp::Reply;
+///
+/// #[derive(Debug)]
+/// struct Unauthorized;
+///
+/// impl Reject for Unauthorized {
+///     fn status(&self) -> StatusCode {
+///         StatusCode::UNAUTHORIZED
+///     }
+///
+///     fn to_response(&self) -> Response {
+///         let reply = warp::reply::with_status("log in first", self.status());
+///         warp::reply::with_header(reply, "www-authenticate", "Bearer").into_response()
+///     }
+/// }
+/// ```
 // Require `Sized` for now to prevent passing a `Box<dyn Reject>`, since we
 // would be double-boxing it, and the downcasting wouldn't work as expected.
-pub trait Reject: fmt::Debug + Sized + Send + Sync + 'static {}
+pub trait Reject: fmt::Debug + Sized + Send + Sync + 'static {
+    /// The status code of this rejection.
+    ///
+    /// Defaults to `500 Internal Server Error`.
+    fn status(&self) -> StatusCode {
+        StatusCode::INTERNAL_SERVER_ERROR
+    }
 
-trait Cause: fmt::Debug + Send + Sync + 'static {
+    /// Render this rejection into a response.
+    ///
+    /// Defaults to a plain text response with the [`status`](Reject::status).
+    /// Server errors, such as the default status, are logged as unhandled,
+    /// and their body is this rejection's `Debug` output.
+    fn to_response(&self) -> crate::reply::Response {
+        let status = self.status();
+        let body = if status.is_server_error() {
+            tracing::error!(
+                "unhandled custom rejection, returning {} response: {:?}",
+                status.as_u16(),
+                self
+            );
+            format!("Unhandled rejection: {:?}", self)
+        } else {
+            status.canonical_reason().unwrap_or_default().to_owned()
+        };
+        let mut res = http::Response::new(Body::from(body));
+        *res.status_mut() = status;
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        res
+    }
+}
+
+pub(crate) trait Cause: fmt::Debug + Send + Sync + 'static {
     fn as_any(&self) -> &dyn Any;
+    fn status(&self) -> StatusCode;
+    fn to_response(&self) -> crate::reply::Response;
 }
 
-impl<T> Cause for T
-where
-    T: fmt::Debug + Send + Sync + 'static,
-{
+impl<T: Reject> Cause for T {
     fn as_any(&self) -> &dyn Any {
         self
     }
+
+    fn status(&self) -> StatusCode {
+        Reject::status(self)
+    }
+
+    fn to_response(&self) -> crate::reply::Response {
+        Reject::to_response(self)
+    }
 }
 
 impl dyn Cause {
@@ -182,6 +287,12 @@ enum Reason {
     Other(Box<Rejections>),
 }
 
+pub(crate) enum Preferred<'a> {
+    NotFound,
+    Known(&'a Known),
+    Custom(&'a dyn Cause),
+}
+
 enum Rejections {
     Known(Known),
     Custom(Box<dyn Cause>),
@@ -198,7 +309,7 @@ macro_rules! enum_known {
         }
 
         impl Known {
-            fn inner_as_any(&self) -> &dyn Any {
+            pub(crate) fn inner_as_any(&self) -> &dyn Any {
                 match *self {
                     $(
                     $(#[$attr])*
@@ -256,9 +367,12 @@ enum_known! {
     BodyReadError(crate::body::BodyReadError),
     BodyDeserializeError(crate::body::BodyDeserializeError),
     CorsForbidden(crate::cors::CorsForbidden),
+    Unauthorized(crate::auth::Unauthorized),
     #[cfg(feature = "websocket")]
     MissingConnectionUpgrade(crate::ws::MissingConnectionUpgrade),
     MissingExtension(crate::ext::MissingExtension),
+    #[cfg(feature = "session")]
+    SessionStoreError(crate::session::SessionStoreError),
     BodyConsumedMultipleTimes(crate::body::BodyConsumedMultipleTimes),
 }
 
@@ -317,6 +431,86 @@ impl Rejection {
             false
         }
     }
+
+    /// Returns the methods that would have been allowed, if a method filter
+    /// rejected the request.
+    ///
+    /// When several routes were tried, this collects the method of each one
+    /// that rejected with `405 Method Not Allowed`. These are the methods sent
+    /// in the `Allow` header of the default response, which a
+    /// [`recover`](crate::Filter::recover) handler can use to do the same.
+    ///
+    /// Routes that check the method before the path reject before their path
+    /// is known, so here they're included whatever the path. Before the
+    /// default response is sent, those whose path doesn't match the request
+    /// are left out, and if none are left, the request is `404 Not Found`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use warp::http::StatusCode;
+    /// use warp::{Filter, Rejection, Reply};
+    ///
+    /// async fn handle(err: Rejection) -> Result<impl Reply, Rejection> {
+    ///     if err.find::<warp::reject::MethodNotAllowed>().is_some() {
+    ///         let allow = err
+    ///             .allowed_methods()
+    ///             .iter()
+    ///             .map(|m| m.as_str())
+    ///             .collect::<Vec<_>>()
+    ///             .join(", ");
+    ///         let reply = warp::reply::with_status("nope", StatusCode::METHOD_NOT_ALLOWED);
+    ///         Ok(warp::reply::with_header(reply, "allow", allow))
+    ///     } else {
+    ///         Err(err)
+    ///     }
+    /// }
+    ///
+    /// let route = warp::get().map(warp::reply).recover(handle);
+    /// ```
+    pub fn allowed_methods(&self) -> Vec<Method> {
+        match self.reason {
+            Reason::NotFound => Vec::new(),
+            Reason::Other(ref other) => other.allowed_methods(),
+        }
+    }
+
+    // The single rejection that decides the response, as `preferred` picks
+    // among combined ones.
+    pub(crate) fn preferred(&self) -> Preferred<'_> {
+        match self.reason {
+            Reason::NotFound => Preferred::NotFound,
+            Reason::Other(ref other) => other.preferred(),
+        }
+    }
+
+    // The headers a response with this rejection's status needs, whichever
+    // renders it: `Allow` for `405` and the challenge for `401`.
+    pub(crate) fn insert_headers(&self, res: &mut crate::reply::Response) {
+        match res.status() {
+            StatusCode::METHOD_NOT_ALLOWED => {
+                let allowed = self.allowed_methods();
+                if allowed.is_empty() {
+                    return;
+                }
+                let allow = allowed
+                    .iter()
+                    .map(Method::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                if let Ok(allow) = HeaderValue::from_str(&allow) {
+                    res.headers_mut().insert(ALLOW, allow);
+                }
+            }
+            StatusCode::UNAUTHORIZED => {
+                if let Preferred::Known(Known::Unauthorized(e)) = self.preferred() {
+                    res.headers_mut()
+                        .insert(WWW_AUTHENTICATE, e.challenge().clone());
+                }
+            }
+            _ => (),
+        }
+    }
 }
 
 impl<T: Reject> From<T> for Rejection {
@@ -341,6 +535,10 @@ impl IsReject for Infallible {
     fn into_response(&self) -> crate::reply::Response {
         match *self {}
     }
+
+    fn route_methods(self, _: &RouteTable, _: &str) -> Self {
+        match self {}
+    }
 }
 
 impl IsReject for Rejection {
@@ -358,9 +556,44 @@ impl IsReject for Rejection {
                 *res.status_mut() = StatusCode::NOT_FOUND;
                 res
             }
-            Reason::Other(ref other) => other.into_response(),
+            Reason::Other(ref other) => {
+                let mut res = other.into_response();
+                self.insert_headers(&mut res);
+                res
+            }
         }
     }
+
+    fn route_methods(self, routes: &RouteTable, path: &str) -> Self {
+        let other = match self.reason {
+            Reason::Other(other) => other,
+            Reason::NotFound => return self,
+        };
+        let rejected = other.allowed_methods();
+        if rejected.is_empty() {
+            return Rejection {
+                reason: Reason::Other(other),
+            };
+        }
+
+        let path = path.strip_prefix('/').unwrap_or(path);
+        let mut rejection = match other.without_method_not_allowed() {
+            Some(rest) => Rejection {
+                reason: Reason::Other(Box::new(rest)),
+            },
+            None => not_found(),
+        };
+        for method in rejected {
+            // Filters that don't describe their routes could have rejected
+            // with any method, so only those the table knows are dropped.
+            let mut known = routes.iter().filter(|info| info.method() == Some(&method));
+            let routed = known.clone().next().is_none() || known.any(|info| info.could_match(path));
+            if routed {
+                rejection = rejection.combine(method_not_allowed(method));
+            }
+        }
+        rejection
+    }
 }
 
 impl fmt::Debug for Rejection {
@@ -387,30 +620,41 @@ impl fmt::Debug for Reason {
     }
 }
 
+// ===== Known =====
+
+impl Known {
+    pub(crate) fn status(&self) -> StatusCode {
+        match *self {
+            Known::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+            Known::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Known::InvalidHeader(_)
+            | Known::MissingHeader(_)
+            | Known::MissingCookie(_)
+            | Known::InvalidQuery(_)
+            | Known::BodyReadError(_)
+            | Known::BodyDeserializeError(_) => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "websocket")]
+            Known::MissingConnectionUpgrade(_) => StatusCode::BAD_REQUEST,
+            Known::LengthRequired(_) => StatusCode::LENGTH_REQUIRED,
+            Known::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            Known::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Known::FilePermissionError(_) | Known::CorsForbidden(_) => StatusCode::FORBIDDEN,
+            Known::FileOpenError(_)
+            | Known::MissingExtension(_)
+            | Known::BodyConsumedMultipleTimes(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            #[cfg(feature = "session")]
+            Known::SessionStoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 // ===== Rejections =====
 
 impl Rejections {
     fn status(&self) -> StatusCode {
         match *self {
-            Rejections::Known(ref k) => match *k {
-                Known::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
-                Known::InvalidHeader(_)
-                | Known::MissingHeader(_)
-                | Known::MissingCookie(_)
-                | Known::InvalidQuery(_)
-                | Known::BodyReadError(_)
-                | Known::BodyDeserializeError(_) => StatusCode::BAD_REQUEST,
-                #[cfg(feature = "websocket")]
-                Known::MissingConnectionUpgrade(_) => StatusCode::BAD_REQUEST,
-                Known::LengthRequired(_) => StatusCode::LENGTH_REQUIRED,
-                Known::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
-                Known::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
-                Known::FilePermissionError(_) | Known::CorsForbidden(_) => StatusCode::FORBIDDEN,
-                Known::FileOpenError(_)
-                | Known::MissingExtension(_)
-                | Known::BodyConsumedMultipleTimes(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            },
-            Rejections::Custom(..) => StatusCode::INTERNAL_SERVER_ERROR,
+            Rejections::Known(ref k) => k.status(),
+            Rejections::Custom(ref e) => e.status(),
             Rejections::Combined(ref a, ref b) => preferred(a, b).status(),
         }
     }
@@ -426,24 +670,19 @@ impl Rejections {
                 );
                 res
             }
-            Rejections::Custom(ref e) => {
-                tracing::error!(
-                    "unhandled custom rejection, returning 500 response: {:?}",
-                    e
-                );
-                let body = format!("Unhandled rejection: {:?}", e);
-                let mut res = http::Response::new(Body::from(body));
-                *res.status_mut() = self.status();
-                res.headers_mut().insert(
-                    CONTENT_TYPE,
-                    HeaderValue::from_static("text/plain; charset=utf-8"),
-                );
-                res
-            }
+            Rejections::Custom(ref e) => e.to_response(),
             Rejections::Combined(ref a, ref b) => preferred(a, b).into_response(),
         }
     }
 
+    fn preferred(&self) -> Preferred<'_> {
+        match *self {
+            Rejections::Known(ref e) => Preferred::Known(e),
+            Rejections::Custom(ref e) => Preferred::Custom(&**e),
+            Rejections::Combined(ref a, ref b) => preferred(a, b).preferred(),
+        }
+    }
+
     fn find<T: 'static>(&self) -> Option<&T> {
         match *self {
             Rejections::Known(ref e) => e.inner_as_any().downcast_ref(),
@@ -452,6 +691,63 @@ impl Rejections {
         }
     }
 
+    fn without_method_not_allowed(self) -> Option<Rejections> {
+        match self {
+            Rejections::Known(Known::MethodNotAllowed(_)) => None,
+            Rejections::Combined(a, b) => {
+                match (
+                    a.without_method_not_allowed(),
+                    b.without_method_not_allowed(),
+                ) {
+                    (Some(a), Some(b)) => Some(Rejections::Combined(Box::new(a), Box::new(b))),
+                    (a, b) => a.or(b),
+                }
+            }
+            other => Some(other),
+        }
+    }
+
+    // The method of every `MethodNotAllowed`, without duplicates.
+    //
+    // `or` combines rejections of later routes first, so they are sorted in
+    // the order of RFC 7231 instead, for a predictable `Allow` header.
+    fn allowed_methods(&self) -> Vec<Method> {
+        fn collect(rejections: &Rejections, methods: &mut Vec<Method>) {
+            match *rejections {
+                Rejections::Known(Known::MethodNotAllowed(ref e)) => {
+                    if !methods.contains(&e.allowed) {
+                        methods.push(e.allowed.clone());
+                    }
+                }
+                Rejections::Known(_) | Rejections::Custom(_) => (),
+                Rejections::Combined(ref a, ref b) => {
+                    collect(a, methods);
+                    collect(b, methods);
+                }
+            }
+        }
+
+        const ORDER: [Method; 9] = [
+            Method::GET,
+            Method::HEAD,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::CONNECT,
+            Method::OPTIONS,
+            Method::TRACE,
+            Method::PATCH,
+        ];
+
+        let mut methods = Vec::new();
+        collect(self, &mut methods);
+        methods.sort_by_cached_key(|m| {
+            let known = ORDER.iter().position(|o| o == m);
+            (known.unwrap_or(ORDER.len()), m.as_str().to_owned())
+        });
+        methods
+    }
+
     fn debug_list(&self, f: &mut fmt::DebugList<'_, '_>) {
         match *self {
             Rejections::Known(ref e) => {
@@ -484,16 +780,6 @@ fn preferred<'a>(a: &'a Rejections, b: &'a Rejections) -> &'a Rejections {
     }
 }
 
-unit_error! {
-    /// Invalid query
-    pub InvalidQuery: "Invalid query string"
-}
-
-unit_error! {
-    /// HTTP method not allowed
-    pub MethodNotAllowed: "HTTP method not allowed"
-}
-
 unit_error! {
     /// A content-length header is required
     pub LengthRequired: "A content-length header is required"
@@ -509,6 +795,58 @@ unit_error! {
     pub UnsupportedMediaType: "The request's content-type is not supported"
 }
 
+/// Invalid query
+#[derive(Debug)]
+pub struct InvalidQuery {
+    error: Option<crate::filters::query::QueryError>,
+}
+
+impl InvalidQuery {
+    /// Retrieve the error the query string failed to decode with, if known
+    ///
+    /// This has the field that failed, what it was expected to be and the
+    /// offending value, as far as the decoder could tell. Unlike the
+    /// rejection's own message, it can contain what the client sent.
+    pub fn error(&self) -> Option<&crate::filters::query::QueryError> {
+        self.error.as_ref()
+    }
+}
+
+impl ::std::fmt::Display for InvalidQuery {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        // The decoder's message can have the client's value in it, so it's
+        // only available from `error`.
+        f.write_str("Invalid query string")
+    }
+}
+
+impl StdError for InvalidQuery {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.error.as_ref().map(|e| e as _)
+    }
+}
+
+/// HTTP method not allowed
+#[derive(Debug)]
+pub struct MethodNotAllowed {
+    allowed: Method,
+}
+
+impl MethodNotAllowed {
+    /// Retrieve the method that would have been allowed
+    pub fn allowed(&self) -> &Method {
+        &self.allowed
+    }
+}
+
+impl ::std::fmt::Display for MethodNotAllowed {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        f.write_str("HTTP method not allowed")
+    }
+}
+
+impl StdError for MethodNotAllowed {}
+
 /// Missing request header
 #[derive(Debug)]
 pub struct MissingHeader {
@@ -534,6 +872,8 @@ impl StdError for MissingHeader {}
 #[derive(Debug)]
 pub struct InvalidHeader {
     name: &'static str,
+    value: Option<String>,
+    expected: Option<String>,
 }
 
 impl InvalidHeader {
@@ -541,6 +881,23 @@ impl InvalidHeader {
     pub fn name(&self) -> &str {
         self.name
     }
+
+    /// Retrieve the value of the header that was invalid, if known
+    ///
+    /// The values of `authorization`, `proxy-authorization` and `cookie`
+    /// headers, or any value marked as sensitive, are redacted and never
+    /// returned.
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Retrieve the type the header failed to parse into, if known
+    ///
+    /// Headers that didn't match an exact value have none, since the value
+    /// could be a secret.
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
 }
 
 impl ::std::fmt::Display for InvalidHeader {
@@ -573,7 +930,7 @@ impl ::std::fmt::Display for MissingCookie {
 impl StdError for MissingCookie {}
 
 mod sealed {
-    use super::{Reason, Rejection, Rejections};
+    use super::{Reason, Rejection, Rejections, RouteTable};
     use http::StatusCode;
     use std::convert::Infallible;
     use std::fmt;
@@ -583,6 +940,14 @@ mod sealed {
     pub trait IsReject: fmt::Debug + Send + Sync {
         fn status(&self) -> StatusCode;
         fn into_response(&self) -> crate::reply::Response;
+
+        // Drops the `405 Method Not Allowed` rejections of `routes` whose path
+        // doesn't match the request's `path`. Routes checking the method
+        // before the path reject before it's known, so this is done once a
+        // rejection is about to be sent.
+        fn route_methods(self, routes: &RouteTable, path: &str) -> Self
+        where
+            Self: Sized;
     }
 
     fn _assert_object_safe() {
@@ -688,7 +1053,7 @@ mod tests {
     fn rejection_status() {
         assert_eq!(not_found().status(), StatusCode::NOT_FOUND);
         assert_eq!(
-            method_not_allowed().status(),
+            method_not_allowed(Method::GET).status(),
             StatusCode::METHOD_NOT_ALLOWED
         );
         assert_eq!(length_required().status(), StatusCode::LENGTH_REQUIRED);
@@ -765,6 +1130,57 @@ mod tests {
         );
     }
 
+    #[derive(Debug)]
+    struct Unauthorized;
+
+    impl Reject for Unauthorized {
+        fn status(&self) -> StatusCode {
+            StatusCode::UNAUTHORIZED
+        }
+    }
+
+    #[derive(Debug)]
+    struct Teapot;
+
+    impl Reject for Teapot {
+        fn status(&self) -> StatusCode {
+            StatusCode::IM_A_TEAPOT
+        }
+
+        fn to_response(&self) -> crate::reply::Response {
+            let mut res = crate::reply::Response::new("short and stout".into());
+            *res.status_mut() = StatusCode::IM_A_TEAPOT;
+            res
+        }
+    }
+
+    #[tokio::test]
+    async fn custom_status() {
+        assert_eq!(custom(Unauthorized).status(), StatusCode::UNAUTHORIZED);
+
+        // Ranked like any other status, above 404 and 405...
+        let reject = method_not_allowed(Method::GET)
+            .combine(custom(Unauthorized))
+            .combine(not_found());
+        let resp = reject.into_response();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response_body_string(resp).await, "Unauthorized");
+
+        // ...but below a greater one.
+        let reject = custom(Unauthorized).combine(custom(Left));
+        assert_eq!(reject.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let reject = custom(Unauthorized).combine(invalid_query());
+        assert_eq!(reject.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn custom_response() {
+        let reject = not_found().combine(custom(Teapot));
+        let resp = reject.into_response();
+        assert_eq!(resp.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(response_body_string(resp).await, "short and stout");
+    }
+
     async fn response_body_string(resp: crate::reply::Response) -> String {
         let (_, body) = resp.into_parts();
         let body_bytes = hyper::body::to_bytes(body).await.expect("failed concat");
@@ -777,12 +1193,28 @@ mod tests {
 
         assert_eq!(rej.find::<Left>(), Some(&Left));
 
-        let rej = rej.combine(method_not_allowed());
+        let rej = rej.combine(method_not_allowed(Method::GET));
 
         assert_eq!(rej.find::<Left>(), Some(&Left));
         assert!(rej.find::<MethodNotAllowed>().is_some(), "MethodNotAllowed");
     }
 
+    #[test]
+    fn allowed_methods() {
+        let rej = method_not_allowed(Method::GET)
+            .combine(not_found())
+            .combine(method_not_allowed(Method::POST))
+            .combine(method_not_allowed(Method::GET));
+
+        assert_eq!(rej.allowed_methods(), [Method::GET, Method::POST]);
+
+        let resp = rej.into_response();
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(resp.headers()["allow"], "GET, POST");
+
+        assert!(not_found().allowed_methods().is_empty());
+    }
+
     #[test]
     fn size_of_rejection() {
         assert_eq!(
@@ -1,11 +1,14 @@
 use scoped_tls::scoped_thread_local;
 use std::cell::RefCell;
+use std::fmt;
 use std::mem;
 use std::net::SocketAddr;
 
 use http;
+use http::uri::{Authority, Scheme};
 use hyper::Body;
 
+use crate::reply::Response;
 use crate::Request;
 
 scoped_thread_local!(static ROUTE: RefCell<Route>);
@@ -34,6 +37,37 @@ pub(crate) struct Route {
     remote_addr: Option<SocketAddr>,
     req: Request,
     segments_index: usize,
+    on_response: OnResponse,
+    forwarded: Option<Forwarded>,
+}
+
+// What trusted proxies said about the original request.
+#[derive(Debug)]
+struct Forwarded {
+    scheme: Option<Scheme>,
+    host: Option<Authority>,
+}
+
+// Callbacks run on the response, once the filter chain has completed,
+// whether it replied or rejected. Those added by filters that rejected
+// before another one handled the request are discarded instead.
+#[derive(Default)]
+struct OnResponse(Vec<Hook>);
+
+#[cfg_attr(not(feature = "cookie-jar"), allow(dead_code))]
+enum Hook {
+    Response(ResponseFn),
+    // Undoes something done by a filter, if it's discarded.
+    Discard(DiscardFn),
+}
+
+type ResponseFn = Box<dyn FnOnce(&mut Response) + Send>;
+type DiscardFn = Box<dyn FnOnce(&mut Route) + Send>;
+
+impl fmt::Debug for OnResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("OnResponse").field(&self.0.len()).finish()
+    }
 }
 
 #[derive(Debug)]
@@ -56,6 +90,8 @@ impl Route {
             remote_addr,
             req,
             segments_index,
+            on_response: OnResponse::default(),
+            forwarded: None,
         })
     }
 
@@ -75,7 +111,6 @@ impl Route {
         self.req.extensions()
     }
 
-    #[cfg(feature = "websocket")]
     pub(crate) fn extensions_mut(&mut self) -> &mut http::Extensions {
         self.req.extensions_mut()
     }
@@ -128,6 +163,92 @@ impl Route {
         self.remote_addr
     }
 
+    pub(crate) fn peer(&self) -> Option<crate::filters::addr::Peer> {
+        use crate::filters::addr::Peer;
+
+        self.extensions()
+            .get::<Peer>()
+            .cloned()
+            .or_else(|| self.remote_addr.map(Peer::Tcp))
+    }
+
+    pub(crate) fn is_forwarded(&self) -> bool {
+        self.forwarded.is_some()
+    }
+
+    // Replaces what the connection says about the request with what a
+    // trusted proxy does.
+    pub(crate) fn set_forwarded(
+        &mut self,
+        remote_addr: Option<SocketAddr>,
+        scheme: Option<Scheme>,
+        host: Option<Authority>,
+    ) {
+        if remote_addr.is_some() {
+            self.remote_addr = remote_addr;
+        }
+        self.forwarded = Some(Forwarded { scheme, host });
+    }
+
+    pub(crate) fn forwarded_host(&self) -> Option<&Authority> {
+        self.forwarded.as_ref()?.host.as_ref()
+    }
+
+    pub(crate) fn scheme(&self) -> Option<&Scheme> {
+        self.forwarded
+            .as_ref()
+            .and_then(|f| f.scheme.as_ref())
+            .or_else(|| self.uri().scheme())
+    }
+
+    // The `Host` a trusted proxy forwarded, or else the header.
+    pub(crate) fn host(&self) -> Option<&str> {
+        match self.forwarded_host() {
+            Some(host) => Some(host.as_str()),
+            None => self
+                .headers()
+                .get(http::header::HOST)
+                .and_then(|v| v.to_str().ok()),
+        }
+    }
+
+    #[cfg(feature = "cookie-jar")]
+    pub(crate) fn on_response<F>(&mut self, func: F)
+    where
+        F: FnOnce(&mut Response) + Send + 'static,
+    {
+        self.on_response.0.push(Hook::Response(Box::new(func)));
+    }
+
+    #[cfg(feature = "cookie-jar")]
+    pub(crate) fn on_discard<F>(&mut self, func: F)
+    where
+        F: FnOnce(&mut Route) + Send + 'static,
+    {
+        self.on_response.0.push(Hook::Discard(Box::new(func)));
+    }
+
+    pub(crate) fn response_hooks(&self) -> usize {
+        self.on_response.0.len()
+    }
+
+    // Discards the hooks added since there were `len`, latest first.
+    pub(crate) fn discard_response_hooks(&mut self, len: usize) {
+        while self.on_response.0.len() > len {
+            if let Some(Hook::Discard(func)) = self.on_response.0.pop() {
+                func(self);
+            }
+        }
+    }
+
+    pub(crate) fn finish_response(&mut self, res: &mut Response) {
+        for hook in self.on_response.0.drain(..) {
+            if let Hook::Response(func) = hook {
+                func(res);
+            }
+        }
+    }
+
     pub(crate) fn take_body(&mut self) -> Option<Body> {
         match self.body {
             BodyState::Ready => {
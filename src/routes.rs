@@ -0,0 +1,331 @@
+//! Route introspection
+//!
+//! A [`Filter`](crate::Filter) is normally opaque: once many routes have been
+//! combined with `or`, there's no way to look inside and see what requests
+//! they match. The built-in path and method filters record what they match,
+//! and that is combined through `and`, `or`, `boxed` and the other
+//! combinators, so that [`Filter::routes`](crate::Filter::routes) can list
+//! every route a filter serves.
+//!
+//! Routes can additionally be given a name with
+//! [`Filter::named`](crate::Filter::named).
+//!
+//! # Example
+//!
+//! ```
+//! use warp::Filter;
+//!
+//! let get_user = warp::get()
+//!     .and(warp::path!("users" / u32))
+//!     .map(|id| format!("user #{}", id))
+//!     .named("get_user");
+//!
+//! let delete_user = warp::delete()
+//!     .and(warp::path!("users" / u32))
+//!     .map(|id| format!("deleted #{}", id))
+//!     .named("delete_user");
+//!
+//! let routes = get_user.or(delete_user);
+//!
+//! for route in routes.routes().iter() {
+//!     println!("{}", route);
+//! }
+//! ```
+
+use std::fmt;
+use std::slice;
+
+use http::Method;
+use serde::de::DeserializeOwned;
+
+/// The set of routes a [`Filter`](crate::Filter) matches.
+///
+/// Returned by [`Filter::routes`](crate::Filter::routes).
+#[derive(Clone, Debug)]
+pub struct RouteTable {
+    routes: Vec<RouteInfo>,
+}
+
+/// Information about a single route.
+#[derive(Clone, Debug, Default)]
+pub struct RouteInfo {
+    name: Option<&'static str>,
+    method: Option<Method>,
+    segments: Vec<Segment>,
+    end: bool,
+    #[cfg(feature = "openapi")]
+    pub(crate) operation: crate::openapi::Operation,
+}
+
+/// A segment of a route's path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// A segment that must match exactly, from `warp::path("foo")`.
+    Exact(String),
+    /// A segment extracted as a parameter, from `warp::path::param::<T>()`.
+    ///
+    /// Contains the name of the parameter type.
+    Param(String),
+    /// The rest of the path, from `warp::path::tail()`.
+    Tail,
+}
+
+impl RouteTable {
+    /// Returns an iterator over the routes.
+    pub fn iter(&self) -> slice::Iter<'_, RouteInfo> {
+        self.routes.iter()
+    }
+
+    /// Returns the number of routes.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns whether there are no routes.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Find a route by its name.
+    pub fn get(&self, name: &str) -> Option<&RouteInfo> {
+        self.routes.iter().find(|route| route.name == Some(name))
+    }
+
+    // A filter that doesn't say anything about routing.
+    pub(crate) fn any() -> RouteTable {
+        RouteTable::one(RouteInfo::default())
+    }
+
+    pub(crate) fn one(route: RouteInfo) -> RouteTable {
+        RouteTable {
+            routes: vec![route],
+        }
+    }
+
+    // Both filters must match, so every route of `self` is followed by every
+    // route of `other`.
+    pub(crate) fn and(self, other: RouteTable) -> RouteTable {
+        let mut routes = Vec::with_capacity(self.routes.len() * other.routes.len());
+        for first in &self.routes {
+            for second in &other.routes {
+                routes.push(first.clone().and(second.clone()));
+            }
+        }
+        RouteTable { routes }
+    }
+
+    // Either filter may match.
+    pub(crate) fn or(mut self, other: RouteTable) -> RouteTable {
+        self.routes.extend(other.routes);
+        self
+    }
+
+    pub(crate) fn map<F>(mut self, func: F) -> RouteTable
+    where
+        F: Fn(&mut RouteInfo),
+    {
+        self.routes.iter_mut().for_each(func);
+        self
+    }
+}
+
+impl<'a> IntoIterator for &'a RouteTable {
+    type Item = &'a RouteInfo;
+    type IntoIter = slice::Iter<'a, RouteInfo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl IntoIterator for RouteTable {
+    type Item = RouteInfo;
+    type IntoIter = std::vec::IntoIter<RouteInfo>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.routes.into_iter()
+    }
+}
+
+impl fmt::Display for RouteTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for route in &self.routes {
+            writeln!(f, "{}", route)?;
+        }
+        Ok(())
+    }
+}
+
+impl RouteInfo {
+    /// The name given with [`Filter::named`](crate::Filter::named), if any.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// The method this route requires, or `None` if it accepts any method.
+    pub fn method(&self) -> Option<&Method> {
+        self.method.as_ref()
+    }
+
+    /// The segments of the path this route matches.
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Whether this route only matches a prefix of the path.
+    ///
+    /// This is `false` for routes ending in `warp::path::end()` (such as
+    /// those built with `warp::path!`) or `warp::path::tail()`.
+    pub fn is_prefix(&self) -> bool {
+        !self.end
+    }
+
+    /// The path template of this route, such as `/users/{u32}`.
+    ///
+    /// Prefix routes end with `/..`, like the `path!` prefix syntax.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.segments {
+            path.push('/');
+            match segment {
+                Segment::Exact(s) => path.push_str(s),
+                Segment::Param(ty) => {
+                    path.push('{');
+                    path.push_str(ty);
+                    path.push('}');
+                }
+                Segment::Tail => path.push('*'),
+            }
+        }
+        if !self.end {
+            path.push_str("/..");
+        } else if path.is_empty() {
+            path.push('/');
+        }
+        path
+    }
+
+    // Whether the rest of a request's path could match this route, assuming
+    // its parameters parse.
+    pub(crate) fn could_match(&self, path: &str) -> bool {
+        let mut rest = path;
+        for segment in &self.segments {
+            let (seg, after) = match rest.find('/') {
+                Some(idx) => (&rest[..idx], &rest[idx + 1..]),
+                None => (rest, ""),
+            };
+            match segment {
+                Segment::Tail => return true,
+                Segment::Exact(exact) if exact != seg => return false,
+                Segment::Param(_) if seg.is_empty() => return false,
+                _ => (),
+            }
+            rest = after;
+        }
+        !self.end || rest.is_empty()
+    }
+
+    pub(crate) fn set_name(&mut self, name: &'static str) {
+        self.name = Some(name);
+    }
+
+    pub(crate) fn set_method(&mut self, method: Method) {
+        self.method = Some(method);
+    }
+
+    pub(crate) fn push(&mut self, segment: Segment) {
+        if segment == Segment::Tail {
+            self.end = true;
+        }
+        self.segments.push(segment);
+    }
+
+    pub(crate) fn set_end(&mut self) {
+        self.end = true;
+    }
+
+    // The following record what a route expects from a request, for
+    // generating OpenAPI documents. They do nothing without the `openapi`
+    // feature.
+
+    pub(crate) fn query<T: DeserializeOwned>(&mut self) {
+        #[cfg(feature = "openapi")]
+        self.operation.query::<T>();
+    }
+
+    pub(crate) fn body<T: DeserializeOwned>(&mut self, content_type: &'static str) {
+        #[cfg(feature = "openapi")]
+        self.operation.body::<T>(content_type);
+        #[cfg(not(feature = "openapi"))]
+        let _ = content_type;
+    }
+
+    pub(crate) fn header(&mut self, name: &'static str, required: bool) {
+        #[cfg(feature = "openapi")]
+        self.operation.header(name, required);
+        #[cfg(not(feature = "openapi"))]
+        let _ = (name, required);
+    }
+
+    fn and(mut self, other: RouteInfo) -> RouteInfo {
+        self.name = self.name.or(other.name);
+        if other.method.is_some() {
+            self.method = other.method;
+        }
+        self.segments.extend(other.segments);
+        self.end |= other.end;
+        #[cfg(feature = "openapi")]
+        self.operation.and(other.operation);
+        self
+    }
+}
+
+impl fmt::Display for RouteInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.method {
+            Some(ref method) => write!(f, "{:<7} ", method.as_str())?,
+            None => f.write_str("*       ")?,
+        }
+        f.write_str(&self.path())?;
+        if let Some(name) = self.name {
+            write!(f, " ({})", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Shorten a `std::any::type_name`, such as `alloc::string::String`, to
+/// what a user would write, such as `String`.
+pub(crate) fn type_name<T: ?Sized>() -> String {
+    let full = std::any::type_name::<T>();
+    let mut short = String::with_capacity(full.len());
+    let mut ident = String::new();
+    for c in full.chars() {
+        match c {
+            ':' => ident.clear(),
+            c if c.is_alphanumeric() || c == '_' => ident.push(c),
+            c => {
+                short.push_str(&ident);
+                ident.clear();
+                short.push(c);
+            }
+        }
+    }
+    short.push_str(&ident);
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_type_names() {
+        assert_eq!(type_name::<u32>(), "u32");
+        assert_eq!(type_name::<String>(), "String");
+        assert_eq!(
+            type_name::<std::collections::HashMap<String, Vec<u8>>>(),
+            "HashMap<String, Vec<u8>>"
+        );
+    }
+}
@@ -1,11 +1,15 @@
 #[cfg(feature = "tls")]
-use crate::tls::TlsConfigBuilder;
+use crate::tls::rustls::{ProtocolVersion, ServerConfig, SupportedCipherSuite};
+#[cfg(feature = "tls")]
+use crate::tls::{LiveConfig, Reloader, SniFallback, Source, TlsConfigBuilder};
 use std::convert::Infallible;
 use std::error::Error as StdError;
 use std::future::Future;
 use std::net::SocketAddr;
 #[cfg(feature = "tls")]
 use std::path::Path;
+use std::pin::Pin;
+use std::time::Duration;
 
 use futures::{future, FutureExt, TryFuture, TryStream, TryStreamExt};
 use hyper::server::conn::AddrIncoming;
@@ -15,9 +19,16 @@ use tokio::io::{AsyncRead, AsyncWrite};
 use tracing::Instrument;
 
 use crate::filter::Filter;
-use crate::reject::IsReject;
+use crate::filters::shutdown::{Executor, Shutdown};
+use crate::listener::{Incoming, Listener};
+use crate::problem::internal::WithProblems;
+use crate::problem::Problems;
+use crate::proxy_protocol::{ProxyIncoming, ProxyProtocol};
+use crate::reject::{IsReject, Rejection};
 use crate::reply::Reply;
 use crate::transport::Transport;
+#[cfg(unix)]
+use crate::unix::UnixSocket;
 
 /// Create a `Server` with the provided `Filter`.
 pub fn serve<F>(filter: F) -> Server<F>
@@ -28,6 +39,10 @@ where
 {
     Server {
         pipeline: false,
+        proxy_protocol: None,
+        shutdown: Shutdown::new(),
+        drain_timeout: None,
+        conn: ConnConfig::default(),
         filter,
     }
 }
@@ -36,9 +51,36 @@ where
 #[derive(Debug)]
 pub struct Server<F> {
     pipeline: bool,
+    proxy_protocol: Option<ProxyProtocol>,
+    shutdown: Shutdown,
+    drain_timeout: Option<Duration>,
+    conn: ConnConfig,
     filter: F,
 }
 
+// Settings of hyper and of accepted TCP connections. Hyper's defaults are
+// used for whatever isn't set.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ConnConfig {
+    http1_keepalive: Option<bool>,
+    http1_half_close: Option<bool>,
+    http1_max_buf_size: Option<usize>,
+    http1_header_read_timeout: Option<Duration>,
+    #[cfg(feature = "http2")]
+    http2_max_concurrent_streams: Option<u32>,
+    #[cfg(feature = "http2")]
+    http2_initial_stream_window_size: Option<u32>,
+    #[cfg(feature = "http2")]
+    http2_keep_alive_interval: Option<Duration>,
+    #[cfg(feature = "http2")]
+    http2_keep_alive_timeout: Option<Duration>,
+    #[cfg(feature = "http2")]
+    http2_adaptive_window: Option<bool>,
+    tcp_keepalive: Option<Duration>,
+    tcp_sleep_on_accept_errors: Option<bool>,
+    limits: Limits,
+}
+
 /// A Warp Server ready to filter requests over TLS.
 ///
 /// *This type requires the `"tls"` feature.*
@@ -46,17 +88,30 @@ pub struct Server<F> {
 pub struct TlsServer<F> {
     server: Server<F>,
     tls: TlsConfigBuilder,
+    reloader: Option<Reloader>,
 }
 
 // Getting all various generic bounds to make this a re-usable method is
 // very complicated, so instead this is just a macro.
 macro_rules! into_service {
-    ($into:expr) => {{
+    ($into:expr, $shutdown:expr) => {{
         let inner = crate::service($into);
+        let shutdown = $shutdown.clone();
         make_service_fn(move |transport| {
             let inner = inner.clone();
+            let shutdown = shutdown.clone();
+            // Hyper drops the service once the connection is closed.
+            let connection = shutdown.connection();
             let remote_addr = Transport::remote_addr(transport);
+            let peer = Transport::peer(transport);
+            #[cfg(feature = "tls")]
+            let tls_info = Transport::tls_info(transport);
             future::ok::<_, Infallible>(service_fn(move |req| {
+                let _ = &connection;
+                let req = crate::shutdown::with_shutdown(req, &shutdown);
+                let req = crate::addr::with_peer(req, &peer);
+                #[cfg(feature = "tls")]
+                let req = crate::tls::with_info(req, &tls_info);
                 inner.call_with_addr(req, remote_addr)
             }))
         })
@@ -64,31 +119,36 @@ macro_rules! into_service {
 }
 
 macro_rules! addr_incoming {
-    ($addr:expr) => {{
+    ($server:expr, $addr:expr) => {{
         let mut incoming = AddrIncoming::bind($addr)?;
-        incoming.set_nodelay(true);
+        $server.conn.tcp(&mut incoming);
         let addr = incoming.local_addr();
-        (addr, incoming)
+        (
+            addr,
+            ProxyIncoming::new(incoming, $server.proxy_protocol.clone()),
+        )
     }};
 }
 
 macro_rules! bind_inner {
     ($this:ident, $addr:expr) => {{
-        let service = into_service!($this.filter);
-        let (addr, incoming) = addr_incoming!($addr);
-        let srv = HyperServer::builder(incoming)
-            .http1_pipeline_flush($this.pipeline)
-            .serve(service);
+        let (addr, incoming) = addr_incoming!($this, $addr);
+        let srv = $this
+            .builder(incoming)
+            .serve(into_service!($this.filter, $this.shutdown));
         Ok::<_, hyper::Error>((addr, srv))
     }};
 
     (tls: $this:ident, $addr:expr) => {{
-        let service = into_service!($this.server.filter);
-        let (addr, incoming) = addr_incoming!($addr);
-        let tls = $this.tls.build()?;
-        let srv = HyperServer::builder(crate::tls::TlsAcceptor::new(tls, incoming))
-            .http1_pipeline_flush($this.server.pipeline)
-            .serve(service);
+        let (addr, incoming) = addr_incoming!($this.server, $addr);
+        let tls = std::sync::Arc::new(LiveConfig::new($this.tls)?);
+        if let Some(ref reloader) = $this.reloader {
+            reloader.attach(&tls);
+        }
+        let srv = $this
+            .server
+            .builder(crate::tls::TlsAcceptor::new(tls, incoming))
+            .serve(into_service!($this.server.filter, $this.server.shutdown));
         Ok::<_, Box<dyn std::error::Error + Send + Sync>>((addr, srv))
     }};
 }
@@ -119,6 +179,24 @@ macro_rules! try_bind {
     }};
 }
 
+mod limit;
+mod multi;
+
+pub use self::limit::LimitPolicy;
+use self::limit::{Limited, Limits};
+pub use self::multi::{LocalAddr, MultiServer};
+
+fn only_inherited() -> Result<Listener, crate::Error> {
+    let mut listeners = Listener::inherited()?;
+    if listeners.len() != 1 {
+        return Err(crate::Error::new(format!(
+            "expected 1 inherited listener, found {}",
+            listeners.len()
+        )));
+    }
+    Ok(listeners.remove(0))
+}
+
 // ===== impl Server =====
 
 impl<F> Server<F>
@@ -242,6 +320,49 @@ where
         Ok((addr, srv))
     }
 
+    /// Bind to a Unix domain socket, returning a `Future` that can be
+    /// executed on the current runtime.
+    ///
+    /// See [`UnixSocket`](crate::UnixSocket) for the options, and
+    /// [`warp::addr::peer`](crate::addr::peer) to see who connected.
+    ///
+    /// *This function is only available on Unix.*
+    ///
+    /// # Panics
+    ///
+    /// Panics if we are unable to bind to the provided path.
+    #[cfg(unix)]
+    pub fn bind_unix(self, socket: impl Into<UnixSocket>) -> impl Future<Output = ()> + 'static {
+        let socket = socket.into();
+        let path = socket.path().to_owned();
+        self.try_bind_unix(socket).unwrap_or_else(|e| {
+            panic!("error binding to {}: {}", path.display(), e);
+        })
+    }
+
+    /// Tries to bind to a Unix domain socket.
+    ///
+    /// Returns a `Result` which fails in case we are unable to bind with the
+    /// underlying error.
+    ///
+    /// *This function is only available on Unix.*
+    #[cfg(unix)]
+    pub fn try_bind_unix(
+        self,
+        socket: impl Into<UnixSocket>,
+    ) -> Result<impl Future<Output = ()> + 'static, crate::Error> {
+        let incoming = socket.into().bind().map_err(crate::Error::new)?;
+        let srv = self
+            .builder(incoming)
+            .serve(into_service!(self.filter, self.shutdown))
+            .map(|result| {
+                if let Err(err) = result {
+                    tracing::error!("server error: {}", err)
+                }
+            });
+        Ok(srv)
+    }
+
     /// Create a server with graceful shutdown signal.
     ///
     /// When the signal completes, the server will start the graceful shutdown
@@ -280,13 +401,16 @@ where
         addr: impl Into<SocketAddr> + 'static,
         signal: impl Future<Output = ()> + Send + 'static,
     ) -> (SocketAddr, impl Future<Output = ()> + 'static) {
+        let (shutdown, drain_timeout) = (self.shutdown.clone(), self.drain_timeout);
         let (addr, srv) = bind!(self, addr);
-        let fut = srv.with_graceful_shutdown(signal).map(|result| {
-            if let Err(err) = result {
-                tracing::error!("server error: {}", err)
-            }
-        });
-        (addr, fut)
+        let fut = srv
+            .with_graceful_shutdown(shutdown.signal(signal))
+            .map(|result| {
+                if let Err(err) = result {
+                    tracing::error!("server error: {}", err)
+                }
+            });
+        (addr, shutdown.deadline(fut, drain_timeout))
     }
 
     /// Create a server with graceful shutdown signal.
@@ -299,14 +423,129 @@ where
         signal: impl Future<Output = ()> + Send + 'static,
     ) -> Result<(SocketAddr, impl Future<Output = ()> + 'static), crate::Error> {
         let addr = addr.into();
+        let (shutdown, drain_timeout) = (self.shutdown.clone(), self.drain_timeout);
         let (addr, srv) = try_bind!(self, &addr).map_err(crate::Error::new)?;
-        let srv = srv.with_graceful_shutdown(signal).map(|result| {
+        let srv = srv
+            .with_graceful_shutdown(shutdown.signal(signal))
+            .map(|result| {
+                if let Err(err) = result {
+                    tracing::error!("server error: {}", err)
+                }
+            });
+
+        Ok((addr, shutdown.deadline(srv, drain_timeout)))
+    }
+
+    /// Serve on a socket that is already listening, returning a `Future`
+    /// that can be executed on the current runtime.
+    ///
+    /// See [`Listener`](crate::Listener) for where these come from.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the listener can't be registered with the runtime.
+    pub fn bind_listener(
+        self,
+        listener: impl Into<Listener>,
+    ) -> impl Future<Output = ()> + 'static {
+        self.try_bind_listener(listener)
+            .unwrap_or_else(|e| panic!("error serving listener: {}", e))
+    }
+
+    /// Tries to serve on a socket that is already listening.
+    ///
+    /// Returns a `Result` which fails in case the listener can't be registered
+    /// with the runtime.
+    pub fn try_bind_listener(
+        self,
+        listener: impl Into<Listener>,
+    ) -> Result<impl Future<Output = ()> + 'static, crate::Error> {
+        self.serve_listener(listener.into(), future::pending())
+    }
+
+    /// Serve on a socket that is already listening, with a graceful shutdown
+    /// signal.
+    ///
+    /// When the signal completes, the server will start the graceful shutdown
+    /// process.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the listener can't be registered with the runtime.
+    pub fn bind_listener_with_graceful_shutdown(
+        self,
+        listener: impl Into<Listener>,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> impl Future<Output = ()> + 'static {
+        self.serve_listener(listener.into(), signal)
+            .unwrap_or_else(|e| panic!("error serving listener: {}", e))
+    }
+
+    /// Serve on the socket passed to this process by systemd's socket
+    /// activation, or a tool like `systemfd`.
+    ///
+    /// Returns an error if there isn't exactly one such socket. Use
+    /// [`Listener::inherited`](crate::Listener::inherited) to handle several,
+    /// or none.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use warp::Filter;
+    ///
+    /// # async fn run() {
+    /// let routes = warp::any().map(|| "Hello, World!");
+    ///
+    /// warp::serve(routes)
+    ///     .bind_inherited()
+    ///     .expect("run with socket activation")
+    ///     .await;
+    /// # }
+    /// ```
+    pub fn bind_inherited(self) -> Result<impl Future<Output = ()> + 'static, crate::Error> {
+        let listener = only_inherited()?;
+        self.serve_listener(listener, future::pending())
+    }
+
+    /// Serve on the socket passed to this process by systemd's socket
+    /// activation, with a graceful shutdown signal.
+    ///
+    /// Returns an error if there isn't exactly one such socket.
+    pub fn bind_inherited_with_graceful_shutdown(
+        self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<impl Future<Output = ()> + 'static, crate::Error> {
+        let listener = only_inherited()?;
+        self.serve_listener(listener, signal)
+    }
+
+    fn serve_listener(
+        self,
+        listener: Listener,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<Pin<Box<dyn Future<Output = ()> + Send>>, crate::Error> {
+        let incoming = listener.into_incoming(self.proxy_protocol.clone(), &self.conn)?;
+        let signal = self.shutdown.signal(signal);
+        // Each kind of connection needs its own service.
+        let srv: Pin<Box<dyn Future<Output = Result<(), hyper::Error>> + Send>> = match incoming {
+            Incoming::Tcp(incoming) => Box::pin(
+                self.builder(incoming)
+                    .serve(into_service!(self.filter, self.shutdown))
+                    .with_graceful_shutdown(signal),
+            ),
+            #[cfg(unix)]
+            Incoming::Unix(incoming) => Box::pin(
+                self.builder(incoming)
+                    .serve(into_service!(self.filter, self.shutdown))
+                    .with_graceful_shutdown(signal),
+            ),
+        };
+        let srv = srv.map(|result| {
             if let Err(err) = result {
                 tracing::error!("server error: {}", err)
             }
         });
-
-        Ok((addr, srv))
+        Ok(Box::pin(self.shutdown.deadline(srv, self.drain_timeout)))
     }
 
     /// Setup this `Server` with a specific stream of incoming connections.
@@ -345,20 +584,22 @@ where
         I::Error: Into<Box<dyn StdError + Send + Sync>>,
     {
         let incoming = incoming.map_ok(crate::transport::LiftIo);
-        let service = into_service!(self.filter);
-        let pipeline = self.pipeline;
+        let builder = self.builder(hyper::server::accept::from_stream(incoming.into_stream()));
+        let service = into_service!(self.filter, self.shutdown);
+        let shutdown = self.shutdown;
+        let drain_timeout = self.drain_timeout;
 
         async move {
-            let srv =
-                HyperServer::builder(hyper::server::accept::from_stream(incoming.into_stream()))
-                    .http1_pipeline_flush(pipeline)
-                    .serve(service)
-                    .with_graceful_shutdown(signal)
-                    .await;
-
-            if let Err(err) = srv {
-                tracing::error!("server error: {}", err);
-            }
+            let srv = builder
+                .serve(service)
+                .with_graceful_shutdown(shutdown.signal(signal))
+                .map(|result| {
+                    if let Err(err) = result {
+                        tracing::error!("server error: {}", err);
+                    }
+                });
+
+            shutdown.deadline(srv, drain_timeout).await;
         }
         .instrument(tracing::info_span!(
             "Server::serve_incoming_with_graceful_shutdown"
@@ -371,11 +612,9 @@ where
         I::Ok: Transport + Send + 'static + Unpin,
         I::Error: Into<Box<dyn StdError + Send + Sync>>,
     {
-        let service = into_service!(self.filter);
-
-        let srv = HyperServer::builder(hyper::server::accept::from_stream(incoming.into_stream()))
-            .http1_pipeline_flush(self.pipeline)
-            .serve(service)
+        let srv = self
+            .builder(hyper::server::accept::from_stream(incoming.into_stream()))
+            .serve(into_service!(self.filter, self.shutdown))
             .await;
 
         if let Err(err) = srv {
@@ -383,6 +622,264 @@ where
         }
     }
 
+    /// Serve on several listeners at once, such as IPv4 and IPv6, with and
+    /// without TLS, sharing one graceful shutdown.
+    ///
+    /// See [`MultiServer`](crate::MultiServer) for more.
+    pub fn multi(self) -> MultiServer<F> {
+        MultiServer::new(self)
+    }
+
+    /// Render the rejections of every route as problem details.
+    ///
+    /// Unlike wrapping the filter given to [`serve`] with the
+    /// [`Problems`](crate::problem::Problems), this also renders `404 Not
+    /// Found` and `405 Method Not Allowed` rejections, since no other route
+    /// can handle the request.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use warp::Filter;
+    ///
+    /// let routes = warp::path("hello").map(warp::reply);
+    /// let server = warp::serve(routes).problems(warp::problem::json());
+    /// ```
+    pub fn problems(self, problems: Problems) -> Server<WithProblems<F>>
+    where
+        F::Error: Into<Rejection>,
+    {
+        Server {
+            pipeline: self.pipeline,
+            proxy_protocol: self.proxy_protocol,
+            shutdown: self.shutdown,
+            drain_timeout: self.drain_timeout,
+            conn: self.conn,
+            filter: problems.serve(self.filter),
+        }
+    }
+
+    /// Expect every connection to start with a [PROXY protocol] header.
+    ///
+    /// This applies to the `run` and `bind` methods, including with TLS, where
+    /// the header is read before the TLS handshake. It doesn't apply to
+    /// connections from `run_incoming` and `serve_incoming`.
+    ///
+    /// See [`ProxyProtocol`](crate::ProxyProtocol) for more.
+    ///
+    /// [PROXY protocol]: https://www.haproxy.org/download/2.0/doc/proxy-protocol.txt
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use warp::{Filter, ProxyProtocol};
+    ///
+    /// let routes = warp::path("hello").map(warp::reply);
+    /// let server = warp::serve(routes).proxy_protocol(ProxyProtocol::new());
+    /// ```
+    pub fn proxy_protocol(mut self, config: ProxyProtocol) -> Self {
+        self.proxy_protocol = Some(config);
+        self
+    }
+
+    /// Use a [`Shutdown`](crate::shutdown::Shutdown) handle, to see how many
+    /// connections are open, or to share the graceful shutdown of several
+    /// servers.
+    ///
+    /// Each server otherwise has its own.
+    pub fn shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Close the connections still open this long after a graceful shutdown
+    /// started.
+    ///
+    /// Otherwise, the server waits for them to finish however long it takes.
+    /// See [`warp::shutdown`](crate::shutdown) for letting long-lived
+    /// responses finish sooner.
+    ///
+    /// This applies to the `with_graceful_shutdown` methods.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether to use keep-alive for HTTP/1 connections.
+    ///
+    /// Default is `true`.
+    pub fn http1_keepalive(mut self, val: bool) -> Self {
+        self.conn.http1_keepalive = Some(val);
+        self
+    }
+
+    /// Sets whether HTTP/1 connections should support half-closures.
+    ///
+    /// Clients can shut down their write side while waiting for the response.
+    /// Setting this to `true` keeps the connection open when the end of the
+    /// request is read that way.
+    ///
+    /// Default is `false`.
+    pub fn http1_half_close(mut self, val: bool) -> Self {
+        self.conn.http1_half_close = Some(val);
+        self
+    }
+
+    /// Sets the maximum size of the buffer for reading HTTP/1 requests, which
+    /// limits the size of their headers.
+    ///
+    /// Default is about 400kb.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is less than 8192.
+    pub fn http1_max_buf_size(mut self, max: usize) -> Self {
+        assert!(max >= 8192, "the max_buf_size cannot be smaller than 8192");
+        self.conn.http1_max_buf_size = Some(max);
+        self
+    }
+
+    /// Sets a timeout for reading the headers of an HTTP/1 request, after
+    /// which the connection is closed.
+    ///
+    /// Default is no timeout.
+    pub fn http1_header_read_timeout(mut self, timeout: Duration) -> Self {
+        self.conn.http1_header_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the [`SETTINGS_MAX_CONCURRENT_STREAMS`][spec] option for HTTP/2
+    /// connections.
+    ///
+    /// Default is no limit.
+    ///
+    /// [spec]: https://http2.github.io/http2-spec/#SETTINGS_MAX_CONCURRENT_STREAMS
+    ///
+    /// *This function requires the `"http2"` feature.*
+    #[cfg(feature = "http2")]
+    pub fn http2_max_concurrent_streams(mut self, max: u32) -> Self {
+        self.conn.http2_max_concurrent_streams = Some(max);
+        self
+    }
+
+    /// Sets the [`SETTINGS_INITIAL_WINDOW_SIZE`][spec] option for HTTP/2
+    /// stream-level flow control.
+    ///
+    /// [spec]: https://http2.github.io/http2-spec/#SETTINGS_INITIAL_WINDOW_SIZE
+    ///
+    /// *This function requires the `"http2"` feature.*
+    #[cfg(feature = "http2")]
+    pub fn http2_initial_stream_window_size(mut self, size: u32) -> Self {
+        self.conn.http2_initial_stream_window_size = Some(size);
+        self
+    }
+
+    /// Sets whether to use an adaptive flow control for HTTP/2, which
+    /// overrides [`http2_initial_stream_window_size`](Server::http2_initial_stream_window_size).
+    ///
+    /// Default is `false`.
+    ///
+    /// *This function requires the `"http2"` feature.*
+    #[cfg(feature = "http2")]
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.conn.http2_adaptive_window = Some(enabled);
+        self
+    }
+
+    /// Sets an interval at which HTTP/2 ping frames are sent to keep
+    /// connections alive.
+    ///
+    /// Default is not to send any.
+    ///
+    /// *This function requires the `"http2"` feature.*
+    #[cfg(feature = "http2")]
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.conn.http2_keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// Sets how long to wait for the acknowledgement of an HTTP/2 keep-alive
+    /// ping before closing the connection.
+    ///
+    /// Does nothing unless [`http2_keep_alive_interval`](Server::http2_keep_alive_interval)
+    /// is set. Default is 20 seconds.
+    ///
+    /// *This function requires the `"http2"` feature.*
+    #[cfg(feature = "http2")]
+    pub fn http2_keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.conn.http2_keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long accepted TCP connections stay idle before keepalive
+    /// probes are sent.
+    ///
+    /// Default is not to send any.
+    pub fn tcp_keepalive(mut self, time: Duration) -> Self {
+        self.conn.tcp_keepalive = Some(time);
+        self
+    }
+
+    /// Sets whether to sleep for a second after an error accepting a TCP
+    /// connection, such as when the process is out of file descriptors.
+    ///
+    /// Otherwise, the error stops the server. Default is `true`.
+    pub fn tcp_sleep_on_accept_errors(mut self, val: bool) -> Self {
+        self.conn.tcp_sleep_on_accept_errors = Some(val);
+        self
+    }
+
+    /// Limits the number of connections open at once to `max`, across all
+    /// listeners of the server.
+    ///
+    /// What happens to connections beyond the limit is decided by
+    /// [`max_connections_policy`](Server::max_connections_policy). Upgraded
+    /// connections, such as WebSockets, count until they close.
+    ///
+    /// Slow clients can still hold on to connections; see
+    /// [`http1_header_read_timeout`](Server::http1_header_read_timeout). The
+    /// number of open connections can be read from the
+    /// [`Shutdown`](crate::shutdown::Shutdown) handle given to
+    /// [`Server::shutdown`](Server::shutdown).
+    ///
+    /// Default is no limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        assert!(max > 0, "max_connections must be at least 1");
+        self.conn.limits.max_connections(max);
+        self
+    }
+
+    /// Sets what happens to new connections once there are
+    /// [`max_connections`](Server::max_connections) open.
+    ///
+    /// Default is [`LimitPolicy::Pause`].
+    pub fn max_connections_policy(mut self, policy: LimitPolicy) -> Self {
+        self.conn.limits.policy(policy);
+        self
+    }
+
+    /// Limits the number of connections open at once from a single IP address
+    /// to `max`.
+    ///
+    /// Connections beyond the limit are closed right away. The address is
+    /// the one given by the PROXY protocol, if it's enabled. Connections
+    /// without an IP address, such as over Unix sockets, aren't limited.
+    ///
+    /// Default is no limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is 0.
+    pub fn max_connections_per_ip(mut self, max: usize) -> Self {
+        assert!(max > 0, "max_connections_per_ip must be at least 1");
+        self.conn.limits.max_connections_per_ip(max);
+        self
+    }
+
     // Generally shouldn't be used, as it can slow down non-pipelined responses.
     //
     // It's only real use is to make silly pipeline benchmarks look better.
@@ -400,6 +897,64 @@ where
         TlsServer {
             server: self,
             tls: TlsConfigBuilder::new(),
+            reloader: None,
+        }
+    }
+}
+
+impl<F> Server<F> {
+    fn builder<I>(&self, incoming: I) -> hyper::server::Builder<Limited<I>, Executor> {
+        let builder = HyperServer::builder(self.conn.limits.wrap(incoming))
+            .executor(self.shutdown.executor())
+            .http1_pipeline_flush(self.pipeline);
+        self.conn.hyper(builder)
+    }
+}
+
+// ===== impl ConnConfig =====
+
+impl ConnConfig {
+    fn hyper<I, E>(
+        &self,
+        mut builder: hyper::server::Builder<I, E>,
+    ) -> hyper::server::Builder<I, E> {
+        if let Some(val) = self.http1_keepalive {
+            builder = builder.http1_keepalive(val);
+        }
+        if let Some(val) = self.http1_half_close {
+            builder = builder.http1_half_close(val);
+        }
+        if let Some(max) = self.http1_max_buf_size {
+            builder = builder.http1_max_buf_size(max);
+        }
+        if let Some(timeout) = self.http1_header_read_timeout {
+            builder = builder.http1_header_read_timeout(timeout);
+        }
+        #[cfg(feature = "http2")]
+        {
+            builder = builder
+                .http2_max_concurrent_streams(self.http2_max_concurrent_streams)
+                .http2_initial_stream_window_size(self.http2_initial_stream_window_size);
+            if let Some(enabled) = self.http2_adaptive_window {
+                builder = builder.http2_adaptive_window(enabled);
+            }
+            if let Some(interval) = self.http2_keep_alive_interval {
+                builder = builder.http2_keep_alive_interval(interval);
+            }
+            if let Some(timeout) = self.http2_keep_alive_timeout {
+                builder = builder.http2_keep_alive_timeout(timeout);
+            }
+        }
+        builder
+    }
+
+    pub(crate) fn tcp(&self, incoming: &mut AddrIncoming) {
+        incoming.set_nodelay(true);
+        if self.tcp_keepalive.is_some() {
+            incoming.set_keepalive(self.tcp_keepalive);
+        }
+        if let Some(val) = self.tcp_sleep_on_accept_errors {
+            incoming.set_sleep_on_errors(val);
         }
     }
 }
@@ -417,6 +972,8 @@ where
 
     /// Specify the file path to read the private key.
     ///
+    /// The key is PEM encoded, as PKCS#8, RSA or SEC1 (`EC PRIVATE KEY`).
+    ///
     /// *This function requires the `"tls"` feature.*
     pub fn key_path(self, path: impl AsRef<Path>) -> Self {
         self.with_tls(|tls| tls.key_path(path))
@@ -444,6 +1001,9 @@ where
     /// Only authenticated clients will be accepted. If no trust anchor is provided by any of the
     /// `client_auth_` methods, then client authentication is disabled by default.
     ///
+    /// Filters can see who the client is with
+    /// [`warp::tls::peer_certificates`](crate::tls::peer_certificates).
+    ///
     /// *This function requires the `"tls"` feature.*
     pub fn client_auth_required_path(self, path: impl AsRef<Path>) -> Self {
         self.with_tls(|tls| tls.client_auth_required_path(path))
@@ -451,6 +1011,8 @@ where
 
     /// Specify the in-memory contents of the private key.
     ///
+    /// The key is PEM encoded, as PKCS#8, RSA or SEC1 (`EC PRIVATE KEY`).
+    ///
     /// *This function requires the `"tls"` feature.*
     pub fn key(self, key: impl AsRef<[u8]>) -> Self {
         self.with_tls(|tls| tls.key(key.as_ref()))
@@ -478,6 +1040,9 @@ where
     /// Only authenticated clients will be accepted. If no trust anchor is provided by any of the
     /// `client_auth_` methods, then client authentication is disabled by default.
     ///
+    /// Filters can see who the client is with
+    /// [`warp::tls::peer_certificates`](crate::tls::peer_certificates).
+    ///
     /// *This function requires the `"tls"` feature.*
     pub fn client_auth_required(self, trust_anchor: impl AsRef<[u8]>) -> Self {
         self.with_tls(|tls| tls.client_auth_required(trust_anchor.as_ref()))
@@ -490,13 +1055,186 @@ where
         self.with_tls(|tls| tls.ocsp_resp(resp.as_ref()))
     }
 
+    /// Specify the file paths to read the certificate and private key used for
+    /// the server name `name`.
+    ///
+    /// Clients asking for `name` with SNI get this certificate instead of the
+    /// default one, which then becomes optional. A name starting with `*.`
+    /// matches any single label in its place, and names the same exactly take
+    /// precedence. Filters can see which name was asked for with
+    /// [`TlsInfo::server_name`](crate::tls::TlsInfo::server_name).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid server name.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn sni_cert_path(self, name: &str, cert: impl AsRef<Path>, key: impl AsRef<Path>) -> Self {
+        self.with_tls(|tls| {
+            tls.sni(
+                name,
+                Source::Path(cert.as_ref().into()),
+                Source::Path(key.as_ref().into()),
+            )
+        })
+    }
+
+    /// Specify the certificate and private key used for the server name
+    /// `name`.
+    ///
+    /// See [`sni_cert_path`](TlsServer::sni_cert_path) for how names match.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid server name.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn sni_cert(self, name: &str, cert: impl AsRef<[u8]>, key: impl AsRef<[u8]>) -> Self {
+        self.with_tls(|tls| {
+            tls.sni(
+                name,
+                Source::Bytes(cert.as_ref().to_vec()),
+                Source::Bytes(key.as_ref().to_vec()),
+            )
+        })
+    }
+
+    /// Specify the DER-encoded OCSP response of the certificate for `name`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no certificate was given for `name` yet.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn sni_ocsp_resp(self, name: &str, resp: impl AsRef<[u8]>) -> Self {
+        self.with_tls(|tls| tls.sni_ocsp_resp(name, resp.as_ref()))
+    }
+
+    /// Specify what to do with handshakes that don't ask for any of the names
+    /// given to [`sni_cert`](TlsServer::sni_cert), or don't ask for one at
+    /// all.
+    ///
+    /// Defaults to [`SniFallback::Default`].
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn sni_fallback(self, fallback: SniFallback) -> Self {
+        self.with_tls(|tls| tls.sni_fallback(fallback))
+    }
+
+    /// Specify the protocols offered with ALPN, most preferred first.
+    ///
+    /// Defaults to `h2` and `http/1.1`. Leaving out `h2` keeps clients that
+    /// negotiate with ALPN on HTTP/1.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn alpn_protocols<I>(self, protocols: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        let protocols = protocols
+            .into_iter()
+            .map(|protocol| protocol.as_ref().to_vec())
+            .collect();
+        self.with_tls(|tls| tls.alpn_protocols(protocols))
+    }
+
+    /// Specify the versions of TLS to accept.
+    ///
+    /// Defaults to TLS 1.2 and 1.3.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use warp::tls::rustls::ProtocolVersion;
+    /// use warp::Filter;
+    ///
+    /// # async fn run() {
+    /// warp::serve(warp::any().map(warp::reply))
+    ///     .tls()
+    ///     .cert_path("cert.pem")
+    ///     .key_path("key.pem")
+    ///     .protocol_versions(&[ProtocolVersion::TLSv1_3])
+    ///     .run(([0, 0, 0, 0], 443))
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn protocol_versions(self, versions: &[ProtocolVersion]) -> Self {
+        self.with_tls(|tls| tls.protocol_versions(versions))
+    }
+
+    /// Specify the cipher suites to accept, most preferred first.
+    ///
+    /// Every enabled protocol version needs at least one suite. Defaults to
+    /// all the suites of [`rustls::ALL_CIPHERSUITES`](crate::tls::rustls::ALL_CIPHERSUITES).
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn cipher_suites(self, suites: &[&'static SupportedCipherSuite]) -> Self {
+        self.with_tls(|tls| tls.cipher_suites(suites))
+    }
+
+    /// Specify how many sessions are kept for clients to resume, or `0` to
+    /// keep none.
+    ///
+    /// Defaults to 256.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn session_cache(self, size: usize) -> Self {
+        self.with_tls(|tls| tls.session_cache(size))
+    }
+
+    /// Enable session tickets, which let clients resume sessions without the
+    /// server keeping them.
+    ///
+    /// The keys that tickets are encrypted with are replaced every
+    /// `rotation`, and tickets last until the keys after theirs are replaced
+    /// too, but no more than 12 hours. Reloading with a
+    /// [`Reloader`](crate::tls::Reloader) keeps the keys.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn session_tickets(self, rotation: Duration) -> Self {
+        self.with_tls(|tls| tls.session_tickets(rotation))
+    }
+
+    /// Use a fully built rustls config, for anything the other options can't
+    /// express.
+    ///
+    /// Starting the server fails if this is combined with any of the other
+    /// options, since it would override them.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn rustls_config(self, config: ServerConfig) -> Self {
+        self.with_tls(|tls| tls.rustls_config(config))
+    }
+
+    /// Allow the key, certificate and trust anchors to be reloaded while the
+    /// server runs, with the given handle.
+    ///
+    /// See [`Reloader`](crate::tls::Reloader) for more.
+    ///
+    /// *This function requires the `"tls"` feature.*
+    pub fn reloader(mut self, reloader: Reloader) -> Self {
+        self.reloader = Some(reloader);
+        self
+    }
+
     fn with_tls<Func>(self, func: Func) -> Self
     where
         Func: FnOnce(TlsConfigBuilder) -> TlsConfigBuilder,
     {
-        let TlsServer { server, tls } = self;
+        let TlsServer {
+            server,
+            tls,
+            reloader,
+        } = self;
         let tls = func(tls);
-        TlsServer { server, tls }
+        TlsServer {
+            server,
+            tls,
+            reloader,
+        }
     }
 
     // Server run methods
@@ -552,14 +1290,18 @@ where
         addr: impl Into<SocketAddr> + 'static,
         signal: impl Future<Output = ()> + Send + 'static,
     ) -> (SocketAddr, impl Future<Output = ()> + 'static) {
+        let shutdown = self.server.shutdown.clone();
+        let drain_timeout = self.server.drain_timeout;
         let (addr, srv) = bind!(tls: self, addr);
 
-        let fut = srv.with_graceful_shutdown(signal).map(|result| {
-            if let Err(err) = result {
-                tracing::error!("server error: {}", err)
-            }
-        });
-        (addr, fut)
+        let fut = srv
+            .with_graceful_shutdown(shutdown.signal(signal))
+            .map(|result| {
+                if let Err(err) = result {
+                    tracing::error!("server error: {}", err)
+                }
+            });
+        (addr, shutdown.deadline(fut, drain_timeout))
     }
 }
 
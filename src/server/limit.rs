@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures::ready;
+use hyper::server::accept::Accept;
+use pin_project::pin_project;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore};
+
+use crate::transport::Transport;
+
+/// What a server does with new connections once it has as many open as
+/// [`Server::max_connections`](crate::Server::max_connections) allows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LimitPolicy {
+    /// Stop accepting until a connection closes.
+    ///
+    /// New connections wait in the OS's listen backlog, which refuses them
+    /// once it's full.
+    #[default]
+    Pause,
+    /// Accept new connections and close them right away.
+    Close,
+}
+
+// Limits on the connections of a server, shared by all of its listeners.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Limits {
+    total: Option<Arc<Semaphore>>,
+    policy: LimitPolicy,
+    per_ip: Option<PerIp>,
+}
+
+#[derive(Clone, Debug)]
+struct PerIp {
+    max: usize,
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+type Acquire = Pin<Box<dyn Future<Output = Result<OwnedSemaphorePermit, AcquireError>> + Send>>;
+
+// Accepts connections from `incoming` within the limits.
+#[pin_project]
+pub(crate) struct Limited<I> {
+    #[pin]
+    incoming: I,
+    limits: Limits,
+    acquire: Option<Acquire>,
+    // Acquired while paused, for the next connection.
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+// A connection, counted against the limits until it's dropped.
+pub(crate) struct LimitedConn<T> {
+    inner: T,
+    _permit: Option<OwnedSemaphorePermit>,
+    _ip: Option<IpGuard>,
+}
+
+struct IpGuard {
+    ip: IpAddr,
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl Limits {
+    pub(crate) fn max_connections(&mut self, max: usize) {
+        self.total = Some(Arc::new(Semaphore::new(max.min(Semaphore::MAX_PERMITS))));
+    }
+
+    pub(crate) fn policy(&mut self, policy: LimitPolicy) {
+        self.policy = policy;
+    }
+
+    pub(crate) fn max_connections_per_ip(&mut self, max: usize) {
+        self.per_ip = Some(PerIp {
+            max,
+            open: Arc::default(),
+        });
+    }
+
+    pub(crate) fn wrap<I>(&self, incoming: I) -> Limited<I> {
+        Limited {
+            incoming,
+            limits: self.clone(),
+            acquire: None,
+            permit: None,
+        }
+    }
+}
+
+impl PerIp {
+    fn acquire(&self, addr: SocketAddr) -> Option<IpGuard> {
+        let ip = addr.ip();
+        let mut open = self.open.lock().unwrap();
+        let count = open.entry(ip).or_insert(0);
+        if *count >= self.max {
+            tracing::debug!("closing connection from {}, too many connections", addr);
+            return None;
+        }
+        *count += 1;
+        Some(IpGuard {
+            ip,
+            open: self.open.clone(),
+        })
+    }
+}
+
+impl Drop for IpGuard {
+    fn drop(&mut self) {
+        let mut open = self.open.lock().unwrap();
+        if let Some(count) = open.get_mut(&self.ip) {
+            *count -= 1;
+            if *count == 0 {
+                open.remove(&self.ip);
+            }
+        }
+    }
+}
+
+impl<I> Accept for Limited<I>
+where
+    I: Accept,
+    I::Conn: Transport,
+{
+    type Conn = LimitedConn<I::Conn>;
+    type Error = I::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let mut pin = self.project();
+        loop {
+            // Paused until a connection would be within the limit.
+            match (&pin.limits.total, pin.limits.policy) {
+                (Some(total), LimitPolicy::Pause) if pin.permit.is_none() => {
+                    let acquire = pin
+                        .acquire
+                        .get_or_insert_with(|| Box::pin(total.clone().acquire_owned()));
+                    let permit = ready!(acquire.as_mut().poll(cx));
+                    *pin.acquire = None;
+                    *pin.permit = Some(permit.expect("semaphore is never closed"));
+                }
+                _ => {}
+            }
+
+            let conn = match ready!(pin.incoming.as_mut().poll_accept(cx)) {
+                Some(Ok(conn)) => conn,
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            };
+
+            let permit = match pin.limits.total {
+                Some(ref total) => match pin.permit.take() {
+                    Some(permit) => Some(permit),
+                    None => match total.clone().try_acquire_owned() {
+                        Ok(permit) => Some(permit),
+                        Err(_) => {
+                            tracing::debug!("closing connection, too many connections");
+                            continue;
+                        }
+                    },
+                },
+                None => None,
+            };
+            let ip = match (&pin.limits.per_ip, conn.remote_addr()) {
+                (Some(per_ip), Some(addr)) => match per_ip.acquire(addr) {
+                    Some(guard) => Some(guard),
+                    None => continue,
+                },
+                _ => None,
+            };
+
+            return Poll::Ready(Some(Ok(LimitedConn {
+                inner: conn,
+                _permit: permit,
+                _ip: ip,
+            })));
+        }
+    }
+}
+
+impl<T: Transport + Unpin> Transport for LimitedConn<T> {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        self.inner.remote_addr()
+    }
+
+    fn peer(&self) -> Option<crate::addr::Peer> {
+        self.inner.peer()
+    }
+
+    #[cfg(feature = "tls")]
+    fn tls_info(&self) -> Option<crate::tls::InfoSlot> {
+        self.inner.tls_info()
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for LimitedConn<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for LimitedConn<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
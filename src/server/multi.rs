@@ -0,0 +1,367 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+
+use futures::future::{self, Either};
+use futures::{FutureExt, TryFuture};
+use hyper::server::conn::AddrIncoming;
+use hyper::service::{make_service_fn, service_fn};
+use tracing::Instrument;
+
+use super::Server;
+#[cfg(feature = "tls")]
+use super::TlsServer;
+use crate::filter::Filter;
+use crate::listener::{Incoming, Listener};
+use crate::proxy_protocol::ProxyIncoming;
+use crate::reject::IsReject;
+use crate::reply::Reply;
+#[cfg(feature = "tls")]
+use crate::tls::{LiveConfig, Reloader, TlsConfigBuilder};
+use crate::transport::Transport;
+#[cfg(unix)]
+use crate::unix::UnixSocket;
+
+type Serving = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A Warp Server ready to filter requests on several listeners at once.
+///
+/// Created with [`Server::multi`](crate::Server::multi). Every listener is
+/// driven by the same future, and stops accepting connections on the same
+/// graceful shutdown signal.
+///
+/// # Example
+///
+/// ```no_run
+/// use tokio::sync::oneshot;
+/// use warp::Filter;
+///
+/// # async fn run() {
+/// let routes = warp::any().map(|| "Hello, World!");
+///
+/// let (tx, rx) = oneshot::channel::<()>();
+///
+/// let (addrs, server) = warp::serve(routes)
+///     .multi()
+///     .bind(([0, 0, 0, 0], 8080))
+///     .bind(([0, 0, 0, 0, 0, 0, 0, 0], 8080))
+///     .bind_with_graceful_shutdown(async {
+///         rx.await.ok();
+///     });
+///
+/// for addr in addrs {
+///     println!("listening on {}", addr);
+/// }
+/// tokio::task::spawn(server);
+///
+/// // Later, stop every listener at once...
+/// let _ = tx.send(());
+/// # }
+/// ```
+pub struct MultiServer<F> {
+    server: Server<F>,
+    binds: Vec<Bind>,
+}
+
+enum Bind {
+    Tcp(SocketAddr),
+    #[cfg(feature = "tls")]
+    Tls(SocketAddr, Box<TlsConfigBuilder>, Option<Reloader>),
+    #[cfg(unix)]
+    Unix(UnixSocket),
+    Listener(Listener),
+}
+
+/// An address a [`MultiServer`] listens on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LocalAddr {
+    /// A TCP socket serving plain HTTP.
+    Tcp(SocketAddr),
+    /// A TCP socket serving HTTPS.
+    ///
+    /// *This variant requires the `"tls"` feature.*
+    #[cfg(feature = "tls")]
+    Tls(SocketAddr),
+    /// A Unix domain socket, with its path unless it's unnamed.
+    ///
+    /// *This variant is only available on Unix.*
+    #[cfg(unix)]
+    Unix(Option<PathBuf>),
+}
+
+impl fmt::Display for LocalAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalAddr::Tcp(addr) => write!(f, "http://{}", addr),
+            #[cfg(feature = "tls")]
+            LocalAddr::Tls(addr) => write!(f, "https://{}", addr),
+            #[cfg(unix)]
+            LocalAddr::Unix(Some(path)) => write!(f, "unix:{}", path.display()),
+            #[cfg(unix)]
+            LocalAddr::Unix(None) => f.write_str("unix"),
+        }
+    }
+}
+
+// Each kind of connection needs its own service.
+macro_rules! serve {
+    ($server:expr, $incoming:expr, $shutdown:expr) => {{
+        let srv = $server
+            .builder($incoming)
+            .serve(into_service!($server.filter.clone(), $server.shutdown))
+            .with_graceful_shutdown($shutdown)
+            .map(|result| {
+                if let Err(err) = result {
+                    tracing::error!("server error: {}", err)
+                }
+            });
+        Box::pin(srv) as Serving
+    }};
+}
+
+impl<F> MultiServer<F>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    <F::Future as TryFuture>::Ok: Reply,
+    <F::Future as TryFuture>::Error: IsReject,
+{
+    pub(super) fn new(server: Server<F>) -> MultiServer<F> {
+        MultiServer {
+            server,
+            binds: Vec::new(),
+        }
+    }
+
+    /// Listen on a possibly ephemeral socket address.
+    pub fn bind(mut self, addr: impl Into<SocketAddr>) -> Self {
+        self.binds.push(Bind::Tcp(addr.into()));
+        self
+    }
+
+    /// Listen on a possibly ephemeral socket address with TLS, configured
+    /// by `tls` for this listener only.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use warp::Filter;
+    ///
+    /// # async fn run() {
+    /// let routes = warp::any().map(|| "Hello, World!");
+    ///
+    /// warp::serve(routes)
+    ///     .multi()
+    ///     .bind(([0, 0, 0, 0], 80))
+    ///     .bind_tls(([0, 0, 0, 0], 443), |tls| {
+    ///         tls.cert_path("cert.pem").key_path("key.pem")
+    ///     })
+    ///     .run()
+    ///     .await;
+    /// # }
+    /// ```
+    ///
+    /// *This function requires the `"tls"` feature.*
+    #[cfg(feature = "tls")]
+    pub fn bind_tls<T>(mut self, addr: impl Into<SocketAddr>, tls: T) -> Self
+    where
+        T: FnOnce(TlsServer<F>) -> TlsServer<F>,
+    {
+        let server = Server {
+            pipeline: self.server.pipeline,
+            proxy_protocol: self.server.proxy_protocol.clone(),
+            shutdown: self.server.shutdown.clone(),
+            drain_timeout: self.server.drain_timeout,
+            conn: self.server.conn.clone(),
+            filter: self.server.filter.clone(),
+        };
+        let TlsServer { tls, reloader, .. } = tls(server.tls());
+        self.binds
+            .push(Bind::Tls(addr.into(), Box::new(tls), reloader));
+        self
+    }
+
+    /// Listen on a Unix domain socket.
+    ///
+    /// See [`UnixSocket`](crate::UnixSocket) for the options.
+    ///
+    /// *This function is only available on Unix.*
+    #[cfg(unix)]
+    pub fn bind_unix(mut self, socket: impl Into<UnixSocket>) -> Self {
+        self.binds.push(Bind::Unix(socket.into()));
+        self
+    }
+
+    /// Serve on a socket that is already listening.
+    ///
+    /// See [`Listener`](crate::Listener) for where these come from.
+    pub fn bind_listener(mut self, listener: impl Into<Listener>) -> Self {
+        self.binds.push(Bind::Listener(listener.into()));
+        self
+    }
+
+    /// Run this `MultiServer` forever on the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if we are unable to bind any of the listeners.
+    pub async fn run(self) {
+        let (addrs, fut) = self.bind_with_graceful_shutdown(future::pending());
+        let span = tracing::info_span!("MultiServer::run");
+        for addr in &addrs {
+            tracing::info!(parent: &span, "listening on {}", addr);
+        }
+
+        fut.instrument(span).await;
+    }
+
+    /// Bind every listener.
+    ///
+    /// Returns the bound addresses, in the order the listeners were added,
+    /// and a `Future` that can be executed on the current runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if we are unable to bind any of the listeners.
+    pub fn bind_all(self) -> (Vec<LocalAddr>, impl Future<Output = ()> + 'static) {
+        self.bind_with_graceful_shutdown(future::pending())
+    }
+
+    /// Tries to bind every listener.
+    ///
+    /// Returns a `Result` which fails in case we are unable to bind any of
+    /// them, after closing the ones already bound.
+    pub fn try_bind_all(
+        self,
+    ) -> Result<(Vec<LocalAddr>, impl Future<Output = ()> + 'static), crate::Error> {
+        self.try_bind_with_graceful_shutdown(future::pending())
+    }
+
+    /// Bind every listener, with a graceful shutdown signal.
+    ///
+    /// When the signal completes, every listener stops accepting connections,
+    /// and the `Future` resolves once the open connections are done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if we are unable to bind any of the listeners.
+    pub fn bind_with_graceful_shutdown(
+        self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> (Vec<LocalAddr>, impl Future<Output = ()> + 'static) {
+        self.try_bind_with_graceful_shutdown(signal)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Tries to bind every listener, with a graceful shutdown signal.
+    ///
+    /// Returns a `Result` which fails in case we are unable to bind any of
+    /// them, after closing the ones already bound.
+    pub fn try_bind_with_graceful_shutdown(
+        self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<(Vec<LocalAddr>, impl Future<Output = ()> + 'static), crate::Error> {
+        if self.binds.is_empty() {
+            return Err(crate::Error::new("no listeners to bind"));
+        }
+
+        let shutdown = self.server.shutdown.clone();
+        let mut addrs = Vec::with_capacity(self.binds.len());
+        let mut servers = Vec::with_capacity(self.binds.len());
+        for bind in self.binds {
+            let (addr, srv) = serve(&self.server, bind, shutdown.draining())?;
+            addrs.push(addr);
+            servers.push(srv);
+        }
+
+        let signal = Box::pin(shutdown.signal(signal));
+        let servers = future::join_all(servers);
+        let fut = async move {
+            if let Either::Left((_, servers)) = future::select(signal, servers).await {
+                servers.await;
+            }
+        };
+        Ok((addrs, shutdown.deadline(fut, self.server.drain_timeout)))
+    }
+}
+
+fn serve<F>(
+    server: &Server<F>,
+    bind: Bind,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(LocalAddr, Serving), crate::Error>
+where
+    F: Filter + Clone + Send + Sync + 'static,
+    <F::Future as TryFuture>::Ok: Reply,
+    <F::Future as TryFuture>::Error: IsReject,
+{
+    match bind {
+        Bind::Tcp(addr) => {
+            let (addr, incoming) = tcp_incoming(server, addr)?;
+            Ok((LocalAddr::Tcp(addr), serve!(server, incoming, shutdown)))
+        }
+        #[cfg(feature = "tls")]
+        Bind::Tls(addr, tls, reloader) => {
+            let tls = LiveConfig::new(*tls).map_err(|e| bind_error(addr, e))?;
+            let tls = Arc::new(tls);
+            if let Some(ref reloader) = reloader {
+                reloader.attach(&tls);
+            }
+            let (addr, incoming) = tcp_incoming(server, addr)?;
+            let incoming = crate::tls::TlsAcceptor::new(tls, incoming);
+            Ok((LocalAddr::Tls(addr), serve!(server, incoming, shutdown)))
+        }
+        #[cfg(unix)]
+        Bind::Unix(socket) => {
+            let path = socket.path().to_owned();
+            let incoming = socket.bind().map_err(|e| bind_error(path.display(), e))?;
+            Ok((
+                LocalAddr::Unix(Some(path)),
+                serve!(server, incoming, shutdown),
+            ))
+        }
+        Bind::Listener(listener) => {
+            let addr = listener.local();
+            match listener.into_incoming(server.proxy_protocol.clone(), &server.conn)? {
+                Incoming::Tcp(incoming) => Ok((addr, serve!(server, incoming, shutdown))),
+                #[cfg(unix)]
+                Incoming::Unix(incoming) => Ok((addr, serve!(server, incoming, shutdown))),
+            }
+        }
+    }
+}
+
+fn tcp_incoming<F>(
+    server: &Server<F>,
+    addr: SocketAddr,
+) -> Result<(SocketAddr, ProxyIncoming), crate::Error> {
+    let mut incoming = AddrIncoming::bind(&addr).map_err(|e| bind_error(addr, e))?;
+    server.conn.tcp(&mut incoming);
+    let addr = incoming.local_addr();
+    Ok((
+        addr,
+        ProxyIncoming::new(incoming, server.proxy_protocol.clone()),
+    ))
+}
+
+fn bind_error(addr: impl fmt::Display, err: impl fmt::Display) -> crate::Error {
+    crate::Error::new(format!("error binding to {}: {}", addr, err))
+}
+
+impl<F> fmt::Debug for MultiServer<F>
+where
+    F: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiServer")
+            .field("server", &self.server)
+            .field("listeners", &self.binds.len())
+            .finish()
+    }
+}
@@ -371,15 +371,18 @@ impl RequestBuilder {
         assert!(!route::is_set(), "nested test filter calls");
 
         let route = Route::new(self.req, self.remote_addr);
+        let routes = f.describe(crate::filter::Internal);
         let mut fut = Box::pin(
-            route::set(&route, move || f.filter(crate::filter::Internal)).then(|result| {
-                let res = match result {
+            route::set(&route, move || f.filter(crate::filter::Internal)).then(move |result| {
+                let mut res = match result {
                     Ok(rep) => rep.into_response(),
                     Err(rej) => {
                         tracing::debug!("rejected: {:?}", rej);
-                        rej.into_response()
+                        route::with(|route| rej.route_methods(&routes, route.full_path()))
+                            .into_response()
                     }
                 };
+                route::with(|route| route.finish_response(&mut res));
                 let (parts, body) = res.into_parts();
                 hyper::body::to_bytes(body)
                     .map_ok(|chunk| Response::from_parts(parts, chunk.into()))
@@ -1,23 +1,371 @@
+//! TLS Filters
+//!
+//! Information about the TLS session of a connection, such as the client
+//! certificate verified by
+//! [`client_auth_required_path`](crate::TlsServer::client_auth_required_path).
+//!
+//! *This module requires the `"tls"` feature.*
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
 use std::fs::File;
 use std::future::Future;
 use std::io::{self, BufReader, Cursor, Read};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock, RwLock, Weak};
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
-use futures::ready;
+use futures::{future, ready};
 use hyper::server::accept::Accept;
-use hyper::server::conn::{AddrIncoming, AddrStream};
 
+use crate::filter::{filter_fn_one, Filter, One};
 use crate::transport::Transport;
+use tokio_rustls::rustls::sign::{self, CertifiedKey};
 use tokio_rustls::rustls::{
-    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, NoClientAuth,
-    RootCertStore, ServerConfig, TLSError,
+    AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient, Certificate, ClientHello,
+    NoClientAuth, NoServerSessionStorage, PrivateKey, ProducesTickets, ProtocolVersion,
+    ResolvesServerCert, RootCertStore, ServerConfig, ServerSession, ServerSessionMemoryCache,
+    Session, SupportedCipherSuite, TLSError, Ticketer,
 };
 
+mod cert;
+mod key;
+
+/// The version of `rustls` used, for configuring it with
+/// [`rustls_config`](crate::TlsServer::rustls_config) and the like.
+pub use tokio_rustls::rustls;
+
+/// Creates a `Filter` to get the TLS session of the connection.
+///
+/// Extracts `None` if the request wasn't received over TLS.
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+/// use warp::tls::TlsInfo;
+///
+/// let route = warp::tls::info()
+///     .map(|info: Option<TlsInfo>| {
+///         format!("version = {:?}", info.and_then(|info| info.version()))
+///     });
+/// ```
+pub fn info() -> impl Filter<Extract = One<Option<TlsInfo>>, Error = Infallible> + Copy {
+    filter_fn_one(|route| future::ok(route.extensions().get::<TlsInfo>().cloned()))
+}
+
+/// Creates a `Filter` to get the certificates the client authenticated with.
+///
+/// Extracts `None` if the request wasn't received over TLS, or the client
+/// didn't send a certificate. Certificates are only ever verified if client
+/// authentication is configured on the [`TlsServer`](crate::TlsServer).
+///
+/// # Example
+///
+/// ```
+/// use warp::Filter;
+/// use warp::tls::PeerCertificates;
+///
+/// let route = warp::tls::peer_certificates()
+///     .map(|peer: Option<PeerCertificates>| {
+///         match peer.as_ref().and_then(|peer| peer.subject()) {
+///             Some(subject) => format!("hello, {}", subject),
+///             None => "hello, stranger".to_owned(),
+///         }
+///     });
+/// ```
+pub fn peer_certificates(
+) -> impl Filter<Extract = One<Option<PeerCertificates>>, Error = Infallible> + Copy {
+    filter_fn_one(|route| {
+        let peer = route
+            .extensions()
+            .get::<TlsInfo>()
+            .and_then(|info| info.peer_certificates().cloned());
+        future::ok(peer)
+    })
+}
+
+/// The TLS session a request was received on.
+#[derive(Clone)]
+pub struct TlsInfo {
+    inner: Arc<Established>,
+}
+
+struct Established {
+    version: Option<&'static str>,
+    alpn_protocol: Option<Vec<u8>>,
+    server_name: Option<String>,
+    peer: Option<PeerCertificates>,
+}
+
+impl TlsInfo {
+    fn new(session: &ServerSession) -> TlsInfo {
+        let version = session
+            .get_protocol_version()
+            .and_then(|version| match version {
+                ProtocolVersion::TLSv1_0 => Some("TLSv1.0"),
+                ProtocolVersion::TLSv1_1 => Some("TLSv1.1"),
+                ProtocolVersion::TLSv1_2 => Some("TLSv1.2"),
+                ProtocolVersion::TLSv1_3 => Some("TLSv1.3"),
+                _ => None,
+            });
+        let peer = session
+            .get_peer_certificates()
+            .filter(|chain| !chain.is_empty())
+            .map(|chain| {
+                let chain = chain.into_iter().map(|cert| cert.0).collect::<Vec<_>>();
+                let (subject, alt_names) = match cert::parse(&chain[0]) {
+                    Some((subject, alt_names)) => (Some(subject), alt_names),
+                    None => (None, Vec::new()),
+                };
+                PeerCertificates {
+                    chain: Arc::new(chain),
+                    subject,
+                    alt_names,
+                }
+            });
+
+        TlsInfo {
+            inner: Arc::new(Established {
+                version,
+                alpn_protocol: session.get_alpn_protocol().map(<[u8]>::to_vec),
+                server_name: session.get_sni_hostname().map(str::to_owned),
+                peer,
+            }),
+        }
+    }
+
+    /// The version of TLS negotiated, such as `"TLSv1.3"`.
+    pub fn version(&self) -> Option<&'static str> {
+        self.inner.version
+    }
+
+    /// The protocol negotiated with ALPN, such as `b"h2"`.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.inner.alpn_protocol.as_deref()
+    }
+
+    /// The server name the client asked for with SNI, which picked the
+    /// certificate when there are several, see
+    /// [`sni_cert`](crate::TlsServer::sni_cert).
+    pub fn server_name(&self) -> Option<&str> {
+        self.inner.server_name.as_deref()
+    }
+
+    /// The certificates the client authenticated with, if any.
+    pub fn peer_certificates(&self) -> Option<&PeerCertificates> {
+        self.inner.peer.as_ref()
+    }
+}
+
+impl fmt::Debug for TlsInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TlsInfo")
+            .field("version", &self.inner.version)
+            .field(
+                "alpn_protocol",
+                &self
+                    .inner
+                    .alpn_protocol
+                    .as_ref()
+                    .map(|p| String::from_utf8_lossy(p)),
+            )
+            .field("server_name", &self.inner.server_name)
+            .field("peer_certificates", &self.inner.peer)
+            .finish()
+    }
+}
+
+/// The verified certificate chain of a client.
+#[derive(Clone)]
+pub struct PeerCertificates {
+    chain: Arc<Vec<Vec<u8>>>,
+    subject: Option<String>,
+    alt_names: Vec<SubjectAltName>,
+}
+
+impl PeerCertificates {
+    /// The DER encoded certificates, starting with the client's own.
+    pub fn chain(&self) -> &[Vec<u8>] {
+        &self.chain
+    }
+
+    /// The subject of the client's certificate, formatted like RFC 4514,
+    /// such as `"CN=client,O=Example"`.
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
+    }
+
+    /// The subject alternative names of the client's certificate.
+    pub fn subject_alt_names(&self) -> &[SubjectAltName] {
+        &self.alt_names
+    }
+}
+
+impl fmt::Debug for PeerCertificates {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PeerCertificates")
+            .field("len", &self.chain.len())
+            .field("subject", &self.subject)
+            .field("subject_alt_names", &self.alt_names)
+            .finish()
+    }
+}
+
+/// A subject alternative name of a certificate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SubjectAltName {
+    /// A DNS name, such as `client.example.com`.
+    Dns(String),
+    /// An email address.
+    Email(String),
+    /// A URI, such as a SPIFFE ID.
+    Uri(String),
+    /// An IP address.
+    Ip(IpAddr),
+}
+
+/// What to do with a handshake that doesn't match any of the server names
+/// given to [`sni_cert`](crate::TlsServer::sni_cert).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SniFallback {
+    /// Use the default certificate, if there is one. This is the default.
+    Default,
+    /// Use the default certificate only for clients that don't send a server
+    /// name, such as those connecting to an IP address, and refuse names that
+    /// are unknown.
+    RejectUnknown,
+    /// Refuse the handshake.
+    Reject,
+}
+
+/// A handle to reload the TLS configuration of running servers.
+///
+/// Attach it to a [`TlsServer`](crate::TlsServer) with
+/// [`reloader`](crate::TlsServer::reloader). Calling [`reload`](Reloader::reload)
+/// then reads the key, certificate and trust anchors again, from the same
+/// paths they were configured with. New connections use the new
+/// configuration, while existing ones carry on with the one they started with.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use warp::Filter;
+///
+/// # async fn run() {
+/// let reloader = warp::tls::Reloader::new();
+///
+/// let watcher = reloader.clone();
+/// tokio::spawn(async move {
+///     let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+///     loop {
+///         interval.tick().await;
+///         // Reloading reads files, which blocks.
+///         let watcher = watcher.clone();
+///         if let Ok(Err(err)) = tokio::task::spawn_blocking(move || watcher.reload()).await {
+///             eprintln!("keeping the current certificate: {}", err);
+///         }
+///     }
+/// });
+///
+/// warp::serve(warp::any().map(warp::reply))
+///     .tls()
+///     .cert_path("cert.pem")
+///     .key_path("key.pem")
+///     .reloader(reloader)
+///     .run(([0, 0, 0, 0], 443))
+///     .await;
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Reloader {
+    configs: Arc<Mutex<Vec<Weak<LiveConfig>>>>,
+}
+
+impl Reloader {
+    /// Creates a handle that isn't attached to any server yet.
+    pub fn new() -> Reloader {
+        Reloader::default()
+    }
+
+    /// Rebuilds the configuration of every server this is attached to.
+    ///
+    /// If a new configuration is invalid, such as when a key doesn't match
+    /// its certificate, that server keeps the configuration it had. The
+    /// others are still reloaded, and the errors of all that failed are
+    /// returned.
+    ///
+    /// This reads files, so it blocks; call it from a blocking task.
+    pub fn reload(&self) -> Result<(), crate::Error> {
+        let configs = {
+            let mut configs = self.configs.lock().unwrap();
+            // Forget servers that have stopped.
+            configs.retain(|config| config.strong_count() > 0);
+            configs.iter().filter_map(Weak::upgrade).collect::<Vec<_>>()
+        };
+        let errors = configs
+            .iter()
+            .filter_map(|config| config.reload().err())
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::Error::new(ReloadError(errors)))
+        }
+    }
+
+    pub(crate) fn attach(&self, config: &Arc<LiveConfig>) {
+        self.configs.lock().unwrap().push(Arc::downgrade(config));
+    }
+}
+
+impl fmt::Debug for Reloader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let servers = self.configs.lock().map(|c| c.len()).unwrap_or(0);
+        f.debug_struct("Reloader")
+            .field("servers", &servers)
+            .finish()
+    }
+}
+
+// The servers a `Reloader` failed to reload.
+#[derive(Debug)]
+struct ReloadError(Vec<TlsConfigError>);
+
+impl fmt::Display for ReloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let [ref error] = *self.0 {
+            return error.fmt(f);
+        }
+        write!(f, "{} servers failed to reload", self.0.len())?;
+        for (i, error) in self.0.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { ";" }, error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ReloadError {}
+
+// Where a connection puts its `TlsInfo` once the handshake is done, for the
+// service to add to each request.
+pub(crate) type InfoSlot = Arc<OnceLock<TlsInfo>>;
+
+pub(crate) fn with_info(mut req: crate::Request, slot: &Option<InfoSlot>) -> crate::Request {
+    if let Some(info) = slot.as_ref().and_then(|slot| slot.get()) {
+        req.extensions_mut().insert(info.clone());
+    }
+    req
+}
+
 /// Represents errors that can occur building the TlsConfig
 #[derive(Debug)]
 pub(crate) enum TlsConfigError {
@@ -30,8 +378,22 @@ pub(crate) enum TlsConfigError {
     RsaParseError,
     /// An error from an empty key
     EmptyKey,
+    /// An Error parsing a Sec1 key
+    Sec1ParseError,
     /// An error from an invalid key
     InvalidKey(TLSError),
+    /// No protocol versions are enabled
+    NoProtocolVersions,
+    /// A protocol version rustls doesn't support
+    UnsupportedProtocolVersion(ProtocolVersion),
+    /// An enabled protocol version has no cipher suites
+    NoCipherSuites(ProtocolVersion),
+    /// An ALPN protocol id that is empty or too long
+    InvalidAlpnProtocol(Vec<u8>),
+    /// Session tickets rotated with a zero period
+    InvalidTicketRotation,
+    /// A rustls config along with other options it would override
+    ConflictingConfig,
 }
 
 impl std::fmt::Display for TlsConfigError {
@@ -42,7 +404,22 @@ impl std::fmt::Display for TlsConfigError {
             TlsConfigError::Pkcs8ParseError => write!(f, "pkcs8 parse error"),
             TlsConfigError::RsaParseError => write!(f, "rsa parse error"),
             TlsConfigError::EmptyKey => write!(f, "key contains no private key"),
+            TlsConfigError::Sec1ParseError => write!(f, "sec1 parse error"),
             TlsConfigError::InvalidKey(err) => write!(f, "key contains an invalid key, {}", err),
+            TlsConfigError::NoProtocolVersions => write!(f, "no protocol versions enabled"),
+            TlsConfigError::UnsupportedProtocolVersion(version) => {
+                write!(f, "unsupported protocol version {:?}", version)
+            }
+            TlsConfigError::NoCipherSuites(version) => {
+                write!(f, "no cipher suites for protocol version {:?}", version)
+            }
+            TlsConfigError::InvalidAlpnProtocol(id) => {
+                write!(f, "invalid alpn protocol {:?}", String::from_utf8_lossy(id))
+            }
+            TlsConfigError::InvalidTicketRotation => write!(f, "ticket rotation must not be zero"),
+            TlsConfigError::ConflictingConfig => {
+                write!(f, "rustls config can't be combined with other options")
+            }
         }
     }
 }
@@ -54,17 +431,49 @@ pub(crate) enum TlsClientAuth {
     /// No client auth.
     Off,
     /// Allow any anonymous or authenticated client.
-    Optional(Box<dyn Read + Send + Sync>),
+    Optional(Source),
     /// Allow any authenticated client.
-    Required(Box<dyn Read + Send + Sync>),
+    Required(Source),
+}
+
+/// Where a key or certificate is read from.
+///
+/// Files are opened again every time the config is built, so that a reload
+/// sees their new contents.
+pub(crate) enum Source {
+    Empty,
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl Source {
+    fn reader(&self) -> Box<dyn Read + Send + Sync + '_> {
+        match *self {
+            Source::Empty => Box::new(io::empty()),
+            Source::Path(ref path) => Box::new(LazyFile {
+                path: path.clone(),
+                file: None,
+            }),
+            Source::Bytes(ref bytes) => Box::new(Cursor::new(bytes.as_slice())),
+        }
+    }
 }
 
 /// Builder to set the configuration for the Tls server.
 pub(crate) struct TlsConfigBuilder {
-    cert: Box<dyn Read + Send + Sync>,
-    key: Box<dyn Read + Send + Sync>,
+    cert: Source,
+    key: Source,
     client_auth: TlsClientAuth,
     ocsp_resp: Vec<u8>,
+    sni: Vec<SniEntry>,
+    sni_fallback: SniFallback,
+    alpn_protocols: Vec<Vec<u8>>,
+    versions: Option<Vec<ProtocolVersion>>,
+    cipher_suites: Option<Vec<&'static SupportedCipherSuite>>,
+    session_cache: Option<usize>,
+    // Kept across builds, so that a reload doesn't invalidate tickets.
+    tickets: Option<Arc<RotatingTicketer>>,
+    rustls: Option<ServerConfig>,
 }
 
 impl std::fmt::Debug for TlsConfigBuilder {
@@ -77,40 +486,42 @@ impl TlsConfigBuilder {
     /// Create a new TlsConfigBuilder
     pub(crate) fn new() -> TlsConfigBuilder {
         TlsConfigBuilder {
-            key: Box::new(io::empty()),
-            cert: Box::new(io::empty()),
+            key: Source::Empty,
+            cert: Source::Empty,
             client_auth: TlsClientAuth::Off,
             ocsp_resp: Vec::new(),
+            sni: Vec::new(),
+            sni_fallback: SniFallback::Default,
+            alpn_protocols: vec!["h2".into(), "http/1.1".into()],
+            versions: None,
+            cipher_suites: None,
+            session_cache: None,
+            tickets: None,
+            rustls: None,
         }
     }
 
     /// sets the Tls key via File Path, returns `TlsConfigError::IoError` if the file cannot be open
     pub(crate) fn key_path(mut self, path: impl AsRef<Path>) -> Self {
-        self.key = Box::new(LazyFile {
-            path: path.as_ref().into(),
-            file: None,
-        });
+        self.key = Source::Path(path.as_ref().into());
         self
     }
 
     /// sets the Tls key via bytes slice
     pub(crate) fn key(mut self, key: &[u8]) -> Self {
-        self.key = Box::new(Cursor::new(Vec::from(key)));
+        self.key = Source::Bytes(Vec::from(key));
         self
     }
 
     /// Specify the file path for the TLS certificate to use.
     pub(crate) fn cert_path(mut self, path: impl AsRef<Path>) -> Self {
-        self.cert = Box::new(LazyFile {
-            path: path.as_ref().into(),
-            file: None,
-        });
+        self.cert = Source::Path(path.as_ref().into());
         self
     }
 
     /// sets the Tls certificate via bytes slice
     pub(crate) fn cert(mut self, cert: &[u8]) -> Self {
-        self.cert = Box::new(Cursor::new(Vec::from(cert)));
+        self.cert = Source::Bytes(Vec::from(cert));
         self
     }
 
@@ -119,11 +530,7 @@ impl TlsConfigBuilder {
     /// Anonymous and authenticated clients will be accepted. If no trust anchor is provided by any
     /// of the `client_auth_` methods, then client authentication is disabled by default.
     pub(crate) fn client_auth_optional_path(mut self, path: impl AsRef<Path>) -> Self {
-        let file = Box::new(LazyFile {
-            path: path.as_ref().into(),
-            file: None,
-        });
-        self.client_auth = TlsClientAuth::Optional(file);
+        self.client_auth = TlsClientAuth::Optional(Source::Path(path.as_ref().into()));
         self
     }
 
@@ -132,8 +539,7 @@ impl TlsConfigBuilder {
     /// Anonymous and authenticated clients will be accepted. If no trust anchor is provided by any
     /// of the `client_auth_` methods, then client authentication is disabled by default.
     pub(crate) fn client_auth_optional(mut self, trust_anchor: &[u8]) -> Self {
-        let cursor = Box::new(Cursor::new(Vec::from(trust_anchor)));
-        self.client_auth = TlsClientAuth::Optional(cursor);
+        self.client_auth = TlsClientAuth::Optional(Source::Bytes(Vec::from(trust_anchor)));
         self
     }
 
@@ -142,11 +548,7 @@ impl TlsConfigBuilder {
     /// Only authenticated clients will be accepted. If no trust anchor is provided by any of the
     /// `client_auth_` methods, then client authentication is disabled by default.
     pub(crate) fn client_auth_required_path(mut self, path: impl AsRef<Path>) -> Self {
-        let file = Box::new(LazyFile {
-            path: path.as_ref().into(),
-            file: None,
-        });
-        self.client_auth = TlsClientAuth::Required(file);
+        self.client_auth = TlsClientAuth::Required(Source::Path(path.as_ref().into()));
         self
     }
 
@@ -155,8 +557,7 @@ impl TlsConfigBuilder {
     /// Only authenticated clients will be accepted. If no trust anchor is provided by any of the
     /// `client_auth_` methods, then client authentication is disabled by default.
     pub(crate) fn client_auth_required(mut self, trust_anchor: &[u8]) -> Self {
-        let cursor = Box::new(Cursor::new(Vec::from(trust_anchor)));
-        self.client_auth = TlsClientAuth::Required(cursor);
+        self.client_auth = TlsClientAuth::Required(Source::Bytes(Vec::from(trust_anchor)));
         self
     }
 
@@ -166,47 +567,94 @@ impl TlsConfigBuilder {
         self
     }
 
-    pub(crate) fn build(mut self) -> Result<ServerConfig, TlsConfigError> {
-        let mut cert_rdr = BufReader::new(self.cert);
-        let cert = tokio_rustls::rustls::internal::pemfile::certs(&mut cert_rdr)
-            .map_err(|()| TlsConfigError::CertParseError)?;
+    /// Adds a certificate and key for the server name `name`.
+    pub(crate) fn sni(mut self, name: &str, cert: Source, key: Source) -> Self {
+        let name = sni_name(name);
+        self.sni.retain(|entry| entry.name != name);
+        self.sni.push(SniEntry {
+            name,
+            cert,
+            key,
+            ocsp_resp: Vec::new(),
+        });
+        self
+    }
+
+    /// sets the DER-encoded OCSP response of the certificate for `name`
+    pub(crate) fn sni_ocsp_resp(mut self, name: &str, ocsp_resp: &[u8]) -> Self {
+        let name = sni_name(name);
+        let entry = self
+            .sni
+            .iter_mut()
+            .find(|entry| entry.name == name)
+            .unwrap_or_else(|| panic!("no certificate for server name {:?}", name));
+        entry.ocsp_resp = Vec::from(ocsp_resp);
+        self
+    }
+
+    /// sets what to do with handshakes that don't match a server name
+    pub(crate) fn sni_fallback(mut self, fallback: SniFallback) -> Self {
+        self.sni_fallback = fallback;
+        self
+    }
 
-        let key = {
-            // convert it to Vec<u8> to allow reading it again if key is RSA
-            let mut key_vec = Vec::new();
-            self.key
-                .read_to_end(&mut key_vec)
-                .map_err(TlsConfigError::Io)?;
+    /// sets the ALPN protocols to offer, most preferred first
+    pub(crate) fn alpn_protocols(mut self, protocols: Vec<Vec<u8>>) -> Self {
+        self.alpn_protocols = protocols;
+        self
+    }
 
-            if key_vec.is_empty() {
-                return Err(TlsConfigError::EmptyKey);
-            }
+    /// sets the enabled protocol versions
+    pub(crate) fn protocol_versions(mut self, versions: &[ProtocolVersion]) -> Self {
+        self.versions = Some(versions.to_vec());
+        self
+    }
 
-            let mut pkcs8 = tokio_rustls::rustls::internal::pemfile::pkcs8_private_keys(
-                &mut key_vec.as_slice(),
-            )
-            .map_err(|()| TlsConfigError::Pkcs8ParseError)?;
+    /// sets the enabled cipher suites, most preferred first
+    pub(crate) fn cipher_suites(mut self, suites: &[&'static SupportedCipherSuite]) -> Self {
+        self.cipher_suites = Some(suites.to_vec());
+        self
+    }
 
-            if !pkcs8.is_empty() {
-                pkcs8.remove(0)
-            } else {
-                let mut rsa = tokio_rustls::rustls::internal::pemfile::rsa_private_keys(
-                    &mut key_vec.as_slice(),
-                )
-                .map_err(|()| TlsConfigError::RsaParseError)?;
+    /// sets how many sessions are kept for resumption, 0 to keep none
+    pub(crate) fn session_cache(mut self, size: usize) -> Self {
+        self.session_cache = Some(size);
+        self
+    }
 
-                if !rsa.is_empty() {
-                    rsa.remove(0)
-                } else {
-                    return Err(TlsConfigError::EmptyKey);
-                }
+    /// enables session tickets, with keys rotated every `rotation`
+    pub(crate) fn session_tickets(mut self, rotation: Duration) -> Self {
+        self.tickets = Some(Arc::new(RotatingTicketer::new(rotation)));
+        self
+    }
+
+    /// uses a fully built rustls config instead of any of the other options
+    pub(crate) fn rustls_config(mut self, config: ServerConfig) -> Self {
+        self.rustls = Some(config);
+        self
+    }
+
+    pub(crate) fn build(&self) -> Result<ServerConfig, TlsConfigError> {
+        if let Some(ref config) = self.rustls {
+            let defaults = TlsConfigBuilder::new();
+            let overridden = !matches!(self.cert, Source::Empty)
+                || !matches!(self.key, Source::Empty)
+                || !matches!(self.client_auth, TlsClientAuth::Off)
+                || !self.ocsp_resp.is_empty()
+                || !self.sni.is_empty()
+                || self.alpn_protocols != defaults.alpn_protocols
+                || self.versions.is_some()
+                || self.cipher_suites.is_some()
+                || self.session_cache.is_some()
+                || self.tickets.is_some();
+            if overridden {
+                return Err(TlsConfigError::ConflictingConfig);
             }
-        };
+            return Ok(config.clone());
+        }
 
-        fn read_trust_anchor(
-            trust_anchor: Box<dyn Read + Send + Sync>,
-        ) -> Result<RootCertStore, TlsConfigError> {
-            let mut reader = BufReader::new(trust_anchor);
+        fn read_trust_anchor(trust_anchor: &Source) -> Result<RootCertStore, TlsConfigError> {
+            let mut reader = BufReader::new(trust_anchor.reader());
             let mut store = RootCertStore::empty();
             if let Ok((0, _)) | Err(()) = store.add_pem_file(&mut reader) {
                 Err(TlsConfigError::CertParseError)
@@ -217,21 +665,320 @@ impl TlsConfigBuilder {
 
         let client_auth = match self.client_auth {
             TlsClientAuth::Off => NoClientAuth::new(),
-            TlsClientAuth::Optional(trust_anchor) => {
+            TlsClientAuth::Optional(ref trust_anchor) => {
                 AllowAnyAnonymousOrAuthenticatedClient::new(read_trust_anchor(trust_anchor)?)
             }
-            TlsClientAuth::Required(trust_anchor) => {
+            TlsClientAuth::Required(ref trust_anchor) => {
                 AllowAnyAuthenticatedClient::new(read_trust_anchor(trust_anchor)?)
             }
         };
 
         let mut config = ServerConfig::new(client_auth);
-        config
-            .set_single_cert_with_ocsp_and_sct(cert, key, self.ocsp_resp, Vec::new())
-            .map_err(|err| TlsConfigError::InvalidKey(err))?;
-        config.set_protocols(&["h2".into(), "http/1.1".into()]);
+        if self.sni.is_empty() {
+            config
+                .set_single_cert_with_ocsp_and_sct(
+                    read_certs(&self.cert)?,
+                    read_key(&self.key)?,
+                    self.ocsp_resp.clone(),
+                    Vec::new(),
+                )
+                .map_err(TlsConfigError::InvalidKey)?;
+        } else {
+            config.cert_resolver = Arc::new(self.sni_resolver()?);
+        }
+        self.set_options(&mut config)?;
         Ok(config)
     }
+
+    fn set_options(&self, config: &mut ServerConfig) -> Result<(), TlsConfigError> {
+        if let Some(id) = self
+            .alpn_protocols
+            .iter()
+            .find(|id| id.is_empty() || id.len() > 255)
+        {
+            return Err(TlsConfigError::InvalidAlpnProtocol(id.clone()));
+        }
+        config.set_protocols(&self.alpn_protocols);
+
+        if let Some(ref versions) = self.versions {
+            if versions.is_empty() {
+                return Err(TlsConfigError::NoProtocolVersions);
+            }
+            if let Some(&version) = versions
+                .iter()
+                .find(|&&v| v != ProtocolVersion::TLSv1_2 && v != ProtocolVersion::TLSv1_3)
+            {
+                return Err(TlsConfigError::UnsupportedProtocolVersion(version));
+            }
+            config.versions = versions.clone();
+        }
+        if let Some(ref suites) = self.cipher_suites {
+            config.ciphersuites = suites.clone();
+        }
+        if let Some(&version) = config.versions.iter().find(|&&version| {
+            !config
+                .ciphersuites
+                .iter()
+                .any(|suite| suite.usable_for_version(version))
+        }) {
+            return Err(TlsConfigError::NoCipherSuites(version));
+        }
+
+        match self.session_cache {
+            Some(0) => config.session_storage = Arc::new(NoServerSessionStorage {}),
+            Some(size) => config.session_storage = ServerSessionMemoryCache::new(size),
+            None => {}
+        }
+        if let Some(ref tickets) = self.tickets {
+            if tickets.rotation.as_secs() == 0 {
+                return Err(TlsConfigError::InvalidTicketRotation);
+            }
+            config.ticketer = tickets.clone();
+        }
+        Ok(())
+    }
+
+    fn sni_resolver(&self) -> Result<SniResolver, TlsConfigError> {
+        // With certificates per name, the default one is optional.
+        let default = match (&self.cert, &self.key) {
+            (Source::Empty, Source::Empty) => None,
+            (cert, key) => Some(certified_key(cert, key, &self.ocsp_resp)?),
+        };
+        let mut resolver = SniResolver {
+            exact: HashMap::new(),
+            wildcard: HashMap::new(),
+            default,
+            fallback: self.sni_fallback,
+        };
+        for entry in &self.sni {
+            let key = certified_key(&entry.cert, &entry.key, &entry.ocsp_resp)?;
+            match entry.name.strip_prefix("*.") {
+                Some(parent) => resolver.wildcard.insert(parent.to_owned(), key),
+                None => resolver.exact.insert(entry.name.clone(), key),
+            };
+        }
+        Ok(resolver)
+    }
+}
+
+fn read_certs(source: &Source) -> Result<Vec<Certificate>, TlsConfigError> {
+    let mut cert_rdr = BufReader::new(source.reader());
+    let cert = tokio_rustls::rustls::internal::pemfile::certs(&mut cert_rdr)
+        .map_err(|()| TlsConfigError::CertParseError)?;
+    if cert.is_empty() {
+        return Err(TlsConfigError::CertParseError);
+    }
+    Ok(cert)
+}
+
+fn read_key(source: &Source) -> Result<PrivateKey, TlsConfigError> {
+    // convert it to Vec<u8> to allow reading it again if key is RSA
+    let mut key_vec = Vec::new();
+    source
+        .reader()
+        .read_to_end(&mut key_vec)
+        .map_err(TlsConfigError::Io)?;
+
+    if key_vec.is_empty() {
+        return Err(TlsConfigError::EmptyKey);
+    }
+
+    let mut pkcs8 =
+        tokio_rustls::rustls::internal::pemfile::pkcs8_private_keys(&mut key_vec.as_slice())
+            .map_err(|()| TlsConfigError::Pkcs8ParseError)?;
+
+    if !pkcs8.is_empty() {
+        return Ok(pkcs8.remove(0));
+    }
+
+    let mut rsa =
+        tokio_rustls::rustls::internal::pemfile::rsa_private_keys(&mut key_vec.as_slice())
+            .map_err(|()| TlsConfigError::RsaParseError)?;
+
+    if !rsa.is_empty() {
+        return Ok(rsa.remove(0));
+    }
+
+    let mut sec1 = key::sec1_private_keys(&key_vec).map_err(|()| TlsConfigError::Sec1ParseError)?;
+
+    if !sec1.is_empty() {
+        Ok(sec1.remove(0))
+    } else {
+        Err(TlsConfigError::EmptyKey)
+    }
+}
+
+fn certified_key(
+    cert: &Source,
+    key: &Source,
+    ocsp_resp: &[u8],
+) -> Result<CertifiedKey, TlsConfigError> {
+    let cert = read_certs(cert)?;
+    let key = sign::any_supported_type(&read_key(key)?).map_err(|()| {
+        TlsConfigError::InvalidKey(TLSError::General("invalid private key".into()))
+    })?;
+    let mut certified = CertifiedKey::new(cert, Arc::new(key));
+    if !ocsp_resp.is_empty() {
+        certified.ocsp = Some(ocsp_resp.to_vec());
+    }
+    certified
+        .cross_check_end_entity_cert(None)
+        .map_err(TlsConfigError::InvalidKey)?;
+    Ok(certified)
+}
+
+// Server names are matched in lowercase, without a trailing dot.
+fn sni_name(name: &str) -> String {
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    let labels = name.strip_prefix("*.").unwrap_or(&name);
+    assert!(
+        !labels.is_empty() && !labels.contains('*') && !labels.split('.').any(str::is_empty),
+        "invalid server name {:?}",
+        name
+    );
+    name
+}
+
+/// Session tickets, with keys that are replaced every `rotation`.
+///
+/// Tickets made with the previous keys are still accepted, so a ticket lasts
+/// for up to twice the rotation.
+struct RotatingTicketer {
+    rotation: Duration,
+    keys: Mutex<TicketKeys>,
+}
+
+struct TicketKeys {
+    current: Arc<dyn ProducesTickets>,
+    previous: Option<Arc<dyn ProducesTickets>>,
+    rotated: Instant,
+}
+
+impl RotatingTicketer {
+    fn new(rotation: Duration) -> RotatingTicketer {
+        RotatingTicketer {
+            rotation,
+            keys: Mutex::new(TicketKeys {
+                current: Ticketer::new(),
+                previous: None,
+                rotated: Instant::now(),
+            }),
+        }
+    }
+
+    fn keys(&self) -> std::sync::MutexGuard<'_, TicketKeys> {
+        let mut keys = self.keys.lock().unwrap();
+        let elapsed = keys.rotated.elapsed();
+        if elapsed >= self.rotation {
+            // Keys two rotations old are dropped, and so are the current
+            // ones if nothing rotated them for that long.
+            let previous = std::mem::replace(&mut keys.current, Ticketer::new());
+            keys.previous = Some(previous).filter(|_| elapsed < 2 * self.rotation);
+            keys.rotated = Instant::now();
+        }
+        keys
+    }
+}
+
+impl ProducesTickets for RotatingTicketer {
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    fn get_lifetime(&self) -> u32 {
+        let lifetime = self.rotation.as_secs().saturating_mul(2);
+        lifetime.min(u64::from(self.keys().current.get_lifetime())) as u32
+    }
+
+    fn encrypt(&self, plain: &[u8]) -> Option<Vec<u8>> {
+        self.keys().current.encrypt(plain)
+    }
+
+    fn decrypt(&self, cipher: &[u8]) -> Option<Vec<u8>> {
+        let keys = self.keys();
+        keys.current.decrypt(cipher).or_else(|| {
+            keys.previous
+                .as_ref()
+                .and_then(|previous| previous.decrypt(cipher))
+        })
+    }
+}
+
+struct SniEntry {
+    name: String,
+    cert: Source,
+    key: Source,
+    ocsp_resp: Vec<u8>,
+}
+
+/// Picks the certificate for the server name a client asked for.
+struct SniResolver {
+    exact: HashMap<String, CertifiedKey>,
+    // Keyed by what follows the `*.`
+    wildcard: HashMap<String, CertifiedKey>,
+    default: Option<CertifiedKey>,
+    fallback: SniFallback,
+}
+
+impl SniResolver {
+    fn lookup(&self, name: &str) -> Option<&CertifiedKey> {
+        let name = name.trim_end_matches('.').to_ascii_lowercase();
+        if let Some(key) = self.exact.get(&name) {
+            return Some(key);
+        }
+        // A wildcard covers exactly one label.
+        let (label, parent) = name.split_once('.')?;
+        if label.is_empty() {
+            return None;
+        }
+        self.wildcard.get(parent)
+    }
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, hello: ClientHello) -> Option<CertifiedKey> {
+        let matched = match hello.server_name() {
+            Some(name) => {
+                let name: &str = name.into();
+                if let Some(key) = self.lookup(name) {
+                    return Some(key.clone());
+                }
+                self.fallback == SniFallback::Default
+            }
+            None => self.fallback != SniFallback::Reject,
+        };
+        if matched {
+            self.default.clone()
+        } else {
+            None
+        }
+    }
+}
+
+/// The config of a running server, which can be swapped by a `Reloader`.
+pub(crate) struct LiveConfig {
+    builder: TlsConfigBuilder,
+    current: RwLock<Arc<ServerConfig>>,
+}
+
+impl LiveConfig {
+    pub(crate) fn new(builder: TlsConfigBuilder) -> Result<LiveConfig, TlsConfigError> {
+        let config = builder.build()?;
+        Ok(LiveConfig {
+            builder,
+            current: RwLock::new(Arc::new(config)),
+        })
+    }
+
+    fn current(&self) -> Arc<ServerConfig> {
+        self.current.read().unwrap().clone()
+    }
+
+    fn reload(&self) -> Result<(), TlsConfigError> {
+        let config = self.builder.build()?;
+        *self.current.write().unwrap() = Arc::new(config);
+        Ok(())
+    }
 }
 
 struct LazyFile {
@@ -261,37 +1008,62 @@ impl Read for LazyFile {
     }
 }
 
-impl Transport for TlsStream {
+impl<T> Transport for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
     fn remote_addr(&self) -> Option<SocketAddr> {
-        Some(self.remote_addr)
+        self.remote_addr
+    }
+
+    fn tls_info(&self) -> Option<InfoSlot> {
+        Some(self.info.clone())
     }
 }
 
-enum State {
-    Handshaking(tokio_rustls::Accept<AddrStream>),
-    Streaming(tokio_rustls::server::TlsStream<AddrStream>),
+enum State<T> {
+    Handshaking(tokio_rustls::Accept<T>),
+    Streaming(tokio_rustls::server::TlsStream<T>),
 }
 
 // tokio_rustls::server::TlsStream doesn't expose constructor methods,
 // so we have to TlsAcceptor::accept and handshake to have access to it
 // TlsStream implements AsyncRead/AsyncWrite handshaking tokio_rustls::Accept first
-pub(crate) struct TlsStream {
-    state: State,
-    remote_addr: SocketAddr,
+pub(crate) struct TlsStream<T> {
+    state: State<T>,
+    remote_addr: Option<SocketAddr>,
+    info: InfoSlot,
 }
 
-impl TlsStream {
-    fn new(stream: AddrStream, config: Arc<ServerConfig>) -> TlsStream {
+impl<T> TlsStream<T>
+where
+    T: Transport + Unpin,
+{
+    fn new(stream: T, config: Arc<ServerConfig>) -> TlsStream<T> {
         let remote_addr = stream.remote_addr();
         let accept = tokio_rustls::TlsAcceptor::from(config).accept(stream);
         TlsStream {
             state: State::Handshaking(accept),
             remote_addr,
+            info: InfoSlot::default(),
         }
     }
 }
 
-impl AsyncRead for TlsStream {
+impl<T> TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn established(&mut self, stream: tokio_rustls::server::TlsStream<T>) {
+        let _ = self.info.set(TlsInfo::new(stream.get_ref().1));
+        self.state = State::Streaming(stream);
+    }
+}
+
+impl<T> AsyncRead for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context,
@@ -302,7 +1074,7 @@ impl AsyncRead for TlsStream {
             State::Handshaking(ref mut accept) => match ready!(Pin::new(accept).poll(cx)) {
                 Ok(mut stream) => {
                     let result = Pin::new(&mut stream).poll_read(cx, buf);
-                    pin.state = State::Streaming(stream);
+                    pin.established(stream);
                     result
                 }
                 Err(err) => Poll::Ready(Err(err)),
@@ -312,7 +1084,10 @@ impl AsyncRead for TlsStream {
     }
 }
 
-impl AsyncWrite for TlsStream {
+impl<T> AsyncWrite for TlsStream<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
     fn poll_write(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
@@ -323,7 +1098,7 @@ impl AsyncWrite for TlsStream {
             State::Handshaking(ref mut accept) => match ready!(Pin::new(accept).poll(cx)) {
                 Ok(mut stream) => {
                     let result = Pin::new(&mut stream).poll_write(cx, buf);
-                    pin.state = State::Streaming(stream);
+                    pin.established(stream);
                     result
                 }
                 Err(err) => Poll::Ready(Err(err)),
@@ -347,22 +1122,23 @@ impl AsyncWrite for TlsStream {
     }
 }
 
-pub(crate) struct TlsAcceptor {
-    config: Arc<ServerConfig>,
-    incoming: AddrIncoming,
+pub(crate) struct TlsAcceptor<I> {
+    config: Arc<LiveConfig>,
+    incoming: I,
 }
 
-impl TlsAcceptor {
-    pub(crate) fn new(config: ServerConfig, incoming: AddrIncoming) -> TlsAcceptor {
-        TlsAcceptor {
-            config: Arc::new(config),
-            incoming,
-        }
+impl<I> TlsAcceptor<I> {
+    pub(crate) fn new(config: Arc<LiveConfig>, incoming: I) -> TlsAcceptor<I> {
+        TlsAcceptor { config, incoming }
     }
 }
 
-impl Accept for TlsAcceptor {
-    type Conn = TlsStream;
+impl<I> Accept for TlsAcceptor<I>
+where
+    I: Accept<Error = io::Error> + Unpin,
+    I::Conn: Transport + Unpin,
+{
+    type Conn = TlsStream<I::Conn>;
     type Error = io::Error;
 
     fn poll_accept(
@@ -371,7 +1147,7 @@ impl Accept for TlsAcceptor {
     ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
         let pin = self.get_mut();
         match ready!(Pin::new(&mut pin.incoming).poll_accept(cx)) {
-            Some(Ok(sock)) => Poll::Ready(Some(Ok(TlsStream::new(sock, pin.config.clone())))),
+            Some(Ok(sock)) => Poll::Ready(Some(Ok(TlsStream::new(sock, pin.config.current())))),
             Some(Err(e)) => Poll::Ready(Some(Err(e))),
             None => Poll::Ready(None),
         }
@@ -402,4 +1178,125 @@ mod tests {
             .build()
             .unwrap();
     }
+
+    #[test]
+    fn sni_names() {
+        assert_eq!(sni_name("Example.COM."), "example.com");
+        assert_eq!(sni_name("*.example.com"), "*.example.com");
+        for name in &["", "*", "*.", "a*.example.com", "example..com", "www.*.com"] {
+            assert!(
+                std::panic::catch_unwind(|| sni_name(name)).is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn sni_without_default() {
+        let sni = |key: &str| {
+            TlsConfigBuilder::new()
+                .sni(
+                    "example.com",
+                    Source::Path("examples/tls/cert.pem".into()),
+                    Source::Path(key.into()),
+                )
+                .build()
+        };
+        assert!(sni("examples/tls/key.rsa").is_ok());
+        assert!(matches!(
+            sni("examples/tls/cert.pem"),
+            Err(TlsConfigError::EmptyKey)
+        ));
+    }
+
+    #[test]
+    fn sec1_key() {
+        TlsConfigBuilder::new()
+            .key_path("tests/tls/ec.key")
+            .cert_path("tests/tls/ec.pem")
+            .build()
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_options() {
+        let builder = || {
+            TlsConfigBuilder::new()
+                .key_path("tests/tls/server.key")
+                .cert_path("tests/tls/server.pem")
+        };
+        let err = |builder: TlsConfigBuilder| builder.build().err().unwrap().to_string();
+
+        assert_eq!(
+            err(builder().protocol_versions(&[])),
+            "no protocol versions enabled"
+        );
+        assert_eq!(
+            err(builder().protocol_versions(&[ProtocolVersion::TLSv1_1])),
+            "unsupported protocol version TLSv1_1"
+        );
+        assert_eq!(
+            err(builder()
+                .protocol_versions(&[ProtocolVersion::TLSv1_3])
+                .cipher_suites(&[&rustls::ciphersuite::TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256])),
+            "no cipher suites for protocol version TLSv1_3"
+        );
+        assert_eq!(
+            err(builder().alpn_protocols(vec![Vec::new()])),
+            "invalid alpn protocol \"\""
+        );
+        assert_eq!(
+            err(builder().session_tickets(Duration::from_secs(0))),
+            "ticket rotation must not be zero"
+        );
+        let config = builder().build().unwrap();
+        assert_eq!(
+            err(builder().rustls_config(config.clone())),
+            "rustls config can't be combined with other options"
+        );
+        assert!(TlsConfigBuilder::new()
+            .rustls_config(config)
+            .build()
+            .is_ok());
+
+        assert!(builder()
+            .protocol_versions(&[ProtocolVersion::TLSv1_3])
+            .cipher_suites(&[&rustls::ciphersuite::TLS13_AES_256_GCM_SHA384])
+            .alpn_protocols(vec![b"http/1.1".to_vec()])
+            .session_cache(0)
+            .session_tickets(Duration::from_secs(60))
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn ticket_rotation() {
+        let tickets = RotatingTicketer::new(Duration::from_secs(60 * 60));
+        assert_eq!(tickets.get_lifetime(), 2 * 60 * 60);
+
+        let first = tickets.encrypt(b"session").unwrap();
+        assert_eq!(tickets.decrypt(&first).unwrap(), b"session");
+
+        let rotate = || tickets.keys.lock().unwrap().rotated -= Duration::from_secs(60 * 60);
+        rotate();
+        let second = tickets.encrypt(b"session").unwrap();
+        assert_eq!(tickets.decrypt(&first).unwrap(), b"session");
+        assert_eq!(tickets.decrypt(&second).unwrap(), b"session");
+
+        rotate();
+        assert!(tickets.decrypt(&first).is_none());
+        assert_eq!(tickets.decrypt(&second).unwrap(), b"session");
+    }
+
+    #[test]
+    fn ticket_rotation_idle() {
+        let tickets = RotatingTicketer::new(Duration::from_secs(60));
+        let first = tickets.encrypt(b"session").unwrap();
+
+        // Nothing rotated the keys for several periods.
+        tickets.keys.lock().unwrap().rotated -= Duration::from_secs(3 * 60);
+        assert!(tickets.decrypt(&first).is_none());
+        assert!(tickets.keys.lock().unwrap().previous.is_none());
+    }
 }
@@ -0,0 +1,458 @@
+//! Just enough DER to read the subject and alternative names of a
+//! certificate.
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::SubjectAltName;
+
+pub(super) const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+pub(super) const OID: u8 = 0x06;
+pub(super) const OCTET_STRING: u8 = 0x04;
+const BOOLEAN: u8 = 0x01;
+const EXTENSIONS: u8 = 0xA3;
+
+const SUBJECT_ALT_NAME: &str = "2.5.29.17";
+
+/// Reads the subject, formatted like RFC 4514, and the alternative names.
+pub(super) fn parse(der: &[u8]) -> Option<(String, Vec<SubjectAltName>)> {
+    let mut certificate = Reader::new(Reader::new(der).expect(SEQUENCE)?);
+    let mut tbs = Reader::new(certificate.expect(SEQUENCE)?);
+
+    // version, if not the default
+    if tbs.peek() == Some(0xA0) {
+        tbs.read()?;
+    }
+    let _serial = tbs.read()?;
+    let _signature = tbs.expect(SEQUENCE)?;
+    let _issuer = tbs.expect(SEQUENCE)?;
+    let _validity = tbs.expect(SEQUENCE)?;
+    let subject = name(tbs.expect(SEQUENCE)?)?;
+    let _public_key = tbs.expect(SEQUENCE)?;
+
+    let mut alt_names = Vec::new();
+    while let Some((tag, value)) = tbs.read() {
+        if tag == EXTENSIONS {
+            let mut extensions = Reader::new(Reader::new(value).expect(SEQUENCE)?);
+            while !extensions.is_empty() {
+                let mut extension = Reader::new(extensions.expect(SEQUENCE)?);
+                let id = oid(extension.expect(OID)?);
+                if extension.peek() == Some(BOOLEAN) {
+                    extension.read()?;
+                }
+                let value = extension.expect(OCTET_STRING)?;
+                if id == SUBJECT_ALT_NAME {
+                    alt_names = general_names(value)?;
+                }
+            }
+        }
+    }
+
+    Some((subject, alt_names))
+}
+
+fn name(der: &[u8]) -> Option<String> {
+    let mut rdns = Vec::new();
+    let mut reader = Reader::new(der);
+    while !reader.is_empty() {
+        let mut set = Reader::new(reader.expect(SET)?);
+        let mut attributes = Vec::new();
+        while !set.is_empty() {
+            let mut attribute = Reader::new(set.expect(SEQUENCE)?);
+            let id = oid(attribute.expect(OID)?);
+            let (tag, value) = attribute.read()?;
+            let key = match short_name(&id) {
+                Some(key) => key.to_owned(),
+                None => id,
+            };
+            let value = match string(tag, value) {
+                Some(value) => escape(&value),
+                None => {
+                    // Unknown types are written as `#` and the hex of their
+                    // encoding.
+                    let mut hex = format!("#{:02x}", tag);
+                    for b in encode_len(value.len()).iter().chain(value) {
+                        let _ = write!(hex, "{:02x}", b);
+                    }
+                    hex
+                }
+            };
+            attributes.push(format!("{}={}", key, value));
+        }
+        rdns.push(attributes.join("+"));
+    }
+    // RFC 4514 writes the most specific name first.
+    rdns.reverse();
+    Some(rdns.join(","))
+}
+
+fn short_name(oid: &str) -> Option<&'static str> {
+    Some(match oid {
+        "2.5.4.3" => "CN",
+        "2.5.4.6" => "C",
+        "2.5.4.7" => "L",
+        "2.5.4.8" => "ST",
+        "2.5.4.9" => "STREET",
+        "2.5.4.10" => "O",
+        "2.5.4.11" => "OU",
+        "0.9.2342.19200300.100.1.1" => "UID",
+        "0.9.2342.19200300.100.1.25" => "DC",
+        _ => return None,
+    })
+}
+
+fn string(tag: u8, value: &[u8]) -> Option<String> {
+    match tag {
+        // UTF8String, PrintableString, IA5String
+        0x0C | 0x13 | 0x16 => String::from_utf8(value.to_vec()).ok(),
+        // TeletexString, which is Latin-1 in practice
+        0x14 => Some(value.iter().map(|&b| b as char).collect()),
+        // BMPString
+        0x1E => {
+            let pairs = value.chunks_exact(2);
+            if !pairs.remainder().is_empty() {
+                return None;
+            }
+            let units = pairs
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16(&units).ok()
+        }
+        _ => None,
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let last = value.chars().count().saturating_sub(1);
+    for (i, c) in value.chars().enumerate() {
+        let special = matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';')
+            || (i == 0 && (c == '#' || c == ' '))
+            || (i == last && c == ' ');
+        if special {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn general_names(der: &[u8]) -> Option<Vec<SubjectAltName>> {
+    let mut names = Vec::new();
+    let mut reader = Reader::new(Reader::new(der).expect(SEQUENCE)?);
+    while let Some((tag, value)) = reader.read() {
+        let text = || String::from_utf8(value.to_vec()).ok();
+        let name = match tag {
+            0x81 => SubjectAltName::Email(text()?),
+            0x82 => SubjectAltName::Dns(text()?),
+            0x86 => SubjectAltName::Uri(text()?),
+            0x87 => match value.len() {
+                4 => {
+                    let octets = <[u8; 4]>::try_from(value).ok()?;
+                    SubjectAltName::Ip(IpAddr::V4(Ipv4Addr::from(octets)))
+                }
+                16 => {
+                    let octets = <[u8; 16]>::try_from(value).ok()?;
+                    SubjectAltName::Ip(IpAddr::V6(Ipv6Addr::from(octets)))
+                }
+                _ => return None,
+            },
+            // Other names, directory names and so on aren't exposed.
+            _ => continue,
+        };
+        names.push(name);
+    }
+    Some(names)
+}
+
+fn oid(der: &[u8]) -> String {
+    let mut arcs = Vec::new();
+    let mut arc: u64 = 0;
+    for &b in der {
+        arc = (arc << 7) | u64::from(b & 0x7F);
+        if b & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (arc / 40).min(2);
+                arcs.push(first);
+                arcs.push(arc - first * 40);
+            } else {
+                arcs.push(arc);
+            }
+            arc = 0;
+        }
+    }
+    arcs.iter()
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+pub(super) fn encode_len(len: usize) -> Vec<u8> {
+    if len < 0x80 {
+        return vec![len as u8];
+    }
+    let bytes = len.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    let mut encoded = vec![0x80 | (bytes.len() - skip) as u8];
+    encoded.extend_from_slice(&bytes[skip..]);
+    encoded
+}
+
+pub(super) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(super) fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(super) fn peek(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    // Reads the next tag and its contents.
+    pub(super) fn read(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.data.split_first()?;
+        let (&first, rest) = rest.split_first()?;
+        let (len, rest) = if first < 0x80 {
+            (first as usize, rest)
+        } else {
+            let n = (first & 0x7F) as usize;
+            if n == 0 || n > 4 || rest.len() < n {
+                return None;
+            }
+            let len = rest[..n]
+                .iter()
+                .fold(0usize, |len, &b| (len << 8) | b as usize);
+            (len, &rest[n..])
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (value, rest) = rest.split_at(len);
+        self.data = rest;
+        Some((tag, value))
+    }
+
+    pub(super) fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        match self.read()? {
+            (t, value) if t == tag => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_cert() {
+        let pem = include_bytes!("../../examples/tls/cert.pem");
+        let certs = tokio_rustls::rustls::internal::pemfile::certs(&mut &pem[..]).unwrap();
+        let (subject, alt_names) = parse(&certs[0].0).unwrap();
+        assert_eq!(subject, "CN=testserver.com");
+        assert_eq!(
+            alt_names,
+            vec![
+                SubjectAltName::Dns("testserver.com".into()),
+                SubjectAltName::Dns("second.testserver.com".into()),
+                SubjectAltName::Dns("localhost".into()),
+            ]
+        );
+    }
+
+    fn tlv(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+        let value = parts.concat();
+        [&[tag][..], &encode_len(value.len()), &value].concat()
+    }
+
+    fn attribute(oid: &[u8], tag: u8, value: &[u8]) -> Vec<u8> {
+        tlv(SEQUENCE, &[&tlv(OID, &[oid]), &tlv(tag, &[value])])
+    }
+
+    // A certificate with just enough in it to be parsed.
+    fn certificate(subject: &[u8], names: Option<&[u8]>) -> Vec<u8> {
+        let empty = tlv(SEQUENCE, &[]);
+        let extensions = names.map(|names| {
+            let san = tlv(
+                SEQUENCE,
+                &[
+                    &tlv(OID, &[&[0x55, 0x1D, 0x11]]),
+                    &tlv(OCTET_STRING, &[&tlv(SEQUENCE, &[names])]),
+                ],
+            );
+            tlv(EXTENSIONS, &[&tlv(SEQUENCE, &[&san])])
+        });
+        let tbs = tlv(
+            SEQUENCE,
+            &[
+                &tlv(0xA0, &[&[0x02, 0x01, 0x02]]),
+                &[0x02, 0x01, 0x01],
+                &empty,
+                &empty,
+                &empty,
+                &tlv(SEQUENCE, &[subject]),
+                &empty,
+                extensions.as_deref().unwrap_or(&[]),
+            ],
+        );
+        tlv(SEQUENCE, &[&tbs, &empty, &[0x03, 0x01, 0x00]])
+    }
+
+    const CN: &[u8] = &[0x55, 0x04, 0x03];
+    const O: &[u8] = &[0x55, 0x04, 0x0A];
+
+    #[test]
+    fn subjects() {
+        let subject = |rdns: &[Vec<u8>]| {
+            let der = certificate(&rdns.concat(), None);
+            parse(&der).unwrap().0
+        };
+        let utf8 = |s: &str| attribute(CN, 0x0C, s.as_bytes());
+
+        // Most specific first, and multi-valued ones joined with `+`.
+        assert_eq!(
+            subject(&[
+                tlv(SET, &[&attribute(O, 0x13, b"Acme")]),
+                tlv(SET, &[&utf8("a"), &attribute(O, 0x13, b"b")]),
+            ]),
+            "CN=a+O=b,O=Acme"
+        );
+        assert_eq!(
+            subject(&[tlv(SET, &[&attribute(CN, 0x1E, &[0x00, 0x68, 0x00, 0xE9])])]),
+            "CN=h\u{e9}"
+        );
+        assert_eq!(
+            subject(&[tlv(SET, &[&attribute(&[0x2A, 0x03], 0x13, b"x")])]),
+            "1.2.3=x"
+        );
+        // Odd BMPStrings and unknown types are written as hex.
+        assert_eq!(
+            subject(&[tlv(SET, &[&attribute(CN, 0x1E, &[0x00, 0x68, 0x00])])]),
+            "CN=#1e03006800"
+        );
+        assert_eq!(
+            subject(&[tlv(SET, &[&attribute(CN, 0x02, &[0x07])])]),
+            "CN=#020107"
+        );
+        assert_eq!(subject(&[tlv(SET, &[&utf8(" a,b ")])]), "CN=\\ a\\,b\\ ");
+        assert_eq!(subject(&[]), "");
+
+        // Invalid UTF-8 can't be written as text either.
+        let invalid = certificate(&tlv(SET, &[&attribute(CN, 0x0C, &[0xFF])]), None);
+        assert_eq!(parse(&invalid).unwrap().0, "CN=#0c01ff");
+    }
+
+    #[test]
+    fn alt_names() {
+        let names = [
+            tlv(0x82, &[b"example.com"]),
+            tlv(0x87, &[&[127, 0, 0, 1]]),
+            tlv(0x87, &[&Ipv6Addr::LOCALHOST.octets()]),
+            tlv(0x81, &[b"admin@example.com"]),
+            tlv(0x86, &[b"https://example.com/"]),
+            // An other name, which is skipped.
+            tlv(0xA0, &[&tlv(OID, &[&[0x2A, 0x03]])]),
+        ]
+        .concat();
+        let der = certificate(&[], Some(&names));
+        assert_eq!(
+            parse(&der).unwrap().1,
+            vec![
+                SubjectAltName::Dns("example.com".into()),
+                SubjectAltName::Ip(Ipv4Addr::LOCALHOST.into()),
+                SubjectAltName::Ip(Ipv6Addr::LOCALHOST.into()),
+                SubjectAltName::Email("admin@example.com".into()),
+                SubjectAltName::Uri("https://example.com/".into()),
+            ]
+        );
+
+        // Addresses are 4 or 16 bytes.
+        let der = certificate(&[], Some(&tlv(0x87, &[&[127, 0, 0]])));
+        assert!(parse(&der).is_none());
+        let der = certificate(&[], Some(&tlv(0x82, &[&[0xFF]])));
+        assert!(parse(&der).is_none());
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse(&[]).is_none());
+        assert!(parse(&[SEQUENCE]).is_none());
+        // Lengths that are indefinite, too long, or past the end.
+        assert!(parse(&[SEQUENCE, 0x80, 0x00, 0x00]).is_none());
+        assert!(parse(&[SEQUENCE, 0x85, 0x01, 0x00, 0x00, 0x00, 0x00]).is_none());
+        assert!(parse(&[SEQUENCE, 0x84, 0xFF, 0xFF, 0xFF, 0xFF]).is_none());
+        assert!(parse(&[SEQUENCE, 0x02, 0x30]).is_none());
+        // An attribute without a value.
+        let der = certificate(&tlv(SET, &[&tlv(SEQUENCE, &[&tlv(OID, &[CN])])]), None);
+        assert!(parse(&der).is_none());
+        // An RDN that isn't a set.
+        let der = certificate(&tlv(SEQUENCE, &[&attribute(CN, 0x0C, b"a")]), None);
+        assert!(parse(&der).is_none());
+        // Extensions that aren't a sequence.
+        let mut der = certificate(&[], Some(&tlv(0x82, &[b"a"])));
+        let at = der.iter().rposition(|&b| b == EXTENSIONS).unwrap();
+        der[at + 2] = SET;
+        assert!(parse(&der).is_none());
+    }
+
+    // Cut and corrupted certificates are rejected or misread, but never
+    // panic.
+    #[test]
+    fn corrupted() {
+        let pem = include_bytes!("../../examples/tls/cert.pem");
+        let certs = tokio_rustls::rustls::internal::pemfile::certs(&mut &pem[..]).unwrap();
+        let der = &certs[0].0;
+
+        for len in 0..der.len() {
+            assert!(parse(&der[..len]).is_none(), "cut at {}", len);
+        }
+        for i in 0..der.len() {
+            for &flip in &[0x01, 0x80, 0xFF] {
+                let mut corrupted = der.clone();
+                corrupted[i] ^= flip;
+                let _ = parse(&corrupted);
+            }
+        }
+
+        // And a few bytes at a time, at random.
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut random = move |n: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % n as u64) as usize
+        };
+        for _ in 0..10_000 {
+            let mut corrupted = der.clone();
+            for _ in 0..=random(4) {
+                let i = random(corrupted.len());
+                corrupted[i] = random(256) as u8;
+            }
+            let _ = parse(&corrupted);
+        }
+    }
+
+    #[test]
+    fn oids() {
+        assert_eq!(oid(&[0x55, 0x04, 0x03]), "2.5.4.3");
+        assert_eq!(
+            oid(&[0x09, 0x92, 0x26, 0x89, 0x93, 0xF2, 0x2C, 0x64, 0x01, 0x19]),
+            "0.9.2342.19200300.100.1.25"
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("Acme, Inc."), "Acme\\, Inc.");
+        assert_eq!(escape("#1 "), "\\#1\\ ");
+    }
+}
@@ -8,6 +8,16 @@ use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 
 pub trait Transport: AsyncRead + AsyncWrite {
     fn remote_addr(&self) -> Option<SocketAddr>;
+
+    // The peer of connections that don't have a socket address.
+    fn peer(&self) -> Option<crate::addr::Peer> {
+        None
+    }
+
+    #[cfg(feature = "tls")]
+    fn tls_info(&self) -> Option<crate::tls::InfoSlot> {
+        None
+    }
 }
 
 impl Transport for AddrStream {
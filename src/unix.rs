@@ -0,0 +1,344 @@
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use hyper::server::accept::Accept;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::time::{sleep, Sleep};
+
+use crate::addr::{Credentials, Peer};
+use crate::transport::Transport;
+
+/// Configuration for serving on a Unix domain socket, with
+/// [`bind_unix`](crate::Server::bind_unix).
+///
+/// A socket file left behind by a server that didn't shut down cleanly is
+/// removed before binding, while one that a running server still listens on
+/// is an error. The socket file is removed again once the server stops.
+///
+/// Peers are seen by [`warp::addr::peer`](crate::addr::peer), along with
+/// their credentials.
+///
+/// *This type is only available on Unix.*
+///
+/// # Example
+///
+/// ```no_run
+/// use warp::{Filter, UnixSocket};
+///
+/// # async fn run() {
+/// let routes = warp::addr::peer().map(|peer| format!("hello, {:?}", peer));
+///
+/// warp::serve(routes)
+///     .bind_unix(UnixSocket::new("/run/app/http.sock").mode(0o660))
+///     .await;
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct UnixSocket {
+    path: PathBuf,
+    mode: Option<u32>,
+    remove_stale: bool,
+}
+
+impl UnixSocket {
+    /// Creates a configuration for a socket at `path`.
+    pub fn new(path: impl AsRef<Path>) -> UnixSocket {
+        UnixSocket {
+            path: path.as_ref().to_owned(),
+            mode: None,
+            remove_stale: true,
+        }
+    }
+
+    /// Sets the permissions of the socket file, such as `0o660`.
+    ///
+    /// The socket is bound in a private directory next to the path, and only
+    /// linked to the path once it has these permissions, so it's never
+    /// reachable with others.
+    ///
+    /// Otherwise, they are whatever the umask of the process allows.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Sets whether a stale socket file at the path is removed before
+    /// binding, which it is by default.
+    pub fn remove_stale(mut self, remove: bool) -> Self {
+        self.remove_stale = remove;
+        self
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn bind(&self) -> io::Result<UnixIncoming> {
+        if self.remove_stale {
+            remove_stale(&self.path)?;
+        }
+        let listener = match self.mode {
+            Some(mode) => bind_with_mode(&self.path, mode)?,
+            None => UnixListener::bind(&self.path)?,
+        };
+        let unlink = Unlink::new(&self.path)?;
+        Ok(UnixIncoming {
+            listener,
+            timeout: None,
+            _unlink: Some(unlink),
+        })
+    }
+}
+
+impl From<&str> for UnixSocket {
+    fn from(path: &str) -> UnixSocket {
+        UnixSocket::new(path)
+    }
+}
+
+impl From<String> for UnixSocket {
+    fn from(path: String) -> UnixSocket {
+        UnixSocket::new(path)
+    }
+}
+
+impl From<&Path> for UnixSocket {
+    fn from(path: &Path) -> UnixSocket {
+        UnixSocket::new(path)
+    }
+}
+
+impl From<PathBuf> for UnixSocket {
+    fn from(path: PathBuf) -> UnixSocket {
+        UnixSocket::new(path)
+    }
+}
+
+// Binds a socket in a private directory, sets its mode, and then links it to
+// `path`, failing like binding does if something is already there.
+fn bind_with_mode(path: &Path, mode: u32) -> io::Result<UnixListener> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} isn't a file path", path.display()),
+        )
+    })?;
+    let mut dir_name = std::ffi::OsString::from(".");
+    dir_name.push(name);
+    dir_name.push(format!(".{}", std::process::id()));
+    let dir = path.with_file_name(dir_name);
+    fs::DirBuilder::new().mode(0o700).create(&dir)?;
+
+    let private = dir.join("s");
+    let bound = UnixListener::bind(&private).and_then(|listener| {
+        fs::set_permissions(&private, fs::Permissions::from_mode(mode))?;
+        fs::hard_link(&private, path).map_err(|err| match err.kind() {
+            io::ErrorKind::AlreadyExists => io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("{} already exists", path.display()),
+            ),
+            _ => err,
+        })?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&private);
+    let _ = fs::remove_dir(&dir);
+    bound
+}
+
+// Removes a socket file nobody listens on anymore.
+fn remove_stale(path: &Path) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} exists and isn't a socket", path.display()),
+        ));
+    }
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is in use", path.display()),
+        )),
+        Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path),
+        Err(err) => Err(err),
+    }
+}
+
+// Removes the socket file when the server stops, unless it has been replaced
+// since.
+struct Unlink {
+    path: PathBuf,
+    dev: u64,
+    ino: u64,
+}
+
+impl Unlink {
+    fn new(path: &Path) -> io::Result<Unlink> {
+        let metadata = fs::symlink_metadata(path)?;
+        Ok(Unlink {
+            path: path.to_owned(),
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+}
+
+impl Drop for Unlink {
+    fn drop(&mut self) {
+        if let Ok(metadata) = fs::symlink_metadata(&self.path) {
+            if metadata.dev() == self.dev && metadata.ino() == self.ino {
+                let _ = fs::remove_file(&self.path);
+            }
+        }
+    }
+}
+
+pub(crate) struct UnixIncoming {
+    listener: UnixListener,
+    // Set while backing off after an error such as running out of fds.
+    timeout: Option<Pin<Box<Sleep>>>,
+    _unlink: Option<Unlink>,
+}
+
+impl UnixIncoming {
+    // Serves a socket someone else bound, and leaves its file alone.
+    pub(crate) fn from_listener(listener: UnixListener) -> UnixIncoming {
+        UnixIncoming {
+            listener,
+            timeout: None,
+            _unlink: None,
+        }
+    }
+}
+
+impl UnixIncoming {
+    // Accepts the next connection, like hyper's `AddrIncoming`: errors of a
+    // single connection are skipped, and others, such as running out of
+    // file descriptors, are retried after a second instead of stopping the
+    // server.
+    fn poll_next(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<(UnixStream, tokio::net::unix::SocketAddr)> {
+        if let Some(timeout) = &mut self.timeout {
+            futures::ready!(timeout.as_mut().poll(cx));
+            self.timeout = None;
+        }
+
+        loop {
+            match futures::ready!(self.listener.poll_accept(cx)) {
+                Ok(accepted) => return Poll::Ready(accepted),
+                Err(err) if is_connection_error(&err) => {
+                    tracing::debug!("accepted connection already errored: {}", err);
+                }
+                Err(err) => {
+                    tracing::error!("accept error: {}", err);
+                    let mut timeout = Box::pin(sleep(Duration::from_secs(1)));
+                    match timeout.as_mut().poll(cx) {
+                        Poll::Ready(()) => continue,
+                        Poll::Pending => {
+                            self.timeout = Some(timeout);
+                            return Poll::Pending;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_connection_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    )
+}
+
+impl Accept for UnixIncoming {
+    type Conn = UnixConn;
+    type Error = io::Error;
+
+    fn poll_accept(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Conn, Self::Error>>> {
+        let (stream, addr) = match self.poll_next(cx) {
+            Poll::Ready(accepted) => accepted,
+            Poll::Pending => return Poll::Pending,
+        };
+        let credentials = match stream.peer_cred() {
+            Ok(cred) => Some(Credentials {
+                uid: cred.uid(),
+                gid: cred.gid(),
+                pid: cred.pid(),
+            }),
+            Err(err) => {
+                tracing::debug!("peer credentials error: {}", err);
+                None
+            }
+        };
+        let peer = Peer::Unix {
+            path: addr.as_pathname().map(Path::to_owned),
+            credentials,
+        };
+        Poll::Ready(Some(Ok(UnixConn { stream, peer })))
+    }
+}
+
+pub(crate) struct UnixConn {
+    stream: UnixStream,
+    peer: Peer,
+}
+
+impl Transport for UnixConn {
+    fn remote_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    fn peer(&self) -> Option<Peer> {
+        Some(self.peer.clone())
+    }
+}
+
+impl AsyncRead for UnixConn {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixConn {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stream).poll_shutdown(cx)
+    }
+}
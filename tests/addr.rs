@@ -22,3 +22,16 @@ async fn remote_addr_present() {
         Some(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4)), 5678))
     )
 }
+
+#[tokio::test]
+async fn peer_tcp() {
+    let extract_peer = warp::addr::peer();
+
+    let req = warp::test::request();
+    assert_eq!(req.filter(&extract_peer).await.unwrap(), None);
+
+    let req = warp::test::request().remote_addr("1.2.3.4:5678".parse().unwrap());
+    let peer = req.filter(&extract_peer).await.unwrap().unwrap();
+    assert_eq!(peer, warp::addr::Peer::Tcp("1.2.3.4:5678".parse().unwrap()));
+    assert_eq!(peer.to_string(), "1.2.3.4:5678");
+}
@@ -1,5 +1,7 @@
 #![deny(warnings)]
 
+use std::collections::HashMap;
+
 use bytes::Buf;
 use futures::TryStreamExt;
 use warp::Filter;
@@ -106,6 +108,58 @@ async fn json_invalid() {
     assert_eq!(&res.body()[..prefix.len()], prefix);
 }
 
+#[derive(Debug, serde_derive::Deserialize)]
+#[allow(dead_code)]
+struct Signup {
+    user: User,
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+#[allow(dead_code)]
+struct User {
+    name: String,
+    tags: Vec<String>,
+}
+
+#[tokio::test]
+async fn json_invalid_details() {
+    let _ = pretty_env_logger::try_init();
+
+    let json = warp::body::json::<Signup>();
+
+    let err = warp::test::request()
+        .body("{\n  \"user\": {\"name\": \"a\", \"tags\": [\"x\", 5]}\n}")
+        .filter(&json)
+        .await
+        .unwrap_err();
+    let err = err.find::<warp::body::BodyDeserializeError>().unwrap();
+    assert_eq!(err.field(), Some("user.tags[1]"));
+    assert_eq!(err.line(), Some(2));
+    assert_eq!(err.column(), Some(39));
+
+    // Trailing characters aren't in any field.
+    let err = warp::test::request()
+        .body("{\"user\": {\"name\": \"a\", \"tags\": []}} x")
+        .filter(&json)
+        .await
+        .unwrap_err();
+    let err = err.find::<warp::body::BodyDeserializeError>().unwrap();
+    assert_eq!(err.field(), None);
+    assert_eq!(err.column(), Some(37));
+
+    // Only JSON has positions.
+    let form = warp::body::form::<HashMap<String, u32>>();
+    let err = warp::test::request()
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body("a=1&b=x")
+        .filter(&form)
+        .await
+        .unwrap_err();
+    let err = err.find::<warp::body::BodyDeserializeError>().unwrap();
+    assert_eq!(err.field(), Some("b"));
+    assert_eq!(err.line(), None);
+}
+
 #[test]
 fn json_size_of() {
     let json = warp::body::json::<Vec<i32>>();
@@ -0,0 +1,180 @@
+#![deny(warnings)]
+use warp::cookie::{Cookie, CookieJar, Key, Keys};
+use warp::Filter;
+
+// Turns the `Set-Cookie` headers of a response into a `Cookie` header.
+fn cookie_header(res: &warp::http::Response<warp::hyper::body::Bytes>) -> String {
+    res.headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|v| {
+            let v = v.to_str().unwrap();
+            v.split(';').next().unwrap().to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[tokio::test]
+async fn add_and_remove() {
+    let _ = pretty_env_logger::try_init();
+
+    let route = warp::cookie::jar(Key::generate()).map(|jar: CookieJar| {
+        let theme = jar.get("theme").map(|c| c.value().to_string());
+        jar.add(
+            Cookie::build(("lang", "en"))
+                .path("/")
+                .http_only(true)
+                .same_site(warp::cookie::SameSite::Lax),
+        );
+        jar.remove(Cookie::build("old").path("/"));
+        format!("{:?}", theme)
+    });
+
+    let res = warp::test::request()
+        .header("cookie", "theme=dark; old=1")
+        .reply(&route)
+        .await;
+    assert_eq!(res.body(), "Some(\"dark\")");
+
+    let set = res
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|v| v.to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&"lang=en; HttpOnly; SameSite=Lax; Path=/".to_string()));
+    assert!(set
+        .iter()
+        .any(|c| c.starts_with("old=; Path=/") && c.contains("Max-Age=0")));
+}
+
+#[tokio::test]
+async fn no_changes_no_header() {
+    let route = warp::cookie::jar(Key::generate()).map(|jar: CookieJar| {
+        let _ = jar.get("theme");
+        warp::reply()
+    });
+
+    let res = warp::test::request()
+        .header("cookie", "theme=dark")
+        .reply(&route)
+        .await;
+    assert!(res.headers().get("set-cookie").is_none());
+}
+
+#[tokio::test]
+async fn signed_and_private() {
+    let key = Key::generate();
+
+    let set = warp::path("set")
+        .and(warp::cookie::jar(key.clone()))
+        .map(|jar: CookieJar| {
+            jar.signed().add(("user", "alice"));
+            jar.private().add(("secret", "42"));
+            warp::reply()
+        });
+    let get = warp::path("get")
+        .and(warp::cookie::jar(key))
+        .map(|jar: CookieJar| {
+            let user = jar.signed().get("user").map(|c| c.value().to_string());
+            let secret = jar.private().get("secret").map(|c| c.value().to_string());
+            format!("{:?} {:?}", user, secret)
+        });
+    let route = set.or(get);
+
+    let res = warp::test::request().path("/set").reply(&route).await;
+    let cookies = cookie_header(&res);
+    assert!(!cookies.contains("42"), "private cookies are encrypted");
+
+    let res = warp::test::request()
+        .path("/get")
+        .header("cookie", &cookies)
+        .reply(&route)
+        .await;
+    assert_eq!(res.body(), "Some(\"alice\") Some(\"42\")");
+
+    // Tampering with a signed cookie makes it invalid.
+    let tampered = cookies.replace("alice", "mallory");
+    let res = warp::test::request()
+        .path("/get")
+        .header("cookie", &tampered)
+        .reply(&route)
+        .await;
+    assert_eq!(res.body(), "None Some(\"42\")");
+}
+
+#[tokio::test]
+async fn key_rotation() {
+    let old = Key::generate();
+    let new = Key::generate();
+
+    let set = warp::cookie::jar(old.clone()).map(|jar: CookieJar| {
+        jar.private().add(("session", "abc"));
+        warp::reply()
+    });
+    let res = warp::test::request().reply(&set).await;
+    let cookies = cookie_header(&res);
+
+    let read = |keys: Keys| {
+        warp::cookie::jar(keys).map(|jar: CookieJar| {
+            format!(
+                "{:?}",
+                jar.private().get("session").map(|c| c.value().to_string())
+            )
+        })
+    };
+
+    let res = warp::test::request()
+        .header("cookie", &cookies)
+        .reply(&read(Keys::new(new.clone())))
+        .await;
+    assert_eq!(res.body(), "None");
+
+    let res = warp::test::request()
+        .header("cookie", &cookies)
+        .reply(&read(Keys::new(new).previous(old)))
+        .await;
+    assert_eq!(res.body(), "Some(\"abc\")");
+}
+
+#[tokio::test]
+async fn shared_across_filters_not_rejections() {
+    let key = Key::generate();
+
+    let route = warp::cookie::jar(key.clone())
+        .and_then(|jar: CookieJar| async move {
+            jar.add(("seen", "1"));
+            Err::<CookieJar, _>(warp::reject::not_found())
+        })
+        .or(warp::cookie::jar(key.clone()))
+        .unify()
+        .and(warp::cookie::jar(key))
+        .map(|first: CookieJar, second: CookieJar| {
+            // The changes of the branch that rejected are undone.
+            assert!(first.get("seen").is_none());
+            first.add(("other", "2"));
+            assert!(second.get("other").is_some());
+            warp::reply()
+        });
+
+    let res = warp::test::request().reply(&route).await;
+    assert_eq!(cookie_header(&res), "other=2");
+
+    // Even when the jar was taken before the branch.
+    let key = Key::generate();
+    let outer = warp::cookie::jar(key.clone())
+        .map(|jar: CookieJar| jar.add(("outer", "0")))
+        .untuple_one();
+    let rejects = warp::cookie::jar(key.clone()).and_then(|jar: CookieJar| async move {
+        jar.add(("seen", "1"));
+        jar.remove("outer");
+        Err::<&str, _>(warp::reject::not_found())
+    });
+    let route = outer.and(rejects.or(warp::any().map(|| "ok")).unify());
+
+    let res = warp::test::request().reply(&route).await;
+    assert_eq!(res.body(), "ok");
+    assert_eq!(cookie_header(&res), "outer=0");
+}
@@ -69,3 +69,40 @@ async fn optional() {
         "invalid optional header still rejects",
     );
 }
+
+#[tokio::test]
+async fn invalid_details() {
+    let _ = pretty_env_logger::try_init();
+
+    let con_len = warp::header::<u64>("content-length");
+    let rej = warp::test::request()
+        .header("content-length", "boom")
+        .filter(&con_len)
+        .await
+        .unwrap_err();
+    let err = rej.find::<warp::reject::InvalidHeader>().unwrap();
+    assert_eq!(err.name(), "content-length");
+    assert_eq!(err.value(), Some("boom"));
+    assert_eq!(err.expected(), Some("u64"));
+
+    let host = warp::header::exact("host", "localhost");
+    let rej = warp::test::request()
+        .header("host", "nope")
+        .filter(&host)
+        .await
+        .unwrap_err();
+    let err = rej.find::<warp::reject::InvalidHeader>().unwrap();
+    assert_eq!(err.value(), Some("nope"));
+    // The value compared against could be a secret.
+    assert_eq!(err.expected(), None);
+
+    // Credentials are never kept.
+    let auth = warp::header::exact("authorization", "Bearer secret");
+    let rej = warp::test::request()
+        .header("authorization", "Bearer guess")
+        .filter(&auth)
+        .await
+        .unwrap_err();
+    let err = rej.find::<warp::reject::InvalidHeader>().unwrap();
+    assert_eq!(err.value(), None);
+}
@@ -85,3 +85,102 @@ async fn optional() {
     let req = warp::test::request();
     assert_eq!(req.filter(&filter).await.unwrap(), None);
 }
+
+#[tokio::test]
+async fn pattern() {
+    let filter = warp::host::pattern("{tenant}.*.example.com");
+
+    let req = warp::test::request().header("host", "acme.eu.example.com:8080");
+    let params = req.filter(&filter).await.unwrap();
+    assert_eq!(params.get("tenant"), Some("acme"));
+    assert_eq!(params.iter().collect::<Vec<_>>(), [("tenant", "acme")]);
+
+    // case-insensitive, and through the URI
+    let req = warp::test::request().path("http://ACME.us.Example.COM/");
+    let params = req.filter(&filter).await.unwrap();
+    assert_eq!(params.get("tenant"), Some("acme"));
+
+    // labels don't span dots
+    let req = warp::test::request().header("host", "a.b.eu.example.com");
+    assert!(req.filter(&filter).await.unwrap_err().is_not_found());
+
+    let req = warp::test::request().header("host", "acme.eu.example.org");
+    assert!(req.filter(&filter).await.unwrap_err().is_not_found());
+
+    // no authority
+    let req = warp::test::request();
+    assert!(req.filter(&filter).await.unwrap_err().is_not_found());
+
+    // the mismatch rejection is kept
+    let req = warp::test::request()
+        .header("host", "acme.eu.example.com")
+        .path("http://other.eu.example.com/");
+    assert!(req
+        .filter(&filter)
+        .await
+        .unwrap_err()
+        .find::<warp::reject::InvalidHeader>()
+        .is_some());
+}
+
+#[cfg(feature = "idna")]
+#[tokio::test]
+async fn pattern_port_and_idna() {
+    let filter = warp::host::pattern("{shop}.bücher.example:8443");
+
+    let req = warp::test::request().header("host", "kiosk.xn--bcher-kva.example:8443");
+    let params = req.filter(&filter).await.unwrap();
+    assert_eq!(params.get("shop"), Some("kiosk"));
+
+    let req = warp::test::request().header("host", "kiosk.xn--bcher-kva.example");
+    assert!(req.filter(&filter).await.unwrap_err().is_not_found());
+}
+
+#[cfg(not(feature = "idna"))]
+#[test]
+#[should_panic(expected = "invalid host pattern")]
+fn pattern_idna_needs_feature() {
+    let _ = warp::host::pattern("{shop}.bücher.example");
+}
+
+#[tokio::test]
+async fn pattern_ipv6() {
+    let filter = warp::host::pattern("[::1]");
+    for host in &["[::1]", "[::1]:8080"] {
+        let req = warp::test::request().header("host", *host);
+        assert!(req.filter(&filter).await.is_ok(), "{}", host);
+    }
+    let req = warp::test::request().header("host", "[::2]");
+    assert!(req.filter(&filter).await.unwrap_err().is_not_found());
+
+    let filter = warp::host::pattern("[::1]:8080");
+    let req = warp::test::request().header("host", "[::1]:8080");
+    assert!(req.filter(&filter).await.is_ok());
+    let req = warp::test::request().header("host", "[::1]:8081");
+    assert!(req.filter(&filter).await.unwrap_err().is_not_found());
+}
+
+#[test]
+#[should_panic(expected = "invalid host pattern")]
+fn pattern_ipv6_unclosed() {
+    let _ = warp::host::pattern("[::1:8080");
+}
+
+#[tokio::test]
+async fn suffix() {
+    let filter = warp::host::suffix("example.com");
+
+    for host in &["example.com", "www.example.com:80", "a.b.EXAMPLE.com"] {
+        let req = warp::test::request().header("host", *host);
+        assert!(req.filter(&filter).await.is_ok(), "{}", host);
+    }
+
+    for host in &["notexample.com", "example.com.evil", "com"] {
+        let req = warp::test::request().header("host", *host);
+        assert!(
+            req.filter(&filter).await.unwrap_err().is_not_found(),
+            "{}",
+            host
+        );
+    }
+}
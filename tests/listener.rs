@@ -0,0 +1,222 @@
+#![deny(warnings)]
+use std::net::TcpListener;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use warp::{Filter, Listener};
+
+const REQUEST: &[u8] = b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n";
+
+#[tokio::test]
+async fn tcp() {
+    let _ = pretty_env_logger::try_init();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let listener = Listener::from(listener);
+    assert_eq!(listener.local_addr(), Some(addr));
+    assert_eq!(listener.name(), None);
+
+    let routes = warp::addr::remote()
+        .map(|addr: Option<std::net::SocketAddr>| addr.unwrap().ip().to_string());
+    tokio::spawn(warp::serve(routes).bind_listener(listener));
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(REQUEST).await.unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.ends_with("127.0.0.1"), "{}", response);
+}
+
+#[tokio::test]
+async fn graceful_shutdown() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+    let server = warp::serve(warp::any().map(warp::reply)).bind_listener_with_graceful_shutdown(
+        listener,
+        async {
+            rx.await.ok();
+        },
+    );
+    let server = tokio::spawn(server);
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(REQUEST).await.unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+
+    tx.send(()).unwrap();
+    server.await.unwrap();
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix() {
+    use std::os::unix::net::UnixListener;
+    use tokio::net::UnixStream;
+    use warp::addr::Peer;
+
+    let dir = std::env::temp_dir().join(format!("warp-listener-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("inherited.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path).unwrap();
+    let routes = warp::addr::peer().map(|peer: Option<Peer>| match peer {
+        Some(Peer::Unix { .. }) => "unix",
+        _ => "other",
+    });
+    let server = tokio::spawn(warp::serve(routes).bind_listener(listener));
+
+    let mut stream = UnixStream::connect(&path).await.unwrap();
+    stream.write_all(REQUEST).await.unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    assert!(response.ends_with("unix"), "{}", response);
+
+    // The socket belongs to whoever bound it, so it's left in place.
+    server.abort();
+    let _ = server.await;
+    assert!(path.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn not_inherited() {
+    // Meant for some other process.
+    std::env::set_var("LISTEN_PID", "1");
+    std::env::set_var("LISTEN_FDS", "1");
+    assert!(Listener::inherited().unwrap().is_empty());
+    assert!(std::env::var_os("LISTEN_FDS").is_some());
+
+    let result = warp::serve(warp::any().map(warp::reply)).bind_inherited();
+    assert!(result.is_err());
+}
+
+// Runs the ignored test `name` in a child, passed `fds` from 3 on like systemd
+// does.
+#[cfg(unix)]
+fn spawn_child(name: &str, fds: &[i32], envs: &[(&str, String)]) -> std::process::Child {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let mut command = Command::new(std::env::current_exe().unwrap());
+    command
+        .args(&[name, "--exact", "--ignored"])
+        .env("LISTEN_FDS", fds.len().to_string())
+        .envs(envs.iter().map(|(k, v)| (k, v)))
+        .stdout(Stdio::null());
+    let fds = fds.to_vec();
+    unsafe {
+        command.pre_exec(move || {
+            for (i, &fd) in fds.iter().enumerate() {
+                let target = 3 + i as i32;
+                // `dup2` clears close-on-exec, unless there's nothing to copy.
+                let result = if fd == target {
+                    libc::fcntl(fd, libc::F_SETFD, 0)
+                } else {
+                    libc::dup2(fd, target)
+                };
+                if result == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    command.spawn().unwrap()
+}
+
+// Passes a listener to a child, like systemd does, and has it serve a request.
+#[cfg(unix)]
+#[test]
+fn inherited() {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let mut child = spawn_child(
+        "inherited_child",
+        &[listener.as_raw_fd()],
+        &[
+            ("LISTEN_FDNAMES", "http".to_owned()),
+            ("EXPECTED_ADDR", addr.to_string()),
+        ],
+    );
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+        .unwrap();
+    stream.write_all(REQUEST).unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    if !response.ends_with("inherited http") {
+        let _ = child.kill();
+        panic!("unexpected response: {:?}", response);
+    }
+    assert!(child.wait().unwrap().success());
+}
+
+// One file descriptor that isn't a listener fails without closing the others.
+#[cfg(unix)]
+#[test]
+fn inherited_invalid() {
+    use std::os::unix::io::AsRawFd;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let udp = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let mut child = spawn_child(
+        "inherited_invalid_child",
+        &[listener.as_raw_fd(), udp.as_raw_fd()],
+        &[],
+    );
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(unix)]
+#[test]
+#[ignore = "run by `inherited_invalid`"]
+fn inherited_invalid_child() {
+    std::env::set_var("LISTEN_PID", std::process::id().to_string());
+    assert!(Listener::inherited().is_err());
+    assert_ne!(unsafe { libc::fcntl(3, libc::F_GETFD) }, -1, "fd 3 closed");
+
+    // Nothing was taken, so it's the same again.
+    assert!(Listener::inherited().is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+#[ignore = "run by `inherited`"]
+async fn inherited_child() {
+    // Set by systemd once it knows the pid.
+    std::env::set_var("LISTEN_PID", std::process::id().to_string());
+    let mut listeners = Listener::inherited().unwrap();
+    assert_eq!(listeners.len(), 1);
+    let listener = listeners.remove(0);
+    assert_eq!(listener.name(), Some("http"));
+    assert_eq!(
+        listener.local_addr().unwrap().to_string(),
+        std::env::var("EXPECTED_ADDR").unwrap()
+    );
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+    let routes = warp::any().map(move || {
+        let _ = tx.send(());
+        "inherited http"
+    });
+    let mut rx = rx;
+    warp::serve(routes)
+        .bind_listener_with_graceful_shutdown(listener, async move {
+            rx.recv().await;
+        })
+        .await;
+}
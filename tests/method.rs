@@ -15,6 +15,7 @@ async fn method() {
     let req = warp::test::request().method("POST");
     let resp = req.reply(&get).await;
     assert_eq!(resp.status(), 405);
+    assert_eq!(resp.headers()["allow"], "GET");
 }
 
 #[tokio::test]
@@ -32,6 +33,90 @@ async fn method_not_allowed_trumps_not_found() {
     assert_eq!(resp.status(), 405);
 }
 
+#[tokio::test]
+async fn method_not_allowed_lists_allowed_methods() {
+    let _ = pretty_env_logger::try_init();
+    let get = warp::path("hello").and(warp::get()).map(warp::reply);
+    let post = warp::path("hello").and(warp::post()).map(warp::reply);
+    let put = warp::path("bye").and(warp::put()).map(warp::reply);
+
+    let routes = get.or(post).or(put);
+
+    let req = warp::test::request().method("DELETE").path("/hello");
+
+    let resp = req.reply(&routes).await;
+    assert_eq!(resp.status(), 405);
+    assert_eq!(resp.headers()["allow"], "GET, POST");
+}
+
+#[tokio::test]
+async fn allowed_methods_only_from_matching_paths() {
+    let _ = pretty_env_logger::try_init();
+    let a = warp::get().and(warp::path("a")).map(warp::reply);
+    let b = warp::post().and(warp::path("b")).map(warp::reply);
+    let routes = a.or(b);
+
+    let resp = warp::test::request()
+        .method("DELETE")
+        .path("/a")
+        .reply(&routes)
+        .await;
+    assert_eq!(resp.status(), 405);
+    assert_eq!(resp.headers()["allow"], "GET");
+
+    // No route has this path, whatever the method.
+    let resp = warp::test::request()
+        .method("DELETE")
+        .path("/c")
+        .reply(&routes)
+        .await;
+    assert_eq!(resp.status(), 404);
+
+    // Paths are matched whole when they end.
+    let c = warp::put()
+        .and(warp::path!("c" / u32))
+        .map(|_| warp::reply());
+    let routes = routes.or(c);
+    let resp = warp::test::request()
+        .method("DELETE")
+        .path("/c/1")
+        .reply(&routes)
+        .await;
+    assert_eq!(resp.headers()["allow"], "PUT");
+    let resp = warp::test::request()
+        .method("DELETE")
+        .path("/c/1/2")
+        .reply(&routes)
+        .await;
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn allowed_methods_of_chained_paths() {
+    let _ = pretty_env_logger::try_init();
+    let ab = warp::get()
+        .and(warp::path("a"))
+        .and(warp::path("b"))
+        .map(warp::reply);
+    let c = warp::post().and(warp::path("c")).map(warp::reply);
+    let routes = ab.or(c);
+
+    let resp = warp::test::request()
+        .method("DELETE")
+        .path("/a/b")
+        .reply(&routes)
+        .await;
+    assert_eq!(resp.status(), 405);
+    assert_eq!(resp.headers()["allow"], "GET");
+
+    let resp = warp::test::request()
+        .method("DELETE")
+        .path("/a/c")
+        .reply(&routes)
+        .await;
+    assert_eq!(resp.status(), 404);
+}
+
 #[tokio::test]
 async fn bad_request_trumps_method_not_allowed() {
     let _ = pretty_env_logger::try_init();
@@ -0,0 +1,104 @@
+#![deny(warnings)]
+use std::sync::{Arc, Mutex};
+
+use warp::http::{Method, StatusCode};
+use warp::metrics::{Info, Prometheus, Recorder};
+use warp::Filter;
+
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<String>>>);
+
+impl Recorder for Events {
+    fn started(&self, route: &'static str, method: &Method) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("start {} {}", route, method));
+    }
+
+    fn finished(&self, info: &Info<'_>) {
+        self.0.lock().unwrap().push(format!(
+            "finish {} {:?} rejected={} size={:?}",
+            info.route(),
+            info.status().map(|s| s.as_u16()),
+            info.is_rejection(),
+            info.response_size(),
+        ));
+    }
+}
+
+#[tokio::test]
+async fn records_reply_and_rejection() {
+    let events = Events::default();
+    let route = warp::path("hello")
+        .map(|| "world")
+        .with(warp::metrics::route("hello", events.clone()));
+
+    let res = warp::test::request().path("/hello").reply(&route).await;
+    assert_eq!(res.status(), 200);
+
+    let res = warp::test::request().path("/nope").reply(&route).await;
+    assert_eq!(res.status(), 404);
+
+    assert_eq!(
+        *events.0.lock().unwrap(),
+        vec![
+            "start hello GET",
+            "finish hello Some(200) rejected=false size=Some(5)",
+            "start hello GET",
+            "finish hello Some(404) rejected=true size=None",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn prometheus_exposition() {
+    let prometheus = Prometheus::new();
+    let hello = warp::path("hello")
+        .and(warp::body::bytes())
+        .map(|_| "world")
+        .with(warp::metrics::route("hello", prometheus.clone()));
+    let teapot = warp::path("teapot")
+        .map(|| warp::reply::with_status("short and stout", StatusCode::IM_A_TEAPOT))
+        .with(warp::metrics::route("teapot", prometheus.clone()));
+    let routes = hello.or(teapot);
+
+    warp::test::request()
+        .method("POST")
+        .path("/hello")
+        .body("hi")
+        .reply(&routes)
+        .await;
+    warp::test::request().path("/teapot").reply(&routes).await;
+    warp::test::request()
+        .method("BREW")
+        .path("/teapot")
+        .reply(&routes)
+        .await;
+
+    let res = warp::test::request()
+        .reply(&warp::any().map(move || prometheus.clone()))
+        .await;
+    assert_eq!(
+        res.headers()["content-type"],
+        "text/plain; version=0.0.4; charset=utf-8"
+    );
+
+    let body = std::str::from_utf8(res.body()).unwrap();
+    assert!(body.contains("# TYPE http_requests_total counter"));
+    assert!(body.contains("http_requests_total{route=\"hello\",method=\"POST\",status=\"200\"} 1"));
+    assert!(body.contains("http_requests_total{route=\"teapot\",method=\"GET\",status=\"418\"} 1"));
+    assert!(
+        body.contains("http_requests_total{route=\"teapot\",method=\"other\",status=\"418\"} 1")
+    );
+    assert!(!body.contains("BREW"));
+    assert!(body.contains("http_requests_in_flight{route=\"hello\"} 0"));
+    assert!(body.contains(
+        "http_request_size_bytes_bucket{route=\"hello\",method=\"POST\",status=\"200\",le=\"64\"} 1"
+    ));
+    assert!(body.contains(
+        "http_response_size_bytes_sum{route=\"teapot\",method=\"GET\",status=\"418\"} 15"
+    ));
+    // The `/teapot` request passing by the `hello` route isn't counted there.
+    assert!(!body.contains("status=\"404\""));
+}
@@ -0,0 +1,177 @@
+#![deny(warnings)]
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use warp::{Filter, LocalAddr};
+
+async fn get<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S) -> String {
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    response
+}
+
+fn tcp_addr(addr: &LocalAddr) -> SocketAddr {
+    match addr {
+        LocalAddr::Tcp(addr) => *addr,
+        addr => panic!("unexpected address {}", addr),
+    }
+}
+
+#[tokio::test]
+async fn shared_shutdown() {
+    let _ = pretty_env_logger::try_init();
+
+    let routes = warp::any().map(|| "multi");
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let (addrs, server) = warp::serve(routes)
+        .multi()
+        .bind(([127, 0, 0, 1], 0))
+        .bind_listener(listener)
+        .bind_with_graceful_shutdown(async {
+            rx.await.ok();
+        });
+    assert_eq!(addrs.len(), 2);
+    assert_eq!(
+        addrs[0].to_string(),
+        format!("http://{}", tcp_addr(&addrs[0]))
+    );
+    let server = tokio::spawn(server);
+
+    for addr in &addrs {
+        let addr = tcp_addr(addr);
+        let response = get(TcpStream::connect(addr).await.unwrap()).await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("multi"), "{}", response);
+    }
+
+    tx.send(()).unwrap();
+    server.await.unwrap();
+    for addr in &addrs {
+        assert!(TcpStream::connect(tcp_addr(addr)).await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn bind_error() {
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let taken = taken.local_addr().unwrap();
+
+    let result = warp::serve(warp::any().map(warp::reply))
+        .multi()
+        .bind(([127, 0, 0, 1], 0))
+        .bind(taken)
+        .try_bind_all();
+    let err = result.err().unwrap();
+    assert!(err.to_string().contains(&taken.to_string()), "{}", err);
+
+    let result = warp::serve(warp::any().map(warp::reply))
+        .multi()
+        .try_bind_all();
+    assert!(result.is_err());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn unix() {
+    let dir = std::env::temp_dir().join(format!("warp-multi-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("admin.sock");
+
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+    let (addrs, server) = warp::serve(warp::any().map(|| "multi"))
+        .multi()
+        .bind(([127, 0, 0, 1], 0))
+        .bind_unix(path.as_path())
+        .bind_with_graceful_shutdown(async {
+            rx.await.ok();
+        });
+    assert_eq!(addrs[1], LocalAddr::Unix(Some(path.clone())));
+    let server = tokio::spawn(server);
+
+    let response = get(TcpStream::connect(tcp_addr(&addrs[0])).await.unwrap()).await;
+    assert!(response.ends_with("multi"), "{}", response);
+    let response = get(tokio::net::UnixStream::connect(&path).await.unwrap()).await;
+    assert!(response.ends_with("multi"), "{}", response);
+
+    tx.send(()).unwrap();
+    server.await.unwrap();
+    assert!(!path.exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn tls() {
+    use std::sync::Arc;
+    use tokio_rustls::rustls::internal::pemfile;
+    use tokio_rustls::rustls::{ClientConfig, Session};
+    use tokio_rustls::webpki::DNSNameRef;
+    use tokio_rustls::TlsConnector;
+
+    let routes = warp::tls::info().map(
+        |info: Option<warp::tls::TlsInfo>| {
+            if info.is_some() {
+                "https"
+            } else {
+                "http"
+            }
+        },
+    );
+    let (addrs, server) = warp::serve(routes)
+        .multi()
+        .bind(([127, 0, 0, 1], 0))
+        .bind_tls(([127, 0, 0, 1], 0), |tls| {
+            tls.cert_path("tests/tls/server.pem")
+                .key_path("tests/tls/server.key")
+        })
+        .bind_tls(([127, 0, 0, 1], 0), |tls| {
+            tls.cert_path("tests/tls/alpha.pem")
+                .key_path("tests/tls/server.key")
+        })
+        .bind_all();
+    tokio::spawn(server);
+
+    let response = get(TcpStream::connect(tcp_addr(&addrs[0])).await.unwrap()).await;
+    assert!(response.ends_with("http"), "{}", response);
+
+    // Each listener has its own certificate.
+    for (addr, name, pem) in [
+        (
+            &addrs[1],
+            "localhost",
+            &include_bytes!("tls/server.pem")[..],
+        ),
+        (
+            &addrs[2],
+            "alpha.test",
+            &include_bytes!("tls/alpha.pem")[..],
+        ),
+    ] {
+        let addr = match addr {
+            LocalAddr::Tls(addr) => *addr,
+            addr => panic!("unexpected address {}", addr),
+        };
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_pem_file(&mut &include_bytes!("tls/ca.pem")[..])
+            .unwrap();
+        config.set_protocols(&[b"http/1.1".to_vec()]);
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let name = DNSNameRef::try_from_ascii_str(name).unwrap();
+        let stream = TlsConnector::from(Arc::new(config))
+            .connect(name, tcp)
+            .await
+            .unwrap();
+        let certs = stream.get_ref().1.get_peer_certificates().unwrap();
+        assert_eq!(certs[0].0, pemfile::certs(&mut &pem[..]).unwrap()[0].0);
+        let response = get(stream).await;
+        assert!(response.ends_with("https"), "{}", response);
+    }
+}
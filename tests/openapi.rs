@@ -0,0 +1,129 @@
+#![deny(warnings)]
+use std::collections::HashMap;
+
+use serde_derive::Deserialize;
+use serde_json::json;
+use warp::openapi::Document;
+use warp::Filter;
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct Page {
+    offset: u32,
+    limit: u32,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct NewUser {
+    name: String,
+    email: String,
+}
+
+#[test]
+fn document_from_filters() {
+    let list = warp::get()
+        .and(warp::path!("users"))
+        .and(warp::query::<Page>())
+        .map(|_: Page| "users")
+        .named("list_users");
+    let create = warp::post()
+        .and(warp::path!("users"))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json::<NewUser>())
+        .map(|_: String, _: NewUser| "created");
+    let get = warp::get()
+        .and(warp::path!("users" / u32 / String))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .map(|_: u32, _: String, _: Option<String>| "user");
+    let search = warp::get()
+        .and(warp::path!("search"))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|_| "results");
+    // Routes without a method can't be documented.
+    let any = warp::path!("any").map(|| "any");
+    let files = warp::get()
+        .and(warp::path("files"))
+        .and(warp::path::param::<String>())
+        .map(|_| "file");
+
+    let routes = list.or(create).or(get).or(search).or(any).or(files);
+    let doc = Document::new("Users", "1.0").routes(&routes).to_json();
+
+    assert_eq!(doc["openapi"], "3.0.3");
+    assert_eq!(doc["info"], json!({ "title": "Users", "version": "1.0" }));
+
+    let paths = doc["paths"].as_object().unwrap();
+    assert_eq!(paths.len(), 4);
+
+    let list = &doc["paths"]["/users"]["get"];
+    assert_eq!(list["operationId"], "list_users");
+    let names = list["parameters"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| (p["name"].as_str().unwrap(), p["in"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(names, [("offset", "query"), ("limit", "query")]);
+
+    let create = &doc["paths"]["/users"]["post"];
+    assert_eq!(
+        create["parameters"],
+        json!([{
+            "name": "authorization",
+            "in": "header",
+            "required": true,
+            "schema": { "type": "string" },
+        }])
+    );
+    assert_eq!(
+        create["requestBody"]["content"]["application/json"]["schema"],
+        json!({
+            "type": "object",
+            "properties": { "name": {}, "email": {} },
+        })
+    );
+
+    let get = &doc["paths"]["/users/{param1}/{param2}"]["get"];
+    assert_eq!(
+        get["parameters"][0],
+        json!({
+            "name": "param1",
+            "in": "path",
+            "required": true,
+            "schema": { "type": "integer", "minimum": 0 },
+        })
+    );
+    assert_eq!(get["parameters"][1]["schema"], json!({ "type": "string" }));
+    assert_eq!(get["parameters"][2]["name"], "if-none-match");
+    assert_eq!(get["parameters"][2]["required"], false);
+
+    let search = &doc["paths"]["/search"]["get"];
+    assert_eq!(search["parameters"][0]["schema"]["type"], "object");
+    assert!(search.get("x-warp-prefix").is_none());
+
+    // Also matches longer paths, such as `/files/a/b`.
+    let files = &doc["paths"]["/files/{param1}"]["get"];
+    assert_eq!(files["x-warp-prefix"], true);
+}
+
+#[tokio::test]
+async fn serve() {
+    let hello = warp::get().and(warp::path!("hello")).map(|| "hello");
+    let doc = Document::new("Hello", "0.1").routes(&hello);
+    let route = warp::path!("openapi.json")
+        .and(warp::openapi::serve(doc))
+        .or(hello);
+
+    let res = warp::test::request()
+        .path("/openapi.json")
+        .reply(&route)
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers()["content-type"], "application/json");
+
+    let doc: serde_json::Value = serde_json::from_slice(res.body()).unwrap();
+    assert!(doc["paths"]["/hello"]["get"].is_object());
+    // Only the routes given to the document are listed.
+    assert!(doc["paths"]["/openapi.json"].is_null());
+}
@@ -0,0 +1,270 @@
+#![deny(warnings)]
+use serde_json::{json, Value};
+use warp::http::StatusCode;
+use warp::problem::{Details, Problem};
+use warp::Filter;
+
+#[derive(Debug)]
+struct OutOfCredit;
+
+impl warp::reject::Reject for OutOfCredit {}
+
+impl Problem for OutOfCredit {
+    fn details(&self) -> Details {
+        Details::new(StatusCode::FORBIDDEN)
+            .with_type("https://example.com/probs/out-of-credit")
+            .with_title("You do not have enough credit.")
+            .with_detail("Your balance is 30, but that costs 50.")
+            .with_extension("balance", 30)
+    }
+}
+
+#[derive(Debug)]
+struct Unregistered;
+
+impl warp::reject::Reject for Unregistered {}
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {
+    fn status(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+fn routes() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let buy = warp::post()
+        .and(warp::path("buy"))
+        .and_then(|| async { Err::<String, _>(warp::reject::custom(OutOfCredit)) });
+    let oops = warp::path("oops")
+        .and_then(|| async { Err::<String, _>(warp::reject::custom(Unregistered)) });
+    let count = warp::get()
+        .and(warp::path("count"))
+        .and(warp::header::<u32>("x-count"))
+        .map(|n: u32| n.to_string());
+    buy.or(oops).unify().or(count).unify()
+}
+
+fn body(res: &warp::http::Response<warp::hyper::body::Bytes>) -> Value {
+    assert_eq!(res.headers()["content-type"], "application/problem+json");
+    serde_json::from_slice(res.body()).unwrap()
+}
+
+#[tokio::test]
+async fn known_rejections() {
+    let _ = pretty_env_logger::try_init();
+    let route = routes().with(warp::problem::json());
+
+    let res = warp::test::request()
+        .path("/count")
+        .header("x-count", "many")
+        .reply(&route)
+        .await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        body(&res),
+        json!({
+            "type": "about:blank",
+            "title": "Bad Request",
+            "status": 400,
+            "detail": "Invalid request header \"x-count\"",
+            "header": "x-count",
+            "expected": "u32",
+        })
+    );
+
+    let res = warp::test::request()
+        .method("DELETE")
+        .path("/count")
+        .filter(&route)
+        .await;
+    assert!(res.is_err(), "method not allowed is passed on");
+}
+
+#[tokio::test]
+async fn unmatched_pass_through() {
+    let hello = warp::path("hello")
+        .map(warp::reply)
+        .with(warp::problem::json());
+    let route = hello.clone().or(warp::path("bye").map(|| "bye"));
+
+    let res = warp::test::request().path("/bye").reply(&route).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), "bye");
+
+    let res = warp::test::request().path("/nope").filter(&hello).await;
+    assert!(matches!(res, Err(ref e) if e.is_not_found()));
+}
+
+#[tokio::test]
+async fn server_renders_unmatched() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let hello = warp::path("hello").and(warp::get()).map(warp::reply);
+    let (addr, server) = warp::serve(hello)
+        .problems(warp::problem::json())
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let request = |req: &'static str| async move {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(req.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    };
+
+    let res = request("GET /nope HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n").await;
+    assert!(res.starts_with("HTTP/1.1 404 Not Found"), "{}", res);
+    assert!(
+        res.contains("content-type: application/problem+json"),
+        "{}",
+        res
+    );
+    assert!(res.contains(r#""title":"Not Found""#), "{}", res);
+
+    let res =
+        request("DELETE /hello HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n").await;
+    assert!(
+        res.starts_with("HTTP/1.1 405 Method Not Allowed"),
+        "{}",
+        res
+    );
+    assert!(res.contains("allow: GET\r\n"), "{}", res);
+    assert!(res.contains(r#""title":"Method Not Allowed""#), "{}", res);
+}
+
+#[tokio::test]
+async fn custom_rejections() {
+    let route = routes().with(warp::problem::json().custom::<OutOfCredit>());
+
+    let res = warp::test::request()
+        .method("POST")
+        .path("/buy")
+        .reply(&route)
+        .await;
+    assert_eq!(res.status(), 403);
+    assert_eq!(
+        body(&res),
+        json!({
+            "type": "https://example.com/probs/out-of-credit",
+            "title": "You do not have enough credit.",
+            "status": 403,
+            "detail": "Your balance is 30, but that costs 50.",
+            "balance": 30,
+        })
+    );
+
+    // Unregistered rejections don't leak their debug output.
+    let res = warp::test::request().path("/oops").reply(&route).await;
+    assert_eq!(res.status(), 500);
+    assert_eq!(
+        body(&res),
+        json!({ "type": "about:blank", "title": "Internal Server Error", "status": 500 })
+    );
+
+    // Or with their own status, if they have one.
+    let route = warp::any()
+        .and_then(|| async { Err::<String, _>(warp::reject::custom(Unauthorized)) })
+        .with(warp::problem::json());
+    let res = warp::test::request().reply(&route).await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(body(&res)["title"], "Unauthorized");
+}
+
+#[tokio::test]
+async fn include_values() {
+    let route = routes().with(warp::problem::json().include_values(true));
+
+    let res = warp::test::request()
+        .path("/count")
+        .header("x-count", "many")
+        .reply(&route)
+        .await;
+    assert_eq!(body(&res)["value"], "many");
+}
+
+#[tokio::test]
+async fn values_not_leaked() {
+    let route = warp::body::json::<Vec<u32>>()
+        .map(|_| warp::reply())
+        .with(warp::problem::json());
+
+    // Serde's message has the value in it.
+    let res = warp::test::request()
+        .body(r#"[1, "hunter2"]"#)
+        .reply(&route)
+        .await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(
+        body(&res),
+        json!({
+            "type": "about:blank",
+            "title": "Bad Request",
+            "status": 400,
+            "detail": "Request body deserialize error",
+            "field": "[1]",
+            "line": 1,
+            "column": 13,
+        })
+    );
+
+    let route = warp::header::exact("x-api-key", "hunter2")
+        .map(warp::reply)
+        .with(warp::problem::json());
+    let res = warp::test::request()
+        .header("x-api-key", "guess")
+        .reply(&route)
+        .await;
+    assert!(!String::from_utf8_lossy(res.body()).contains("hunter2"));
+    assert_eq!(body(&res)["expected"], Value::Null);
+}
+
+#[tokio::test]
+async fn negotiates_with_accept() {
+    let route = routes().with(warp::problem::json());
+
+    let res = warp::test::request()
+        .path("/count")
+        .header("accept", "text/plain")
+        .header("x-count", "many")
+        .reply(&route)
+        .await;
+    assert_eq!(res.status(), 400);
+    assert_eq!(res.headers()["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(res.body(), "Invalid request header \"x-count\"");
+
+    let res = warp::test::request()
+        .path("/count")
+        .header("accept", "text/plain;q=0.5, application/json")
+        .header("x-count", "many")
+        .reply(&route)
+        .await;
+    assert_eq!(body(&res)["status"], 400);
+}
+
+#[tokio::test]
+async fn successes_pass_through() {
+    let route = routes().with(warp::problem::json());
+
+    let res = warp::test::request()
+        .path("/count")
+        .header("x-count", "3")
+        .reply(&route)
+        .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.body(), "3");
+}
+
+#[tokio::test]
+async fn details_reply() {
+    let route = warp::any().map(|| Details::new(StatusCode::CONFLICT).with_detail("taken"));
+
+    let res = warp::test::request().reply(&route).await;
+    assert_eq!(res.status(), 409);
+    assert_eq!(
+        body(&res),
+        json!({ "type": "about:blank", "title": "Conflict", "status": 409, "detail": "taken" })
+    );
+}
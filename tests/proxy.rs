@@ -0,0 +1,164 @@
+#![deny(warnings)]
+use std::net::SocketAddr;
+
+use warp::host::Authority;
+use warp::Filter;
+
+fn routes(
+    trusted: warp::proxy::Trusted,
+) -> impl Filter<Extract = (String,), Error = warp::Rejection> + Clone {
+    warp::addr::remote()
+        .and(warp::host::optional())
+        .map(|addr: Option<SocketAddr>, host: Option<Authority>| {
+            format!(
+                "{} {}",
+                addr.map(|a| a.to_string()).unwrap_or_default(),
+                host.map(|h| h.to_string()).unwrap_or_default(),
+            )
+        })
+        .with(trusted)
+}
+
+fn trusted() -> warp::proxy::Trusted {
+    warp::proxy::trusted(["10.0.0.0/8", "::1"])
+}
+
+#[tokio::test]
+async fn untrusted_peer() {
+    let _ = pretty_env_logger::try_init();
+
+    let req = warp::test::request()
+        .remote_addr("192.0.2.1:1234".parse().unwrap())
+        .header("host", "internal")
+        .header("forwarded", "for=203.0.113.7;host=example.com");
+    assert_eq!(
+        req.filter(&routes(trusted())).await.unwrap(),
+        "192.0.2.1:1234 internal"
+    );
+}
+
+#[tokio::test]
+async fn forwarded() {
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("host", "internal")
+        .header(
+            "forwarded",
+            r#"for=203.0.113.7;proto=https;host=example.com, for="10.0.0.9:80";host=lb"#,
+        );
+    assert_eq!(
+        req.filter(&routes(trusted())).await.unwrap(),
+        "203.0.113.7:0 example.com"
+    );
+
+    // An untrusted hop in the chain is the client, even if it claims more.
+    let req = warp::test::request()
+        .remote_addr("[::1]:1234".parse().unwrap())
+        .header("forwarded", "for=203.0.113.7, for=\"[2001:db8::1]:4711\"");
+    assert_eq!(
+        req.filter(&routes(trusted())).await.unwrap(),
+        "[2001:db8::1]:4711 "
+    );
+
+    // Hops that don't say where they came from end the walk.
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("forwarded", "for=203.0.113.7, for=unknown;host=example.com");
+    assert_eq!(
+        req.filter(&routes(trusted())).await.unwrap(),
+        "10.0.0.2:1234 example.com"
+    );
+}
+
+#[tokio::test]
+async fn x_forwarded() {
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("host", "internal")
+        .header("x-forwarded-for", "198.51.100.1, 203.0.113.7, 10.1.1.1")
+        .header("x-forwarded-host", "example.com");
+    assert_eq!(
+        req.filter(&routes(trusted().x_forwarded())).await.unwrap(),
+        "203.0.113.7:0 example.com"
+    );
+
+    // All trusted hops leaves the leftmost one.
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("x-forwarded-for", "10.3.3.3, 10.1.1.1");
+    assert_eq!(
+        req.filter(&routes(trusted().x_forwarded())).await.unwrap(),
+        "10.3.3.3:0 "
+    );
+}
+
+#[tokio::test]
+async fn other_headers_ignored() {
+    // A proxy setting one kind passes along what clients sent of the other.
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("x-forwarded-for", "198.51.100.1")
+        .header("x-forwarded-host", "spoofed")
+        .header("forwarded", "for=203.0.113.7");
+    assert_eq!(
+        req.filter(&routes(trusted())).await.unwrap(),
+        "203.0.113.7:0 "
+    );
+
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("host", "internal")
+        .header("forwarded", "for=198.51.100.1;host=spoofed");
+    assert_eq!(
+        req.filter(&routes(trusted().x_forwarded())).await.unwrap(),
+        "10.0.0.2:1234 internal"
+    );
+
+    let req = warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("forwarded", "for=198.51.100.1")
+        .header("x-forwarded-for", "203.0.113.7");
+    assert_eq!(
+        req.filter(&routes(trusted().x_forwarded())).await.unwrap(),
+        "203.0.113.7:0 "
+    );
+}
+
+#[tokio::test]
+async fn scheme_in_log_info() {
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(None));
+    let log = {
+        let seen = seen.clone();
+        warp::log::custom(move |info| {
+            *seen.lock().unwrap() = Some((
+                info.scheme().map(str::to_owned),
+                info.host().map(str::to_owned),
+                info.remote_addr(),
+            ));
+        })
+    };
+    let route = warp::any()
+        .map(warp::reply)
+        .with(log)
+        .with(warp::proxy::trusted(["10.0.0.0/8"]).x_forwarded());
+
+    warp::test::request()
+        .remote_addr("10.0.0.2:1234".parse().unwrap())
+        .header("x-forwarded-for", "203.0.113.7")
+        .header("x-forwarded-proto", "https")
+        .header("x-forwarded-host", "example.com")
+        .reply(&route)
+        .await;
+
+    let seen = seen.lock().unwrap().take().unwrap();
+    assert_eq!(
+        seen,
+        (
+            Some("https".to_owned()),
+            Some("example.com".to_owned()),
+            Some("203.0.113.7:0".parse().unwrap()),
+        )
+    );
+}
@@ -0,0 +1,134 @@
+#![deny(warnings)]
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use warp::{Filter, ProxyProtocol};
+
+fn serve(config: ProxyProtocol) -> SocketAddr {
+    let routes = warp::addr::remote().map(|addr: Option<SocketAddr>| addr.unwrap().to_string());
+    let (addr, server) = warp::serve(routes)
+        .proxy_protocol(config)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+async fn send(addr: SocketAddr, header: &[u8]) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(header).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: example.com\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    response
+}
+
+#[tokio::test]
+async fn v1() {
+    let _ = pretty_env_logger::try_init();
+    let addr = serve(ProxyProtocol::new());
+
+    let res = send(addr, b"PROXY TCP4 192.0.2.1 127.0.0.1 56324 80\r\n").await;
+    assert!(res.starts_with("HTTP/1.1 200 OK"), "{}", res);
+    assert!(res.ends_with("192.0.2.1:56324"), "{}", res);
+
+    // Health checks from the upstream itself.
+    let res = send(addr, b"PROXY UNKNOWN\r\n").await;
+    assert!(res.contains("\r\n\r\n127.0.0.1:"), "{}", res);
+}
+
+#[tokio::test]
+async fn v2() {
+    let addr = serve(ProxyProtocol::new());
+
+    let mut header = b"\r\n\r\n\0\r\nQUIT\n".to_vec();
+    header.extend_from_slice(&[0x21, 0x21, 0, 36]);
+    header.extend_from_slice(
+        &"2001:db8::1"
+            .parse::<std::net::Ipv6Addr>()
+            .unwrap()
+            .octets(),
+    );
+    header.extend_from_slice(&[0; 16]);
+    header.extend_from_slice(&4711u16.to_be_bytes());
+    header.extend_from_slice(&80u16.to_be_bytes());
+
+    let res = send(addr, &header).await;
+    assert!(res.ends_with("[2001:db8::1]:4711"), "{}", res);
+}
+
+#[tokio::test]
+async fn missing_header() {
+    let addr = serve(ProxyProtocol::new());
+
+    let res = send(addr, b"").await;
+    assert_eq!(res, "");
+}
+
+#[tokio::test]
+async fn timeout() {
+    let addr = serve(ProxyProtocol::new().timeout(Duration::from_millis(50)));
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(b"PROXY TCP4").await.unwrap();
+    let mut response = Vec::new();
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response));
+    assert_eq!(read.await.expect("connection closed").unwrap_or(0), 0);
+}
+
+#[tokio::test]
+async fn allowlist() {
+    let addr = serve(ProxyProtocol::new().allow(["10.0.0.0/8"]));
+
+    let res = send(addr, b"PROXY TCP4 192.0.2.1 127.0.0.1 56324 80\r\n").await;
+    assert_eq!(res, "");
+
+    let addr = serve(ProxyProtocol::new().allow(["127.0.0.0/8"]));
+    let res = send(addr, b"PROXY TCP4 192.0.2.1 127.0.0.1 56324 80\r\n").await;
+    assert!(res.ends_with("192.0.2.1:56324"), "{}", res);
+}
+
+#[tokio::test]
+async fn bounded_handshakes() {
+    let routes = warp::any().map(warp::reply);
+    let (addr, server) = warp::serve(routes)
+        .proxy_protocol(ProxyProtocol::new().timeout(Duration::from_secs(30)))
+        .max_connections(1)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    // Upstreams that don't send their header yet take up every slot.
+    let mut slow = Vec::new();
+    for _ in 0..64 {
+        slow.push(TcpStream::connect(addr).await.unwrap());
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // So the next connection waits in the backlog, with its header sent.
+    let waiting = tokio::spawn(send(addr, b"PROXY TCP4 192.0.2.1 127.0.0.1 56324 80\r\n"));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!waiting.is_finished());
+
+    // A slot frees once a header is read, and closing that connection frees
+    // the limit too.
+    let mut first = slow.remove(0);
+    first
+        .write_all(
+            b"PROXY UNKNOWN\r\nGET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = first.read_to_string(&mut response).await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+
+    let response = tokio::time::timeout(Duration::from_secs(5), waiting)
+        .await
+        .expect("accepted once a slot frees")
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+}
@@ -137,3 +137,134 @@ async fn raw_query() {
     let extracted = req.filter(&as_raw).await.unwrap();
     assert_eq!(extracted, "foo=bar&baz=quux".to_owned());
 }
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Search {
+    tag: Vec<String>,
+    filter: SearchFilter,
+    page: Option<u32>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct SearchFilter {
+    status: String,
+    ids: Vec<u32>,
+}
+
+#[tokio::test]
+async fn structured_query() {
+    let as_struct = warp::query::structured::<Search>();
+
+    let req = warp::test::request()
+        .path("/?tag=a&tag=b%20c&filter[status]=open&filter[ids][1]=7&filter[ids][0]=3");
+
+    let extracted = req.filter(&as_struct).await.unwrap();
+    assert_eq!(
+        extracted,
+        Search {
+            tag: vec!["a".into(), "b c".into()],
+            filter: SearchFilter {
+                status: "open".into(),
+                ids: vec![3, 7],
+            },
+            page: None,
+        }
+    );
+
+    let req = warp::test::request().path("/?tag[]=a&filter[status]=x&filter[ids][]=1&page=2");
+    let extracted = req.filter(&as_struct).await.unwrap();
+    assert_eq!(extracted.tag, ["a"]);
+    assert_eq!(extracted.filter.ids, [1]);
+    assert_eq!(extracted.page, Some(2));
+}
+
+#[tokio::test]
+async fn structured_query_errors() {
+    let as_struct = warp::query::structured::<Search>().map(|_| warp::reply());
+
+    let res = warp::test::request()
+        .path("/?tag=a&filter[status]=open&filter[ids]=1&filter[ids]=x")
+        .reply(&as_struct)
+        .await;
+    assert_eq!(res.status(), 400);
+    // The client's value isn't echoed back.
+    assert_eq!(res.body(), "Invalid query string");
+
+    let rej = warp::test::request()
+        .path("/?tag=a&filter[status]=open&filter[ids]=x")
+        .filter(&warp::query::structured::<Search>())
+        .await
+        .unwrap_err();
+    let err = rej
+        .find::<warp::reject::InvalidQuery>()
+        .and_then(|e| e.error())
+        .unwrap();
+    assert_eq!(err.field(), Some("filter[ids][0]"));
+    assert_eq!(err.expected(), Some("u32"));
+    assert_eq!(err.value(), Some("x"));
+
+    let err = warp::test::request()
+        .path("/?tag=a&filter[ids]=1")
+        .filter(&warp::query::structured::<Search>())
+        .await
+        .unwrap_err();
+    let err = err
+        .find::<warp::reject::InvalidQuery>()
+        .and_then(|e| e.error())
+        .unwrap();
+    assert_eq!(err.field(), Some("filter"));
+    assert_eq!(err.message(), "missing field `status`");
+
+    let err = warp::query::from_str::<Search>("tag=a&filter=open").unwrap_err();
+    assert_eq!(err.field(), Some("filter"));
+}
+
+#[test]
+fn structured_query_limits() {
+    use std::collections::HashMap;
+
+    // Deep nesting is refused, rather than recursed into.
+    let deep = format!("a{}=1", "[x]".repeat(5000));
+    let err = warp::query::from_str::<HashMap<String, String>>(&deep).unwrap_err();
+    assert_eq!(err.field(), Some("a"));
+    assert_eq!(err.message(), "is nested more than 32 levels deep");
+    let ok = format!("a{}=1", "[x]".repeat(31));
+    assert!(warp::query::from_str::<serde_json::Value>(&ok).is_ok());
+
+    let many = (0..1001).map(|i| format!("k{}=1", i)).collect::<Vec<_>>();
+    let err = warp::query::from_str::<HashMap<String, String>>(&many.join("&")).unwrap_err();
+    assert_eq!(err.message(), "has more than 1000 parameters");
+
+    let map = warp::query::from_str::<HashMap<String, String>>(&many[..1000].join("&")).unwrap();
+    assert_eq!(map.len(), 1000);
+}
+
+#[tokio::test]
+async fn query_error_details() {
+    let as_struct = warp::query::<MyRequiredArgs>();
+
+    let rej = warp::test::request()
+        .path("/?foo=bar")
+        .filter(&as_struct)
+        .await
+        .unwrap_err();
+    let err = rej
+        .find::<warp::reject::InvalidQuery>()
+        .and_then(|e| e.error())
+        .unwrap();
+    assert_eq!(err.field(), None);
+    assert_eq!(err.message(), "missing field `baz`");
+
+    let as_map = warp::query::<HashMap<String, u32>>();
+    let rej = warp::test::request()
+        .path("/?a=1&b=x")
+        .filter(&as_map)
+        .await
+        .unwrap_err();
+    let err = rej
+        .find::<warp::reject::InvalidQuery>()
+        .and_then(|e| e.error())
+        .unwrap();
+    assert_eq!(err.field(), Some("b"));
+    assert_eq!(err.expected(), None);
+}
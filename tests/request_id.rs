@@ -0,0 +1,73 @@
+#![deny(warnings)]
+use warp::request_id::RequestId;
+use warp::Filter;
+
+#[tokio::test]
+async fn generates_when_missing() {
+    let route = warp::ext::get::<RequestId>()
+        .map(|id: RequestId| id.to_string())
+        .with(warp::request_id());
+
+    let res = warp::test::request().reply(&route).await;
+    assert_eq!(res.status(), 200);
+
+    let echoed = res.headers()["x-request-id"].to_str().unwrap();
+    assert_eq!(echoed, res.body());
+
+    // A version 4 UUID...
+    assert_eq!(echoed.len(), 36);
+    assert_eq!(&echoed[14..15], "4");
+
+    // ...that is different every time.
+    let res2 = warp::test::request().reply(&route).await;
+    assert_ne!(res2.headers()["x-request-id"], echoed);
+}
+
+#[tokio::test]
+async fn propagates_from_request() {
+    let route = warp::ext::get::<RequestId>()
+        .map(|id: RequestId| id.to_string())
+        .with(warp::request_id());
+
+    let res = warp::test::request()
+        .header("x-request-id", "abc-123")
+        .reply(&route)
+        .await;
+    assert_eq!(res.body(), "abc-123");
+    assert_eq!(res.headers()["x-request-id"], "abc-123");
+}
+
+#[tokio::test]
+async fn custom_header() {
+    let route = warp::any()
+        .map(warp::reply)
+        .with(warp::request_id::header("x-correlation-id"));
+
+    let res = warp::test::request()
+        .header("x-correlation-id", "foo")
+        .header("x-request-id", "bar")
+        .reply(&route)
+        .await;
+    assert_eq!(res.headers()["x-correlation-id"], "foo");
+    assert!(res.headers().get("x-request-id").is_none());
+}
+
+#[tokio::test]
+async fn visible_to_log() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let seen2 = seen.clone();
+    let log = warp::log::custom(move |info| {
+        *seen2.lock().unwrap() = info.request_id().map(String::from);
+    });
+
+    let route = warp::any()
+        .map(warp::reply)
+        .with(log)
+        .with(warp::request_id());
+
+    warp::test::request()
+        .header("x-request-id", "logged")
+        .reply(&route)
+        .await;
+    assert_eq!(seen.lock().unwrap().as_deref(), Some("logged"));
+}
@@ -0,0 +1,68 @@
+#![deny(warnings)]
+use warp::http::Method;
+use warp::routes::Segment;
+use warp::Filter;
+
+#[test]
+fn path_and_method() {
+    let get_user = warp::get()
+        .and(warp::path!("users" / u32))
+        .map(|id: u32| id.to_string())
+        .named("get_user");
+    let files = warp::path("static")
+        .and(warp::path::tail())
+        .map(|_| String::new());
+    let prefix = warp::path!("api" / ..).map(String::new);
+
+    let routes = get_user.or(files).unify().or(prefix).unify().routes();
+    assert_eq!(routes.len(), 3);
+
+    let user = routes.get("get_user").expect("named route");
+    assert_eq!(user.method(), Some(&Method::GET));
+    assert_eq!(
+        user.segments(),
+        &[
+            Segment::Exact("users".to_string()),
+            Segment::Param("u32".to_string())
+        ]
+    );
+    assert!(!user.is_prefix());
+
+    let paths = routes.iter().map(|r| r.path()).collect::<Vec<_>>();
+    assert_eq!(paths, ["/users/{u32}", "/static/*", "/api/.."]);
+}
+
+#[test]
+fn and_or_expand() {
+    let users = warp::path("users").and(
+        warp::get()
+            .and(warp::path::end())
+            .or(warp::post().and(warp::path::end()))
+            .unify(),
+    );
+    let routes = users.map(warp::reply).boxed().routes();
+
+    assert_eq!(routes.to_string(), "GET     /users\nPOST    /users\n");
+    assert_eq!(routes.iter().filter(|r| r.name().is_some()).count(), 0);
+}
+
+#[test]
+fn wrappers_and_custom_filters() {
+    let custom = warp::any().and_then(|| async { Ok::<_, warp::Rejection>("hi") });
+    let routes = warp::path::end()
+        .and(custom)
+        .with(warp::log("routes"))
+        .recover(|_| async { Ok::<_, std::convert::Infallible>("recovered") })
+        .named("index")
+        .routes();
+
+    assert_eq!(routes.to_string(), "*       / (index)\n");
+}
+
+#[tokio::test]
+async fn named_does_not_change_filtering() {
+    let route = warp::path!("hello").map(|| "hi").named("hello");
+
+    assert!(warp::test::request().path("/hello").matches(&route).await);
+    assert!(!warp::test::request().path("/bye").matches(&route).await);
+}
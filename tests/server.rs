@@ -0,0 +1,196 @@
+#![deny(warnings)]
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use warp::shutdown::Shutdown;
+use warp::{Filter, LimitPolicy};
+
+async fn request(addr: SocketAddr, req: &[u8]) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream.write_all(req).await.unwrap();
+    let mut response = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+        .await
+        .expect("connection wasn't closed")
+        .unwrap();
+    String::from_utf8(response).unwrap()
+}
+
+#[tokio::test]
+async fn http1_keepalive() {
+    let _ = pretty_env_logger::try_init();
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .http1_keepalive(false)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    // The connection is closed after the response, though the client didn't
+    // ask for it.
+    let response = request(addr, b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n").await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+}
+
+#[tokio::test]
+async fn http1_header_read_timeout() {
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .http1_header_read_timeout(Duration::from_millis(100))
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    // The headers never end.
+    let response = request(addr, b"GET / HTTP/1.1\r\nhost: localhost\r\n").await;
+    assert!(!response.contains("200 OK"), "{}", response);
+}
+
+#[tokio::test]
+async fn http1_max_buf_size() {
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .http1_max_buf_size(8192)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let req = format!(
+        "GET / HTTP/1.1\r\nhost: localhost\r\nx-big: {}\r\n\r\n",
+        "a".repeat(10_000)
+    );
+    let response = request(addr, req.as_bytes()).await;
+    assert!(
+        response.starts_with("HTTP/1.1 431 Request Header Fields Too Large"),
+        "{}",
+        response
+    );
+}
+
+#[test]
+#[should_panic(expected = "8192")]
+fn http1_max_buf_size_too_small() {
+    let _ = warp::serve(warp::any().map(warp::reply)).http1_max_buf_size(1024);
+}
+
+// Opens a kept-alive connection and makes sure it was accepted.
+async fn open(addr: SocketAddr) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).await.unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK"));
+    stream
+}
+
+// Finds the server's end of a connection, which is in this process too, and
+// reads its `SO_KEEPALIVE` option.
+#[cfg(unix)]
+fn server_keepalive(stream: &TcpStream) -> bool {
+    use std::mem::ManuallyDrop;
+    use std::os::unix::io::{AsRawFd, FromRawFd};
+
+    let client = stream.local_addr().unwrap();
+    let fd = (0..1024)
+        .find(|&fd| {
+            // Borrowed, not closed.
+            let socket = ManuallyDrop::new(unsafe { std::net::TcpStream::from_raw_fd(fd) });
+            socket.peer_addr().ok() == Some(client)
+        })
+        .expect("server end of the connection");
+    assert_ne!(fd, stream.as_raw_fd());
+
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_KEEPALIVE,
+            &mut value as *mut libc::c_int as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    assert_eq!(result, 0, "{}", std::io::Error::last_os_error());
+    value != 0
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn tcp_keepalive() {
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .tcp_keepalive(Duration::from_secs(60))
+        .tcp_sleep_on_accept_errors(false)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let stream = open(addr).await;
+    assert!(server_keepalive(&stream));
+
+    let (addr, server) =
+        warp::serve(warp::any().map(warp::reply)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    let stream = open(addr).await;
+    assert!(!server_keepalive(&stream));
+}
+
+async fn assert_closed(addr: SocketAddr) {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut buf = [0; 1024];
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf))
+        .await
+        .expect("connection wasn't closed");
+    assert!(matches!(read, Ok(0) | Err(_)), "{:?}", read);
+}
+
+#[tokio::test]
+async fn max_connections_pause() {
+    let shutdown = Shutdown::new();
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .shutdown(shutdown.clone())
+        .max_connections(1)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let first = open(addr).await;
+    assert_eq!(shutdown.connections(), 1);
+
+    // Waits in the backlog until the first connection closes.
+    let second = tokio::spawn(request(
+        addr,
+        b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n",
+    ));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!second.is_finished());
+    assert_eq!(shutdown.connections(), 1);
+
+    drop(first);
+    let response = second.await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+}
+
+#[tokio::test]
+async fn max_connections_close() {
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .max_connections(1)
+        .max_connections_policy(LimitPolicy::Close)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let first = open(addr).await;
+    assert_closed(addr).await;
+
+    drop(first);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    open(addr).await;
+}
+
+#[tokio::test]
+async fn max_connections_per_ip() {
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .max_connections_per_ip(2)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let _first = open(addr).await;
+    let _second = open(addr).await;
+    assert_closed(addr).await;
+}
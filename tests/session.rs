@@ -0,0 +1,245 @@
+#![deny(warnings)]
+use std::time::Duration;
+
+use warp::http::StatusCode;
+use warp::session::{MemoryStore, Session, SessionData, SessionStore, StoreFuture};
+use warp::Filter;
+
+fn app(
+    store: impl SessionStore,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let login = warp::path!("login")
+        .and(warp::session::session())
+        .map(|session: Session| {
+            session.regenerate();
+            session.insert("user", "alice").unwrap();
+            "welcome".to_string()
+        });
+    let logout = warp::path!("logout")
+        .and(warp::session::session())
+        .map(|session: Session| {
+            session.destroy();
+            "bye".to_string()
+        });
+    let whoami = warp::path!("whoami")
+        .and(warp::session::session())
+        .map(|session: Session| {
+            session
+                .get::<String>("user")
+                .unwrap_or_else(|| "nobody".to_string())
+        });
+    let reject = warp::path!("reject")
+        .and(warp::session::session())
+        .and_then(|session: Session| async move {
+            session.insert("user", "mallory").unwrap();
+            Err::<String, _>(warp::reject::not_found())
+        });
+
+    login
+        .or(logout)
+        .unify()
+        .or(whoami)
+        .unify()
+        .or(reject)
+        .unify()
+        .with(warp::session::sessions(store).secure(false))
+}
+
+fn session_id(res: &warp::http::Response<warp::hyper::body::Bytes>) -> Option<String> {
+    let cookie = res.headers().get("set-cookie")?.to_str().unwrap();
+    let pair = cookie.split(';').next().unwrap();
+    Some(pair.trim_start_matches("session_id=").to_string())
+}
+
+#[tokio::test]
+async fn login_whoami_logout() {
+    let _ = pretty_env_logger::try_init();
+    let store = MemoryStore::new(Duration::from_secs(60));
+    let app = app(store.clone());
+
+    // Nothing is stored, or sent, until the session changes.
+    let res = warp::test::request().path("/whoami").reply(&app).await;
+    assert_eq!(res.body(), "nobody");
+    assert!(res.headers().get("set-cookie").is_none());
+    assert!(store.is_empty());
+
+    let res = warp::test::request().path("/login").reply(&app).await;
+    let cookie = res.headers()["set-cookie"].to_str().unwrap().to_string();
+    assert!(cookie.contains("HttpOnly"), "{}", cookie);
+    assert!(!cookie.contains("Secure"), "{}", cookie);
+    let id = session_id(&res).unwrap();
+    assert_eq!(id.len(), 64);
+    assert_eq!(store.len(), 1);
+
+    let res = warp::test::request()
+        .path("/whoami")
+        .header("cookie", format!("session_id={}", id))
+        .reply(&app)
+        .await;
+    assert_eq!(res.body(), "alice");
+    assert!(res.headers().get("set-cookie").is_none());
+
+    let res = warp::test::request()
+        .path("/logout")
+        .header("cookie", format!("session_id={}", id))
+        .reply(&app)
+        .await;
+    assert!(res.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .contains("Max-Age=0"));
+    assert!(store.is_empty());
+}
+
+#[tokio::test]
+async fn regenerate_prevents_fixation() {
+    let store = MemoryStore::new(Duration::from_secs(60));
+    let app = app(store.clone());
+
+    let res = warp::test::request().path("/login").reply(&app).await;
+    let first = session_id(&res).unwrap();
+
+    // Logging in again with the existing session moves it to a new ID.
+    let res = warp::test::request()
+        .path("/login")
+        .header("cookie", format!("session_id={}", first))
+        .reply(&app)
+        .await;
+    let second = session_id(&res).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(store.len(), 1);
+    assert!(store.load(&first).await.unwrap().is_none());
+
+    // An ID the store doesn't know isn't adopted.
+    let res = warp::test::request()
+        .path("/login")
+        .header("cookie", "session_id=planted")
+        .reply(&app)
+        .await;
+    assert_ne!(session_id(&res).unwrap(), "planted");
+    assert!(store.load("planted").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn rejections_are_not_saved() {
+    let store = MemoryStore::new(Duration::from_secs(60));
+    let app = app(store.clone());
+
+    let res = warp::test::request().path("/reject").reply(&app).await;
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert!(res.headers().get("set-cookie").is_none());
+    assert!(store.is_empty());
+}
+
+#[tokio::test]
+async fn configure_clones() {
+    let sessions = warp::session::sessions(MemoryStore::new(Duration::from_secs(60)));
+    let admin = sessions.clone().cookie_name("admin_id").secure(false);
+    let login = warp::any()
+        .and(warp::session::session())
+        .map(|session: Session| {
+            session.insert("user", "alice").unwrap();
+            "welcome"
+        });
+
+    let res = warp::test::request()
+        .reply(&login.clone().with(admin))
+        .await;
+    let cookie = res.headers()["set-cookie"].to_str().unwrap();
+    assert!(cookie.starts_with("admin_id="), "{}", cookie);
+    assert!(!cookie.contains("Secure"), "{}", cookie);
+
+    // The original is left as it was.
+    let res = warp::test::request().reply(&login.with(sessions)).await;
+    let cookie = res.headers()["set-cookie"].to_str().unwrap();
+    assert!(cookie.starts_with("session_id="), "{}", cookie);
+    assert!(cookie.contains("Secure"), "{}", cookie);
+}
+
+#[tokio::test]
+async fn memory_store_expires() {
+    tokio::time::pause();
+    let store = MemoryStore::new(Duration::from_millis(10));
+    let mut data = SessionData::new();
+    data.insert("user".into(), "alice".into());
+
+    store.save("a", data).await.unwrap();
+    assert!(store.load("a").await.unwrap().is_some());
+
+    tokio::time::advance(Duration::from_millis(20)).await;
+    assert!(store.load("a").await.unwrap().is_none());
+    assert!(store.is_empty());
+}
+
+#[tokio::test]
+async fn memory_store_slides() {
+    tokio::time::pause();
+    let store = MemoryStore::new(Duration::from_millis(200));
+    store.save("a", SessionData::new()).await.unwrap();
+
+    // Each load keeps the session for another `ttl`.
+    for _ in 0..3 {
+        tokio::time::advance(Duration::from_millis(150)).await;
+        assert!(store.load("a").await.unwrap().is_some());
+    }
+}
+
+#[tokio::test]
+async fn memory_store_sweeps_once_per_ttl() {
+    tokio::time::pause();
+    let store = MemoryStore::new(Duration::from_secs(10));
+    store.save("a", SessionData::new()).await.unwrap();
+
+    tokio::time::advance(Duration::from_secs(9)).await;
+    store.save("b", SessionData::new()).await.unwrap();
+    tokio::time::advance(Duration::from_secs(2)).await;
+    store.save("c", SessionData::new()).await.unwrap();
+    // "a" expired on the way, and the first save after `ttl` removed it.
+    assert_eq!(store.len(), 2);
+
+    // Until the next sweep, "b" is kept around once expired.
+    tokio::time::advance(Duration::from_secs(9)).await;
+    store.save("d", SessionData::new()).await.unwrap();
+    assert_eq!(store.len(), 3);
+    assert!(store.load("b").await.unwrap().is_none());
+    assert_eq!(store.len(), 2);
+}
+
+#[tokio::test]
+async fn memory_store_huge_ttl() {
+    let store = MemoryStore::new(Duration::MAX);
+    store.save("a", SessionData::new()).await.unwrap();
+    assert!(store.load("a").await.unwrap().is_some());
+}
+
+#[derive(Clone)]
+struct Broken;
+
+impl SessionStore for Broken {
+    fn load(&self, _: &str) -> StoreFuture<Option<SessionData>> {
+        Box::pin(async { Err("unavailable".into()) })
+    }
+
+    fn save(&self, _: &str, _: SessionData) -> StoreFuture<()> {
+        Box::pin(async { Err("unavailable".into()) })
+    }
+
+    fn destroy(&self, _: &str) -> StoreFuture<()> {
+        Box::pin(async { Err("unavailable".into()) })
+    }
+}
+
+#[tokio::test]
+async fn store_errors() {
+    let app = app(Broken);
+
+    let res = warp::test::request()
+        .path("/whoami")
+        .header("cookie", "session_id=abc")
+        .reply(&app)
+        .await;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let res = warp::test::request().path("/login").reply(&app).await;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -0,0 +1,253 @@
+#![deny(warnings)]
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use warp::shutdown::{Draining, Shutdown};
+use warp::Filter;
+
+// An event stream that never ends on its own.
+fn endless() -> impl futures::Stream<Item = Result<warp::sse::Event, Infallible>> {
+    futures::stream::once(async { Ok(warp::sse::Event::default().data("hello")) })
+        .chain(futures::stream::pending())
+}
+
+async fn get(addr: SocketAddr, path: &str) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(format!("GET {} HTTP/1.1\r\nhost: localhost\r\n\r\n", path).as_bytes())
+        .await
+        .unwrap();
+    stream
+}
+
+// Reads until the response body has started.
+async fn read_headers(stream: &mut TcpStream) -> String {
+    let mut response = Vec::new();
+    let mut buf = [0; 1024];
+    while !response.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed early");
+        response.extend_from_slice(&buf[..n]);
+    }
+    String::from_utf8(response).unwrap()
+}
+
+async fn wait_for_connections(shutdown: &Shutdown, n: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while shutdown.connections() != n {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{} connections, expected {}", shutdown.connections(), n));
+}
+
+#[tokio::test]
+async fn connections() {
+    let _ = pretty_env_logger::try_init();
+    let shutdown = Shutdown::new();
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .shutdown(shutdown.clone())
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    assert_eq!(shutdown.connections(), 0);
+
+    let mut first = get(addr, "/").await;
+    let mut second = get(addr, "/").await;
+    read_headers(&mut first).await;
+    read_headers(&mut second).await;
+    assert_eq!(shutdown.connections(), 2);
+    assert!(!shutdown.is_draining());
+
+    drop(first);
+    wait_for_connections(&shutdown, 1).await;
+    drop(second);
+    wait_for_connections(&shutdown, 0).await;
+}
+
+#[tokio::test]
+async fn drain_timeout() {
+    let routes = warp::any().map(|| warp::sse::reply(endless()));
+    let shutdown = Shutdown::new();
+    let (tx, rx) = oneshot::channel::<()>();
+    let (addr, server) = warp::serve(routes)
+        .shutdown(shutdown.clone())
+        .drain_timeout(Duration::from_millis(100))
+        .bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+            rx.await.ok();
+        });
+    let server = tokio::spawn(server);
+
+    let mut stream = get(addr, "/").await;
+    read_headers(&mut stream).await;
+    assert_eq!(shutdown.connections(), 1);
+
+    tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("drain timeout didn't close the connection")
+        .unwrap();
+    assert!(shutdown.is_draining());
+    wait_for_connections(&shutdown, 0).await;
+
+    // The connection was closed mid-response.
+    let mut rest = Vec::new();
+    let _ = stream.read_to_end(&mut rest).await;
+    assert!(!String::from_utf8_lossy(&rest).ends_with("0\r\n\r\n"));
+}
+
+#[tokio::test]
+async fn draining() {
+    let routes = warp::shutdown::draining()
+        .map(|draining: Draining| warp::sse::reply(endless().take_until(draining)));
+    let (tx, rx) = oneshot::channel::<()>();
+    let (addr, server) =
+        warp::serve(routes).bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+            rx.await.ok();
+        });
+    let server = tokio::spawn(server);
+
+    let mut stream = get(addr, "/").await;
+    read_headers(&mut stream).await;
+
+    tx.send(()).unwrap();
+    let mut rest = Vec::new();
+    stream.read_to_end(&mut rest).await.unwrap();
+    // The stream finished, so the response did too.
+    assert!(String::from_utf8_lossy(&rest).ends_with("0\r\n\r\n"));
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn shared() {
+    let shutdown = Shutdown::new();
+    let (tx, rx) = oneshot::channel::<()>();
+    let (_, first) = warp::serve(warp::any().map(warp::reply))
+        .shutdown(shutdown.clone())
+        .bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+            rx.await.ok();
+        });
+    let (_, second) = warp::serve(warp::any().map(warp::reply))
+        .shutdown(shutdown.clone())
+        .bind_with_graceful_shutdown(([127, 0, 0, 1], 0), futures::future::pending());
+    let first = tokio::spawn(first);
+    let second = tokio::spawn(second);
+
+    tx.send(()).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        first.await.unwrap();
+        second.await.unwrap();
+    })
+    .await
+    .unwrap();
+}
+
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn websocket() {
+    use futures::SinkExt;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let routes = warp::ws().map(|ws: warp::ws::Ws| {
+        ws.on_upgrade(|websocket| async {
+            // Echo until the client closes.
+            let (tx, rx) = websocket.split();
+            let _ = rx.forward(tx).await;
+        })
+    });
+    let shutdown = Shutdown::new();
+    let (tx, rx) = oneshot::channel::<()>();
+    let (addr, server) = warp::serve(routes)
+        .shutdown(shutdown.clone())
+        .bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+            rx.await.ok();
+        });
+    let server = tokio::spawn(server);
+
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let (mut client, _) = tokio_tungstenite::client_async("ws://localhost/", tcp)
+        .await
+        .unwrap();
+    // Make sure the upgrade is done.
+    client.send(Message::text("hi")).await.unwrap();
+    assert_eq!(client.next().await.unwrap().unwrap(), Message::text("hi"));
+    wait_for_connections(&shutdown, 1).await;
+
+    tx.send(()).unwrap();
+    match client.next().await.unwrap().unwrap() {
+        Message::Close(Some(frame)) => assert_eq!(frame.code, CloseCode::Away),
+        message => panic!("unexpected message {:?}", message),
+    }
+    while let Some(Ok(_)) = client.next().await {}
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .unwrap()
+        .unwrap();
+    wait_for_connections(&shutdown, 0).await;
+}
+
+// A handler that never reads still gets the close frame sent, and is closed
+// by the drain timeout though hyper is done with the connection.
+#[cfg(feature = "websocket")]
+#[tokio::test]
+async fn websocket_send_only() {
+    use futures::SinkExt;
+    use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let routes = warp::ws().map(|ws: warp::ws::Ws| {
+        ws.on_upgrade(|mut websocket| async move {
+            // Ignores errors, so only the drain timeout stops it.
+            loop {
+                let _ = websocket.send(warp::ws::Message::text("tick")).await;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+    });
+    let shutdown = Shutdown::new();
+    let (tx, rx) = oneshot::channel::<()>();
+    let (addr, server) = warp::serve(routes)
+        .shutdown(shutdown.clone())
+        .drain_timeout(Duration::from_millis(200))
+        .bind_with_graceful_shutdown(([127, 0, 0, 1], 0), async {
+            rx.await.ok();
+        });
+    let server = tokio::spawn(server);
+
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let (mut client, _) = tokio_tungstenite::client_async("ws://localhost/", tcp)
+        .await
+        .unwrap();
+    assert_eq!(client.next().await.unwrap().unwrap(), Message::text("tick"));
+    wait_for_connections(&shutdown, 1).await;
+
+    tx.send(()).unwrap();
+    let started = std::time::Instant::now();
+    loop {
+        match client.next().await.unwrap().unwrap() {
+            Message::Text(_) => {}
+            Message::Close(Some(frame)) => {
+                assert_eq!(frame.code, CloseCode::Away);
+                break;
+            }
+            message => panic!("unexpected message {:?}", message),
+        }
+    }
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("drain timeout didn't close the websocket")
+        .unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(shutdown.connections(), 0);
+}
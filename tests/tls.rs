@@ -0,0 +1,295 @@
+#![deny(warnings)]
+#![cfg(feature = "tls")]
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::internal::pemfile;
+use tokio_rustls::rustls::{ClientConfig, ProtocolVersion, Session};
+use tokio_rustls::webpki::DNSNameRef;
+use tokio_rustls::TlsConnector;
+use warp::tls::{PeerCertificates, SniFallback, SubjectAltName, TlsInfo};
+use warp::Filter;
+
+fn serve() -> SocketAddr {
+    let routes = warp::tls::info().and(warp::tls::peer_certificates()).map(
+        |info: Option<TlsInfo>, peer: Option<PeerCertificates>| {
+            let info = info.unwrap();
+            let peer = peer.unwrap();
+            assert_eq!(
+                peer.subject_alt_names(),
+                [
+                    SubjectAltName::Dns("client.example.com".into()),
+                    SubjectAltName::Uri("spiffe://example.com/client".into()),
+                    SubjectAltName::Ip([10, 0, 0, 1].into()),
+                ]
+            );
+            format!(
+                "{} {} {} {} {}",
+                info.version().unwrap(),
+                String::from_utf8_lossy(info.alpn_protocol().unwrap()),
+                info.server_name().unwrap(),
+                peer.subject().unwrap(),
+                peer.chain().len(),
+            )
+        },
+    );
+    let (addr, server) = warp::serve(routes)
+        .tls()
+        .cert_path("tests/tls/server.pem")
+        .key_path("tests/tls/server.key")
+        .client_auth_required_path("tests/tls/ca.pem")
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+fn client() -> TlsConnector {
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_pem_file(&mut &include_bytes!("tls/ca.pem")[..])
+        .unwrap();
+    let certs = pemfile::certs(&mut &include_bytes!("tls/client.pem")[..]).unwrap();
+    let key = pemfile::pkcs8_private_keys(&mut &include_bytes!("tls/client.key")[..])
+        .unwrap()
+        .remove(0);
+    config.set_single_client_cert(certs, key).unwrap();
+    config.set_protocols(&[b"http/1.1".to_vec()]);
+    TlsConnector::from(Arc::new(config))
+}
+
+#[tokio::test]
+async fn client_certificate() {
+    let _ = pretty_env_logger::try_init();
+    let addr = serve();
+
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let name = DNSNameRef::try_from_ascii_str("localhost").unwrap();
+    let mut stream = client().connect(name, tcp).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(
+        response.ends_with("TLSv1.3 http/1.1 localhost CN=client,O=Example 1"),
+        "{}",
+        response
+    );
+}
+
+#[tokio::test]
+async fn plain_requests() {
+    let route = warp::tls::info().map(|info: Option<TlsInfo>| info.is_none().to_string());
+    let res = warp::test::request().reply(&route).await;
+    assert_eq!(res.body(), "true");
+}
+
+// The DER of the certificate the server presented.
+async fn server_certificate(addr: SocketAddr) -> Vec<u8> {
+    certificate_for(addr, "localhost", true).await.unwrap()
+}
+
+// The DER of the certificate presented for `name`, if the handshake worked.
+async fn certificate_for(addr: SocketAddr, name: &str, sni: bool) -> Option<Vec<u8>> {
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_pem_file(&mut &include_bytes!("tls/ca.pem")[..])
+        .unwrap();
+    config.enable_sni = sni;
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let name = DNSNameRef::try_from_ascii_str(name).unwrap();
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(name, tcp)
+        .await
+        .ok()?;
+    let certs = stream.get_ref().1.get_peer_certificates().unwrap();
+    Some(certs[0].0.clone())
+}
+
+fn der(pem: &[u8]) -> Vec<u8> {
+    pemfile::certs(&mut &pem[..]).unwrap().remove(0).0
+}
+
+#[tokio::test]
+async fn reload() {
+    let dir = std::env::temp_dir().join(format!("warp-tls-reload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let cert = dir.join("cert.pem");
+    std::fs::copy("tests/tls/server.pem", &cert).unwrap();
+
+    let reloader = warp::tls::Reloader::new();
+    let (addr, server) = warp::serve(warp::any().map(warp::reply))
+        .tls()
+        .cert_path(&cert)
+        .key_path("tests/tls/server.key")
+        .reloader(reloader.clone())
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let other_cert = dir.join("other.pem");
+    std::fs::copy("tests/tls/server.pem", &other_cert).unwrap();
+    let (other, server) = warp::serve(warp::any().map(warp::reply))
+        .tls()
+        .cert_path(&other_cert)
+        .key_path("tests/tls/server.key")
+        .reloader(reloader.clone())
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let first = der(include_bytes!("tls/server.pem"));
+    let second = der(include_bytes!("tls/server2.pem"));
+    assert_eq!(server_certificate(addr).await, first);
+
+    // Nothing changes until asked to.
+    std::fs::copy("tests/tls/server2.pem", &cert).unwrap();
+    assert_eq!(server_certificate(addr).await, first);
+
+    reloader.reload().unwrap();
+    assert_eq!(server_certificate(addr).await, second);
+
+    // A broken certificate is refused, and the last good one kept.
+    std::fs::write(&cert, "not a certificate").unwrap();
+    assert!(reloader.reload().is_err());
+    assert_eq!(server_certificate(addr).await, second);
+
+    // Other servers are reloaded all the same.
+    assert_eq!(server_certificate(other).await, first);
+    std::fs::copy("tests/tls/server2.pem", &other_cert).unwrap();
+    assert!(reloader.reload().is_err());
+    assert_eq!(server_certificate(addr).await, second);
+    assert_eq!(server_certificate(other).await, second);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn serve_sni(fallback: SniFallback) -> SocketAddr {
+    let routes = warp::tls::info()
+        .map(|info: Option<TlsInfo>| info.unwrap().server_name().unwrap_or("-").to_owned());
+    let (addr, server) = warp::serve(routes)
+        .tls()
+        .cert_path("tests/tls/server.pem")
+        .key_path("tests/tls/server.key")
+        .sni_cert_path("Alpha.Test", "tests/tls/alpha.pem", "tests/tls/server.key")
+        .sni_cert(
+            "*.beta.test",
+            include_bytes!("tls/wildcard.pem"),
+            include_bytes!("tls/server.key"),
+        )
+        .sni_fallback(fallback)
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+#[tokio::test]
+async fn sni() {
+    let addr = serve_sni(SniFallback::Default);
+
+    let default = der(include_bytes!("tls/server.pem"));
+    let alpha = der(include_bytes!("tls/alpha.pem"));
+    let wildcard = der(include_bytes!("tls/wildcard.pem"));
+    assert_eq!(certificate_for(addr, "alpha.test", true).await, Some(alpha));
+    assert_eq!(
+        certificate_for(addr, "www.beta.test", true).await,
+        Some(wildcard)
+    );
+    assert_eq!(
+        certificate_for(addr, "localhost", true).await,
+        Some(default.clone())
+    );
+    // The default certificate isn't valid for these, so the client gives up,
+    // but only after the server picked it.
+    assert_eq!(certificate_for(addr, "beta.test", true).await, None);
+    assert_eq!(certificate_for(addr, "a.www.beta.test", true).await, None);
+
+    // Filters see the name that was asked for.
+    let tcp = TcpStream::connect(addr).await.unwrap();
+    let name = DNSNameRef::try_from_ascii_str("www.beta.test").unwrap();
+    let mut config = ClientConfig::new();
+    config
+        .root_store
+        .add_pem_file(&mut &include_bytes!("tls/ca.pem")[..])
+        .unwrap();
+    config.set_protocols(&[b"http/1.1".to_vec()]);
+    let mut stream = TlsConnector::from(Arc::new(config))
+        .connect(name, tcp)
+        .await
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: www.beta.test\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    assert!(response.ends_with("\r\n\r\nwww.beta.test"), "{}", response);
+}
+
+#[tokio::test]
+async fn sni_fallback() {
+    let default = der(include_bytes!("tls/server.pem"));
+
+    let addr = serve_sni(SniFallback::RejectUnknown);
+    assert_eq!(certificate_for(addr, "localhost", true).await, None);
+    assert_eq!(
+        certificate_for(addr, "localhost", false).await,
+        Some(default)
+    );
+    assert!(certificate_for(addr, "alpha.test", true).await.is_some());
+
+    let addr = serve_sni(SniFallback::Reject);
+    assert_eq!(certificate_for(addr, "localhost", false).await, None);
+    assert!(certificate_for(addr, "alpha.test", true).await.is_some());
+}
+
+#[tokio::test]
+async fn options() {
+    let routes = warp::tls::info().map(|info: Option<TlsInfo>| {
+        let info = info.unwrap();
+        format!(
+            "{} {}",
+            info.version().unwrap(),
+            String::from_utf8_lossy(info.alpn_protocol().unwrap())
+        )
+    });
+    let (addr, server) = warp::serve(routes)
+        .tls()
+        .cert_path("tests/tls/ec.pem")
+        .key_path("tests/tls/ec.key")
+        .protocol_versions(&[ProtocolVersion::TLSv1_3])
+        .alpn_protocols(&["http/1.1"])
+        .bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+
+    let connect = |versions: Vec<ProtocolVersion>| async move {
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_pem_file(&mut &include_bytes!("tls/ca.pem")[..])
+            .unwrap();
+        config.versions = versions;
+        config.set_protocols(&[b"h2".to_vec(), b"http/1.1".to_vec()]);
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let name = DNSNameRef::try_from_ascii_str("localhost").unwrap();
+        TlsConnector::from(Arc::new(config))
+            .connect(name, tcp)
+            .await
+    };
+
+    assert!(connect(vec![ProtocolVersion::TLSv1_2]).await.is_err());
+
+    let mut stream = connect(vec![ProtocolVersion::TLSv1_3]).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    assert!(response.ends_with("TLSv1.3 http/1.1"), "{}", response);
+}
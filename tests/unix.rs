@@ -0,0 +1,132 @@
+#![deny(warnings)]
+#![cfg(unix)]
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use warp::addr::Peer;
+use warp::{Filter, UnixSocket};
+
+fn socket_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("warp-unix-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+async fn get(path: &Path) -> String {
+    let mut stream = UnixStream::connect(path).await.unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response).await;
+    response
+}
+
+#[tokio::test]
+async fn peer_credentials() {
+    let _ = pretty_env_logger::try_init();
+    let path = socket_path("peer.sock");
+
+    let routes = warp::addr::peer()
+        .and(warp::addr::remote())
+        .map(|peer: Option<Peer>, remote: Option<std::net::SocketAddr>| {
+            assert_eq!(remote, None);
+            match peer.unwrap() {
+                Peer::Unix { path, credentials } => {
+                    assert_eq!(path, None);
+                    let credentials = credentials.unwrap();
+                    format!(
+                        "{} {} {:?}",
+                        credentials.uid(),
+                        credentials.gid(),
+                        credentials.pid()
+                    )
+                }
+                peer => panic!("unexpected peer {:?}", peer),
+            }
+        })
+        .with(warp::log("unix"));
+    tokio::spawn(warp::serve(routes).bind_unix(path.as_path()));
+
+    let dir = std::fs::metadata(path.parent().unwrap()).unwrap();
+    let res = get(&path).await;
+    assert!(res.starts_with("HTTP/1.1 200 OK"), "{}", res);
+    assert!(
+        res.ends_with(&format!(
+            "{} {} Some({})",
+            dir.uid(),
+            dir.gid(),
+            std::process::id()
+        )),
+        "{}",
+        res
+    );
+}
+
+#[tokio::test]
+async fn stale_socket() {
+    let path = socket_path("stale.sock");
+    // Binding leaves the file behind once the listener is gone.
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let server = warp::serve(warp::any().map(warp::reply))
+        .try_bind_unix(path.as_path())
+        .unwrap();
+    tokio::spawn(server);
+    assert!(get(&path).await.starts_with("HTTP/1.1 200 OK"));
+
+    // Another server listening there isn't disturbed.
+    let in_use = warp::serve(warp::any().map(warp::reply)).try_bind_unix(path.as_path());
+    assert!(in_use.is_err());
+    assert!(get(&path).await.starts_with("HTTP/1.1 200 OK"));
+}
+
+#[tokio::test]
+async fn not_a_socket() {
+    let path = socket_path("file.sock");
+    std::fs::write(&path, "keep me").unwrap();
+
+    let result = warp::serve(warp::any().map(warp::reply)).try_bind_unix(path.as_path());
+    assert!(result.is_err());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+}
+
+#[tokio::test]
+async fn mode_and_cleanup() {
+    let path = socket_path("mode.sock");
+
+    let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+    let server = warp::serve(warp::any().map(warp::reply))
+        .try_bind_unix(UnixSocket::new(&path).mode(0o600))
+        .unwrap();
+    let server = tokio::spawn(async move {
+        tokio::select! {
+            _ = server => {}
+            _ = rx => {}
+        }
+    });
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let response = get(&path).await;
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    // The private directory it was bound in is gone.
+    let private = path.with_file_name(format!(".mode.sock.{}", std::process::id()));
+    assert!(!private.exists());
+
+    // Another server can't take the path while it's in use.
+    let result = warp::serve(warp::any().map(warp::reply))
+        .try_bind_unix(UnixSocket::new(&path).mode(0o600).remove_stale(false));
+    assert!(result.is_err());
+    assert!(!private.exists());
+
+    tx.send(()).unwrap();
+    server.await.unwrap();
+    assert!(!path.exists());
+}
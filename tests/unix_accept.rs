@@ -0,0 +1,60 @@
+#![deny(warnings)]
+#![cfg(target_os = "linux")]
+// Lowers the process' file descriptor limit, so this is alone in its binary.
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use warp::Filter;
+
+fn set_fd_limit(limit: libc::rlim_t) -> libc::rlim_t {
+    unsafe {
+        let mut rlimit = std::mem::zeroed::<libc::rlimit>();
+        assert_eq!(libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlimit), 0);
+        let old = rlimit.rlim_cur;
+        rlimit.rlim_cur = limit;
+        assert_eq!(libc::setrlimit(libc::RLIMIT_NOFILE, &rlimit), 0);
+        old
+    }
+}
+
+#[tokio::test]
+async fn survives_running_out_of_fds() {
+    let _ = pretty_env_logger::try_init();
+    let dir = std::env::temp_dir().join(format!("warp-unix-accept-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("fds.sock");
+    let _ = std::fs::remove_file(&path);
+
+    let server = warp::serve(warp::any().map(|| "served"))
+        .try_bind_unix(path.as_path())
+        .unwrap();
+    let server = tokio::spawn(server);
+
+    // Connecting doesn't wait for the server to accept, and this runtime
+    // only polls the server once the test yields, when no fd is left.
+    let stream = std::os::unix::net::UnixStream::connect(&path).unwrap();
+    let lowest_free = unsafe { libc::dup(stream.as_raw_fd()) };
+    unsafe { libc::close(lowest_free) };
+    let old = set_fd_limit(lowest_free as libc::rlim_t);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    set_fd_limit(old);
+    assert!(!server.is_finished(), "server stopped on EMFILE");
+
+    stream.set_nonblocking(true).unwrap();
+    let mut stream = UnixStream::from_std(stream).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    tokio::time::timeout(Duration::from_secs(5), stream.read_to_string(&mut response))
+        .await
+        .expect("response after backing off")
+        .unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.ends_with("served"), "{}", response);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}